@@ -0,0 +1,545 @@
+//! Checking the layout of a field.
+//!
+//! Panels are treated as a graph, where exits lead from one panel to
+//! another, the same way [`path`](crate::path) follows them. Empty panels are
+//! not part of the graph, and backtrack exits are not followed.
+
+use crate::{Direction, Field, PanelKind, Pos};
+use crate::path::{successors, shortest_path, distances};
+
+use std::collections::HashMap;
+
+/// Counts of what a field is made of, from [`Field::stats`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldStats {
+    pub width: usize,
+    pub height: usize,
+    /// How many panels there are of each kind, not counting empty panels.
+    pub kinds: HashMap<PanelKind, usize>,
+    /// How many non-empty panels there are.
+    pub panels: usize,
+    /// How many exits lead in each direction.
+    pub exits: HashMap<Direction, usize>,
+    /// How many panels the exits of a non-empty panel lead to, on average.
+    pub branching: f64,
+}
+
+impl Field {
+    /// Counts what the field is made of.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, Direction, PanelKind::*};
+    ///
+    /// let stats = field![
+    ///     [Home + EAST + SOUTH, Draw + SOUTH],
+    ///     [Draw + EAST, Empty],
+    /// ].stats();
+    ///
+    /// assert_eq!(stats.panels, 3);
+    /// assert_eq!(stats.kinds[&Draw], 2);
+    /// assert_eq!(stats.exits[&Direction::South], 2);
+    /// assert_eq!(stats.branching, 2.0 / 3.0);
+    /// ```
+    pub fn stats(&self) -> FieldStats {
+        let mut stats = FieldStats {
+            width: self.width(),
+            height: self.height(),
+            ..FieldStats::default()
+        };
+
+        let mut edges = 0;
+
+        for pos in self.positions() {
+            let panel = &self[pos];
+
+            if panel.kind == PanelKind::Empty {
+                continue;
+            }
+
+            *stats.kinds.entry(panel.kind).or_insert(0) += 1;
+            stats.panels += 1;
+            edges += successors(self, pos).count();
+
+            for dir in panel.exits.directions() {
+                *stats.exits.entry(dir).or_insert(0) += 1;
+            }
+        }
+
+        if stats.panels > 0 {
+            stats.branching = edges as f64 / stats.panels as f64;
+        }
+
+        stats
+    }
+}
+
+/// How panels in a [`components`] group must be connected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Panels are grouped if exits connect them in either direction.
+    ///
+    /// A field made of more than one of these is split into islands.
+    #[default]
+    Weak,
+    /// Panels are grouped if each can be reached from the other.
+    ///
+    /// A player who leaves one of these groups can never come back to it.
+    Strong,
+}
+
+/// Groups the non-empty panels of a field into connected components.
+///
+/// Each component is in row-major order, and components are ordered by their
+/// first panel.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, Pos, PanelKind::*};
+/// use citrus_common::analysis::{self, Connectivity};
+///
+/// let field = field![
+///     [Home + EAST, Draw, Empty, Bonus],
+/// ];
+///
+/// let islands = analysis::components(&field, Connectivity::Weak);
+/// assert_eq!(islands, vec![
+///     vec![Pos::new(0, 0), Pos::new(1, 0)],
+///     vec![Pos::new(3, 0)],
+/// ]);
+///
+/// // the exit between the first two panels only leads one way
+/// assert_eq!(analysis::components(&field, Connectivity::Strong).len(), 3);
+/// ```
+pub fn components(field: &Field, connectivity: Connectivity) -> Vec<Vec<Pos>> {
+    let graph = Graph::new(field);
+
+    let labels = match connectivity {
+        Connectivity::Weak => graph.weak_labels(),
+        Connectivity::Strong => graph.strong_labels(),
+    };
+
+    // renumber the components in the order their first panel appears
+    let mut order = HashMap::new();
+    let mut components: Vec<Vec<Pos>> = Vec::new();
+
+    for (pos, label) in graph.nodes.iter().zip(labels) {
+        let idx = *order.entry(label).or_insert_with(|| {
+            components.push(Vec::new());
+            components.len() - 1
+        });
+
+        components[idx].push(*pos);
+    }
+
+    components
+}
+
+/// The loops on a field, and how long a lap from each Home is.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LapReport {
+    /// The loops on the field, as strongly connected components with more
+    /// than one panel.
+    ///
+    /// Every panel in a loop can be reached again from itself.
+    pub loops: Vec<Vec<Pos>>,
+    /// Each Home on the field, and the fewest moves it takes to leave it and
+    /// come back, or `None` if it can never be come back to.
+    pub laps: Vec<(Pos, Option<usize>)>,
+}
+
+impl LapReport {
+    /// Checks if one loop contains every Home.
+    ///
+    /// Fields without any Homes fail this check.
+    pub fn homes_connected(&self) -> bool {
+        let mut homes = self.laps.iter().map(|(home, _)| home).peekable();
+
+        homes.peek().is_some() && self.loops.iter().any(|panels| {
+            homes.clone().all(|home| panels.contains(home))
+        })
+    }
+}
+
+/// Finds the loops on a field, and the lap length from each Home.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::analysis;
+///
+/// let field = field![
+///     [Home + EAST, Draw + SOUTH],
+///     [Bonus + NORTH, Home + WEST],
+/// ];
+///
+/// let report = analysis::laps(&field);
+///
+/// assert_eq!(report.loops.len(), 1);
+/// assert_eq!(report.laps[0].1, Some(4));
+/// assert!(report.homes_connected());
+/// ```
+pub fn laps(field: &Field) -> LapReport {
+    let loops = components(field, Connectivity::Strong)
+        .into_iter()
+        .filter(|panels| panels.len() > 1)
+        .collect();
+
+    let laps = field.positions()
+        .filter(|pos| field[*pos].kind == PanelKind::Home)
+        .map(|home| (home, lap(field, home).map(|lap| lap.len() - 1)))
+        .collect();
+
+    LapReport { loops, laps }
+}
+
+/// Finds a shortest lap from a panel: a path that leaves it and comes back,
+/// starting and ending with the panel.
+///
+/// # Panics
+/// Will panic if the position is out of bounds.
+pub fn lap(field: &Field, from: Pos) -> Option<Vec<Pos>> {
+    successors(field, from)
+        .filter_map(|next| shortest_path(field, next, from))
+        .min_by_key(|path| path.len())
+        .map(|path| {
+            let mut lap = vec![from];
+            lap.extend(path);
+            lap
+        })
+}
+
+/// How far a Home is from the panels that matter most, from [`balance`].
+///
+/// Each distance is the fewest moves it takes to reach the nearest panel of a
+/// kind, or its 2x version, or `None` if there is no such panel to reach.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HomeBalance {
+    pub home: Pos,
+    pub bonus: Option<usize>,
+    pub draw: Option<usize>,
+    pub drop: Option<usize>,
+    pub encounter: Option<usize>,
+}
+
+impl HomeBalance {
+    fn distances(&self) -> [Option<usize>; 4] {
+        [self.bonus, self.draw, self.drop, self.encounter]
+    }
+}
+
+/// How evenly a field treats each Home, from [`balance`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceReport {
+    /// The distances from each Home, in row-major order.
+    pub homes: Vec<HomeBalance>,
+    /// How alike the Homes are, from `0.0` to `1.0`, where `1.0` means every
+    /// Home is just as far from each kind of panel.
+    ///
+    /// For each kind, the difference between the nearest and farthest Home
+    /// is divided by the farthest distance, and the score is one minus the
+    /// average of these. A kind that only some Homes can reach counts as
+    /// entirely unfair.
+    pub fairness: f64,
+}
+
+/// Measures how far each Home is from the nearest Bonus, Draw, Drop and
+/// Encounter panels, following exits.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::analysis;
+///
+/// let field = field![
+///     [Home + EAST, Bonus + EAST, Home + EAST, Draw + EAST, Bonus + EAST],
+/// ];
+///
+/// let report = analysis::balance(&field);
+///
+/// assert_eq!(report.homes[0].bonus, Some(1));
+/// assert_eq!(report.homes[1].bonus, Some(2));
+/// assert_eq!(report.homes[0].draw, Some(3));
+/// assert!(report.fairness < 1.0);
+/// ```
+pub fn balance(field: &Field) -> BalanceReport {
+    use PanelKind::*;
+
+    let homes: Vec<HomeBalance> = field.positions()
+        .filter(|pos| field[*pos].kind == Home)
+        .map(|home| {
+            let distances = distances(field, home);
+            let nearest = |kind: PanelKind| distances.iter()
+                .filter(|(pos, _)| field[**pos].kind.base_kind() == kind)
+                .map(|(_, distance)| *distance)
+                .min();
+
+            HomeBalance {
+                home,
+                bonus: nearest(Bonus),
+                draw: nearest(Draw),
+                drop: nearest(Drop),
+                encounter: nearest(Encounter),
+            }
+        })
+        .collect();
+
+    let spreads = (0..4).map(|kind| {
+        let distances: Vec<Option<usize>> = homes.iter()
+            .map(|home| home.distances()[kind])
+            .collect();
+
+        if distances.iter().all(Option::is_none) {
+            0.0
+        } else if distances.iter().any(Option::is_none) {
+            1.0
+        } else {
+            let min = distances.iter().flatten().min().copied().unwrap_or(0);
+            let max = distances.iter().flatten().max().copied().unwrap_or(0);
+
+            if max == 0 { 0.0 } else { (max - min) as f64 / max as f64 }
+        }
+    });
+
+    let fairness = 1.0 - spreads.sum::<f64>() / 4.0;
+
+    BalanceReport { homes, fairness }
+}
+
+/// Why an exit is flagged by [`bad_exits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BadExitKind {
+    /// The exit leads off the field.
+    OffField,
+    /// The exit leads into an empty panel.
+    IntoEmpty,
+}
+
+/// An exit that leads nowhere, from [`bad_exits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BadExit {
+    /// The position of the panel.
+    pub pos: Pos,
+    /// The direction of the exit.
+    pub exit: Direction,
+    /// Whether the exit is a backtrack exit.
+    pub backtrack: bool,
+    /// Why the exit is flagged.
+    pub kind: BadExitKind,
+}
+
+/// Lists the non-empty panels with no exits that lead to another panel, in
+/// row-major order.
+///
+/// Players can move onto these panels, but never move off of them.
+pub fn dead_ends(field: &Field) -> Vec<Pos> {
+    field.positions()
+        .filter(|pos| field[*pos].kind != PanelKind::Empty)
+        .filter(|pos| successors(field, *pos).next().is_none())
+        .collect()
+}
+
+/// Lists the exits of non-empty panels that lead off the field or into empty
+/// panels, in row-major order.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::analysis::{self, BadExitKind};
+///
+/// let field = field![[Home + WEST + EAST, Draw + EAST, Empty]];
+/// let bad = analysis::bad_exits(&field);
+///
+/// assert_eq!(bad.len(), 2);
+/// assert_eq!(bad[0].kind, BadExitKind::OffField);
+/// assert_eq!(bad[1].kind, BadExitKind::IntoEmpty);
+/// ```
+pub fn bad_exits(field: &Field) -> Vec<BadExit> {
+    let mut bad = Vec::new();
+
+    for pos in field.positions().filter(|pos| field[*pos].kind != PanelKind::Empty) {
+        let panel = &field[pos];
+
+        for dir in Direction::ALL.iter().copied() {
+            let kind = match pos.step(dir).filter(|to| field.contains(*to)) {
+                None => BadExitKind::OffField,
+                Some(to) if field[to].kind == PanelKind::Empty => BadExitKind::IntoEmpty,
+                Some(_) => continue,
+            };
+
+            for (exits, backtrack) in [(panel.exits, false), (panel.exits_backtrack, true)].iter() {
+                if exits.has(dir.to_exits()) {
+                    bad.push(BadExit { pos, exit: dir, backtrack: *backtrack, kind });
+                }
+            }
+        }
+    }
+
+    bad
+}
+
+/// Finds the groups of panels that a player can move into, but never leave.
+///
+/// These are strongly connected components with no exits leading out of
+/// them, that can be reached from panels outside of them. A field that is
+/// one big loop has none. Dead ends that can be reached are traps of a single
+/// panel.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, Pos, PanelKind::*};
+/// use citrus_common::analysis;
+///
+/// // the Bonus and Drop lead to each other, but not back to the Home
+/// let field = field![[Home + EAST, Bonus + EAST, Drop + WEST]];
+///
+/// assert_eq!(analysis::traps(&field), vec![vec![Pos::new(1, 0), Pos::new(2, 0)]]);
+/// ```
+pub fn traps(field: &Field) -> Vec<Vec<Pos>> {
+    let islands: HashMap<Pos, usize> = components(field, Connectivity::Weak)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(idx, panels)| panels.into_iter().map(move |pos| (pos, idx)))
+        .collect();
+
+    let mut island_sizes = HashMap::new();
+
+    for idx in islands.values() {
+        *island_sizes.entry(*idx).or_insert(0) += 1;
+    }
+
+    components(field, Connectivity::Strong)
+        .into_iter()
+        .filter(|panels| {
+            let closed = panels.iter()
+                .flat_map(|pos| successors(field, *pos))
+                .all(|to| panels.contains(&to));
+
+            closed && island_sizes[&islands[&panels[0]]] > panels.len()
+        })
+        .collect()
+}
+
+/// The non-empty panels of a field, and the exits between them.
+struct Graph {
+    /// The positions of the panels, row-major.
+    nodes: Vec<Pos>,
+    /// The panels each panel's exits lead to.
+    edges: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn new(field: &Field) -> Graph {
+        let nodes: Vec<Pos> = field.positions()
+            .filter(|pos| field[*pos].kind != PanelKind::Empty)
+            .collect();
+        let index: HashMap<Pos, usize> = nodes.iter()
+            .enumerate()
+            .map(|(idx, pos)| (*pos, idx))
+            .collect();
+
+        let edges = nodes.iter()
+            .map(|pos| successors(field, *pos).map(|to| index[&to]).collect())
+            .collect();
+
+        Graph { nodes, edges }
+    }
+
+    /// Gets the panels that lead to each panel.
+    fn reversed(&self) -> Vec<Vec<usize>> {
+        let mut reversed = vec![Vec::new(); self.nodes.len()];
+
+        for (from, edges) in self.edges.iter().enumerate() {
+            for to in edges {
+                reversed[*to].push(from);
+            }
+        }
+
+        reversed
+    }
+
+    /// Labels each panel with its weakly connected component.
+    fn weak_labels(&self) -> Vec<usize> {
+        let reversed = self.reversed();
+        let mut labels = vec![usize::MAX; self.nodes.len()];
+
+        for start in 0..self.nodes.len() {
+            if labels[start] != usize::MAX {
+                continue;
+            }
+
+            labels[start] = start;
+            let mut stack = vec![start];
+
+            while let Some(node) = stack.pop() {
+                for next in self.edges[node].iter().chain(&reversed[node]) {
+                    if labels[*next] == usize::MAX {
+                        labels[*next] = start;
+                        stack.push(*next);
+                    }
+                }
+            }
+        }
+
+        labels
+    }
+
+    /// Labels each panel with its strongly connected component.
+    ///
+    /// This is Kosaraju's algorithm: panels are ordered by when a search
+    /// along exits finishes with them, then searched against their exits in
+    /// the reverse of that order.
+    fn strong_labels(&self) -> Vec<usize> {
+        let mut finished = Vec::with_capacity(self.nodes.len());
+        let mut visited = vec![false; self.nodes.len()];
+
+        for start in 0..self.nodes.len() {
+            if visited[start] {
+                continue;
+            }
+
+            visited[start] = true;
+            // each panel, and how many of its exits were searched
+            let mut stack = vec![(start, 0)];
+
+            while let Some((node, next)) = stack.last_mut() {
+                let node = *node;
+
+                if let Some(to) = self.edges[node].get(*next).copied() {
+                    *next += 1;
+
+                    if !visited[to] {
+                        visited[to] = true;
+                        stack.push((to, 0));
+                    }
+                } else {
+                    finished.push(node);
+                    stack.pop();
+                }
+            }
+        }
+
+        let reversed = self.reversed();
+        let mut labels = vec![usize::MAX; self.nodes.len()];
+
+        for start in finished.into_iter().rev() {
+            if labels[start] != usize::MAX {
+                continue;
+            }
+
+            labels[start] = start;
+            let mut stack = vec![start];
+
+            while let Some(node) = stack.pop() {
+                for next in &reversed[node] {
+                    if labels[*next] == usize::MAX {
+                        labels[*next] = start;
+                        stack.push(*next);
+                    }
+                }
+            }
+        }
+
+        labels
+    }
+}
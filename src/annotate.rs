@@ -0,0 +1,103 @@
+//! Notes attached to the panels of a field.
+//!
+//! Panels only have room for their kind and exits, so anything else tools
+//! want to remember about a panel is kept alongside the field instead. The
+//! `.fldx` format can carry annotations in its
+//! [metadata](crate::format::fldx::Metadata).
+//!
+//! # Examples
+//! ```
+//! use citrus_common::Pos;
+//! use citrus_common::annotate::FieldAnnotations;
+//!
+//! let mut annotations = FieldAnnotations::new();
+//!
+//! let warp = annotations.entry(Pos::new(2, 0));
+//! warp.warp_target = Some(Pos::new(5, 3));
+//! warp.note = Some("skips the encounter loop".to_owned());
+//!
+//! assert_eq!(annotations.get(Pos::new(2, 0)).unwrap().warp_target, Some(Pos::new(5, 3)));
+//! assert!(annotations.get(Pos::new(0, 0)).is_none());
+//! ```
+
+use crate::Pos;
+
+use std::collections::BTreeMap;
+
+/// What is noted about a single panel.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotation {
+    /// Where a Warp panel sends players.
+    pub warp_target: Option<Pos>,
+    /// A note from the field's designer.
+    pub note: Option<String>,
+    /// The name of the part of the field the panel belongs to.
+    pub region: Option<String>,
+}
+
+impl Annotation {
+    /// Checks if nothing is noted.
+    pub fn is_empty(&self) -> bool {
+        *self == Annotation::default()
+    }
+}
+
+/// Annotations for the panels of a field, by position.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldAnnotations {
+    /// Annotations by `(y, x)`, so they are ordered row-major.
+    annotations: BTreeMap<(usize, usize), Annotation>,
+}
+
+impl FieldAnnotations {
+    /// Creates an empty set of annotations.
+    pub fn new() -> FieldAnnotations {
+        FieldAnnotations::default()
+    }
+
+    /// Gets the annotation of a panel.
+    pub fn get(&self, pos: Pos) -> Option<&Annotation> {
+        self.annotations.get(&(pos.y, pos.x))
+    }
+
+    /// Gets the annotation of a panel mutably.
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut Annotation> {
+        self.annotations.get_mut(&(pos.y, pos.x))
+    }
+
+    /// Gets the annotation of a panel, creating an empty one if there is
+    /// none.
+    pub fn entry(&mut self, pos: Pos) -> &mut Annotation {
+        self.annotations.entry((pos.y, pos.x)).or_default()
+    }
+
+    /// Sets the annotation of a panel, returning the old one.
+    pub fn insert(&mut self, pos: Pos, annotation: Annotation) -> Option<Annotation> {
+        self.annotations.insert((pos.y, pos.x), annotation)
+    }
+
+    /// Removes the annotation of a panel, returning it.
+    pub fn remove(&mut self, pos: Pos) -> Option<Annotation> {
+        self.annotations.remove(&(pos.y, pos.x))
+    }
+
+    /// Gets how many panels are annotated.
+    pub fn len(&self) -> usize {
+        self.annotations.len()
+    }
+
+    /// Checks if no panels are annotated.
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+
+    /// Removes empty annotations.
+    pub fn prune(&mut self) {
+        self.annotations.retain(|_, annotation| !annotation.is_empty());
+    }
+
+    /// Gets an iterator over the annotated panels, row-major.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (Pos, &Annotation)> + ExactSizeIterator {
+        self.annotations.iter().map(|((y, x), annotation)| (Pos::new(*x, *y), annotation))
+    }
+}
@@ -0,0 +1,257 @@
+//! Comparing versions of a field.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, Pos, PanelKind::*};
+//! use citrus_common::diff::Change;
+//!
+//! let old = field![[Home + EAST, Draw]];
+//! let new = field![[Home + EAST, Bonus]];
+//!
+//! let changes = old.diff(&new);
+//!
+//! assert_eq!(changes.len(), 1);
+//! assert!(matches!(changes[0], Change::Panel { pos, .. } if pos == Pos::new(1, 0)));
+//! ```
+
+use crate::{Direction, Field, Panel, PanelKind, Pos};
+use crate::pos::Rect;
+use crate::transform::Anchor;
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// A difference between two fields, from [`Field::diff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The field changed size.
+    ///
+    /// The top-left corner of the field stays in place, like
+    /// [`Field::resize`] with [`Anchor::TopLeft`].
+    Resized {
+        old: (usize, usize),
+        new: (usize, usize),
+    },
+    /// A panel changed kind or exits.
+    Panel {
+        pos: Pos,
+        old: Panel,
+        new: Panel,
+    },
+}
+
+/// An error applying changes with [`Field::apply`].
+///
+/// Each error carries the index of the change that could not be applied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PatchError {
+    /// A panel change is for a position off the field.
+    OutOfBounds {
+        index: usize,
+        pos: Pos,
+    },
+    /// A resize expected the field to be a different size.
+    SizeMismatch {
+        index: usize,
+        expected: (usize, usize),
+        got: (usize, usize),
+    },
+    /// A panel change expected the panel to be different, so the field was
+    /// changed since the changes were made.
+    Conflict {
+        index: usize,
+        pos: Pos,
+        expected: Panel,
+        got: Panel,
+    },
+}
+
+impl PatchError {
+    /// Gets the index of the change that could not be applied.
+    pub fn index(&self) -> usize {
+        match self {
+            PatchError::OutOfBounds { index, .. }
+            | PatchError::SizeMismatch { index, .. }
+            | PatchError::Conflict { index, .. } => *index,
+        }
+    }
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            PatchError::OutOfBounds { index, pos } => {
+                write!(f, "change {} is for {}, which is out of bounds", index, pos)
+            },
+            PatchError::SizeMismatch { index, expected, got } => write!(
+                f, "change {} expected a {}x{} field, got a {}x{} field",
+                index, expected.0, expected.1, got.0, got.1
+            ),
+            PatchError::Conflict { index, pos, .. } => {
+                write!(f, "change {} conflicts with the panel at {}", index, pos)
+            },
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl Field {
+    /// Lists the differences between this field and another.
+    ///
+    /// If the fields are different sizes, the first change resizes the
+    /// field, and the panel changes after it compare against this field
+    /// resized from the top-left corner. Panel changes are in row-major
+    /// order.
+    pub fn diff(&self, other: &Field) -> Vec<Change> {
+        let (old, new) = ((self.width(), self.height()), (other.width(), other.height()));
+        let mut changes = Vec::new();
+
+        let resized;
+        let base = if old == new {
+            self
+        } else {
+            changes.push(Change::Resized { old, new });
+            resized = self.resize(new.0, new.1, Anchor::TopLeft);
+            &resized
+        };
+
+        changes.extend(base.positions()
+            .filter(|pos| base[*pos] != other[*pos])
+            .map(|pos| Change::Panel {
+                pos,
+                old: base[pos].clone(),
+                new: other[pos].clone(),
+            }));
+
+        changes
+    }
+
+    /// Applies changes made by [`Field::diff`].
+    ///
+    /// Every change is checked against the field before it is applied: each
+    /// resize must start from the field's size, and each panel change from
+    /// the panel that is there. If any change fails, the field is left as it
+    /// was.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    ///
+    /// let old = field![[Home + EAST, Draw]];
+    /// let new = field![[Home + EAST, Bonus, Drop]];
+    ///
+    /// let mut patched = old.clone();
+    /// patched.apply(&old.diff(&new)).unwrap();
+    /// assert_eq!(patched, new);
+    ///
+    /// // the changes were already applied
+    /// assert!(patched.apply(&old.diff(&new)).is_err());
+    /// ```
+    pub fn apply(&mut self, changes: &[Change]) -> Result<(), PatchError> {
+        let mut field = self.clone();
+
+        for (index, change) in changes.iter().enumerate() {
+            match change {
+                Change::Resized { old, new } => {
+                    let got = (field.width(), field.height());
+
+                    if got != *old {
+                        return Err(PatchError::SizeMismatch { index, expected: *old, got });
+                    }
+
+                    field = field.resize(new.0, new.1, Anchor::TopLeft);
+                },
+                Change::Panel { pos, old, new } => {
+                    if !field.contains(*pos) {
+                        return Err(PatchError::OutOfBounds { index, pos: *pos });
+                    }
+
+                    if field[*pos] != *old {
+                        return Err(PatchError::Conflict {
+                            index,
+                            pos: *pos,
+                            expected: old.clone(),
+                            got: field[*pos].clone(),
+                        });
+                    }
+
+                    field[*pos] = new.clone();
+                },
+            }
+        }
+
+        *self = field;
+        Ok(())
+    }
+}
+
+/// Measures how alike two fields are, from `0.0` to `1.0`, where `1.0`
+/// means they are the same.
+///
+/// Empty space around the fields is ignored, as is their orientation: the
+/// fields are compared with their top-left corners lined up, in whichever of
+/// the eight ways to rotate and mirror `b` matches best. The score is the
+/// average of how many panels have the same kind, out of the positions where
+/// either field has a panel, and how many exits they share, out of all of
+/// their exits. Backtrack exits are ignored.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::diff::similarity;
+///
+/// let a = field![[Home + EAST, Draw + EAST, Bonus]];
+/// let b = field![[Bonus, Draw + WEST, Home + WEST]];
+/// let c = field![[Home + EAST, Draw + EAST, Drop]];
+///
+/// assert_eq!(similarity(&a, &b), 1.0);
+/// assert!(similarity(&a, &c) > 0.8);
+/// assert!(similarity(&a, &c) < 1.0);
+/// ```
+pub fn similarity(a: &Field, b: &Field) -> f32 {
+    let (a, _) = a.trim();
+    let (b, _) = b.trim();
+
+    let turns = [b.clone(), b.rotate_cw(), b.rotate_180(), b.rotate_ccw()];
+
+    turns.iter()
+        .flat_map(|turn| vec![turn.flip_horizontal(), turn.clone()])
+        .map(|b| aligned_similarity(&a, &b))
+        .fold(0.0, f32::max)
+}
+
+/// Measures how alike two fields are, with their top-left corners lined up.
+fn aligned_similarity(a: &Field, b: &Field) -> f32 {
+    let width = a.width().max(b.width());
+    let height = a.height().max(b.height());
+
+    let panel = |field: &Field, pos: Pos| {
+        if field.contains(pos) { field[pos].clone() } else { Panel::EMPTY }
+    };
+
+    let (mut filled, mut same_kind) = (0, 0);
+    let (mut exits, mut same_exits) = (0, 0);
+
+    for pos in Rect::of_size(width, height).positions() {
+        let (a, b) = (panel(a, pos), panel(b, pos));
+
+        if a.kind != PanelKind::Empty || b.kind != PanelKind::Empty {
+            filled += 1;
+            same_kind += (a.kind == b.kind) as usize;
+        }
+
+        for dir in Direction::ALL.iter() {
+            let (in_a, in_b) = (a.exits.has(dir.to_exits()), b.exits.has(dir.to_exits()));
+
+            exits += (in_a || in_b) as usize;
+            same_exits += (in_a && in_b) as usize;
+        }
+    }
+
+    let ratio = |same: usize, total: usize| {
+        if total == 0 { 1.0 } else { same as f32 / total as f32 }
+    };
+
+    (ratio(same_kind, filled) + ratio(same_exits, exits)) / 2.0
+}
@@ -0,0 +1,361 @@
+//! Drawing shapes, paths and exits on fields.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{Field, Pos, PanelKind::*};
+//! use citrus_common::pos::Rect;
+//!
+//! let mut field = Field::builder(3, 3).build();
+//!
+//! field.draw_ring(Rect::new(0, 0, 3, 3), Neutral);
+//! field.draw_line(Pos::new(1, 0), Pos::new(1, 2), Bonus);
+//!
+//! assert_eq!(field.get((1, 1)).kind, Bonus);
+//! assert_eq!(field.get((0, 1)).kind, Neutral);
+//! ```
+
+use crate::{Direction, Field, PanelKind, Pos};
+use crate::pos::Rect;
+
+/// Which exits [`Field::connect_adjacent`] creates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConnectMode {
+    /// Adjacent panels lead to each other, both ways.
+    #[default]
+    Bidirectional,
+    /// Adjacent panels are connected one way only, from each panel to the
+    /// panels east and south of it.
+    ///
+    /// This is meant as a start for one-way loops: reversing the exits on
+    /// part of the field is easier than adding them all by hand.
+    OneWay,
+}
+
+/// An exit added or removed by [`Field::normalize_exits`] or
+/// [`Field::sanitize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExitChange {
+    /// The position of the panel.
+    pub pos: Pos,
+    /// The direction of the exit.
+    pub exit: Direction,
+    /// Whether the exit is a backtrack exit.
+    pub backtrack: bool,
+}
+
+/// What [`Field::normalize_exits`] changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NormalizeReport {
+    /// Exits that led off the field, into empty panels, or out of empty
+    /// panels.
+    pub removed: Vec<ExitChange>,
+    /// Exits added to make one-way connections two-way.
+    pub mirrored: Vec<ExitChange>,
+}
+
+impl NormalizeReport {
+    /// Checks if nothing was changed.
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.mirrored.is_empty()
+    }
+}
+
+impl Field {
+    /// Sets the kind of the panels along a line, returning their positions
+    /// in order from `from` to `to`.
+    ///
+    /// The line is made of orthogonal steps, so each panel on it is next to
+    /// the one before it, like panels on a path.
+    ///
+    /// # Panics
+    /// Will panic if either end of the line is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{Field, Pos, PanelKind::*};
+    ///
+    /// let mut field = Field::builder(3, 2).build();
+    /// let line = field.draw_line(Pos::new(0, 0), Pos::new(2, 1), Neutral);
+    ///
+    /// assert_eq!(line.len(), 4);
+    /// assert_eq!(line.last(), Some(&Pos::new(2, 1)));
+    /// ```
+    pub fn draw_line(&mut self, from: Pos, to: Pos, kind: PanelKind) -> Vec<Pos> {
+        assert!(self.contains(from), "line start {} is out of bounds", from);
+        assert!(self.contains(to), "line end {} is out of bounds", to);
+
+        let (dx, dy) = (from.x.abs_diff(to.x), from.y.abs_diff(to.y));
+        let sx = if to.x < from.x { -1 } else { 1 };
+        let sy = if to.y < from.y { -1 } else { 1 };
+
+        let mut pos = from;
+        let mut line = vec![pos];
+        let (mut ix, mut iy) = (0, 0);
+
+        // step along whichever axis is further behind the ideal line
+        while ix < dx || iy < dy {
+            if (1 + 2 * ix) * dy < (1 + 2 * iy) * dx {
+                pos = pos.offset(sx, 0).unwrap();
+                ix += 1;
+            } else {
+                pos = pos.offset(0, sy).unwrap();
+                iy += 1;
+            }
+
+            line.push(pos);
+        }
+
+        for pos in &line {
+            self[*pos].kind = kind;
+        }
+
+        line
+    }
+
+    /// Sets the kind of the panels in a rectangle, or only on its edges if it
+    /// is not `filled`.
+    ///
+    /// # Panics
+    /// Will panic if any part of the rectangle is out of bounds.
+    pub fn draw_rect(&mut self, rect: Rect, kind: PanelKind, filled: bool) {
+        assert!(rect.fits(self.width(), self.height()),
+            "rectangle {} is out of bounds", rect);
+
+        for pos in rect.positions() {
+            let edge = pos.x == rect.x || pos.y == rect.y
+                || pos.x == rect.x + rect.width - 1 || pos.y == rect.y + rect.height - 1;
+
+            if filled || edge {
+                self[pos].kind = kind;
+            }
+        }
+    }
+
+    /// Sets the kind of the panels on the edges of a rectangle, and connects
+    /// them into a clockwise loop.
+    ///
+    /// Rectangles only one panel wide or tall cannot loop, so their panels
+    /// are left unconnected.
+    ///
+    /// # Panics
+    /// Will panic if any part of the rectangle is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, Field, PanelKind::*};
+    /// use citrus_common::pos::Rect;
+    ///
+    /// let mut field = Field::builder(2, 2).build();
+    /// field.draw_ring(Rect::new(0, 0, 2, 2), Neutral);
+    ///
+    /// assert_eq!(field, field![
+    ///     [Neutral + EAST, Neutral + SOUTH],
+    ///     [Neutral + NORTH, Neutral + WEST],
+    /// ]);
+    /// ```
+    pub fn draw_ring(&mut self, rect: Rect, kind: PanelKind) {
+        self.draw_rect(rect, kind, false);
+
+        if rect.width < 2 || rect.height < 2 {
+            return;
+        }
+
+        let ring = ring(rect);
+        let next = ring.iter().cycle().skip(1);
+
+        for (pos, next) in ring.iter().zip(next) {
+            self[*pos].exits |= direction_between(*pos, *next).unwrap().to_exits();
+        }
+    }
+
+    /// Wires exits along a path, so moving from its first panel leads along
+    /// each panel in turn.
+    ///
+    /// If `kind` is given, every panel on the path is set to it; otherwise,
+    /// their kinds are left alone. If `bidirectional` is set, backtrack exits
+    /// are also wired the opposite way, so the path can be walked back during
+    /// Backtrack. A path that ends where it starts makes a loop.
+    ///
+    /// Exits the panels already have are kept.
+    ///
+    /// # Panics
+    /// Will panic if any position is out of bounds, or if two positions in a
+    /// row are not next to each other.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, Field, Pos, PanelKind::*};
+    ///
+    /// let mut field = Field::builder(3, 1).build();
+    /// let path = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(2, 0)];
+    ///
+    /// field.lay_path(&path, Some(Neutral), false);
+    ///
+    /// assert_eq!(field, field![[Neutral + EAST, Neutral + EAST, Neutral]]);
+    /// ```
+    pub fn lay_path(&mut self, path: &[Pos], kind: Option<PanelKind>, bidirectional: bool) {
+        for pos in path {
+            assert!(self.contains(*pos), "path position {} is out of bounds", pos);
+        }
+
+        for pair in path.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+
+            let dir = direction_between(from, to)
+                .unwrap_or_else(|| panic!("path positions {} and {} are not adjacent", from, to));
+
+            self[from].exits |= dir.to_exits();
+
+            if bidirectional {
+                self[to].exits_backtrack |= dir.opposite().to_exits();
+            }
+        }
+
+        if let Some(kind) = kind {
+            for pos in path {
+                self[*pos].kind = kind;
+            }
+        }
+    }
+
+    /// Creates exits between every pair of non-empty panels next to each
+    /// other.
+    ///
+    /// Exits the panels already have are kept, and backtrack exits are left
+    /// alone.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    /// use citrus_common::draw::ConnectMode;
+    ///
+    /// let mut field = field![
+    ///     [Home, Draw],
+    ///     [Empty, Bonus],
+    /// ];
+    ///
+    /// field.connect_adjacent(ConnectMode::OneWay);
+    ///
+    /// assert_eq!(field, field![
+    ///     [Home + EAST, Draw + SOUTH],
+    ///     [Empty, Bonus],
+    /// ]);
+    /// ```
+    pub fn connect_adjacent(&mut self, mode: ConnectMode) {
+        for pos in self.positions() {
+            if self[pos].kind == PanelKind::Empty {
+                continue;
+            }
+
+            for dir in Direction::ALL.iter().copied() {
+                let forward = dir == Direction::East || dir == Direction::South;
+
+                if mode == ConnectMode::OneWay && !forward {
+                    continue;
+                }
+
+                let linked = pos.step(dir)
+                    .filter(|to| self.contains(*to))
+                    .is_some_and(|to| self[to].kind != PanelKind::Empty);
+
+                if linked {
+                    self[pos].exits |= dir.to_exits();
+                }
+            }
+        }
+    }
+
+    /// Removes exits that lead off the field or into empty panels, and exits
+    /// on empty panels.
+    ///
+    /// If `mirror` is set, panels that one-way exits lead to also get an exit
+    /// leading back. Backtrack exits are never mirrored. Returns what was
+    /// changed.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    ///
+    /// let mut field = field![[Home + EAST + NORTH, Draw, Empty + WEST]];
+    /// let report = field.normalize_exits(true);
+    ///
+    /// assert_eq!(report.removed.len(), 2);
+    /// assert_eq!(report.mirrored.len(), 1);
+    /// assert_eq!(field, field![[Home + EAST, Draw + WEST, Empty]]);
+    /// ```
+    pub fn normalize_exits(&mut self, mirror: bool) -> NormalizeReport {
+        let mut report = NormalizeReport::default();
+
+        for pos in self.positions() {
+            let empty = self[pos].kind == PanelKind::Empty;
+
+            for dir in Direction::ALL.iter().copied() {
+                let exit = dir.to_exits();
+                let leads = !empty && pos.step(dir)
+                    .filter(|to| self.contains(*to))
+                    .is_some_and(|to| self[to].kind != PanelKind::Empty);
+
+                if leads {
+                    continue;
+                }
+
+                for backtrack in [false, true].iter().copied() {
+                    let panel = &mut self[pos];
+                    let exits = if backtrack {
+                        &mut panel.exits_backtrack
+                    } else {
+                        &mut panel.exits
+                    };
+
+                    if exits.has(exit) {
+                        *exits = exits.without(exit);
+                        report.removed.push(ExitChange { pos, exit: dir, backtrack });
+                    }
+                }
+            }
+        }
+
+        if mirror {
+            for pos in self.positions() {
+                for dir in Direction::ALL.iter().copied() {
+                    if !self[pos].exits.has(dir.to_exits()) {
+                        continue;
+                    }
+
+                    // every exit left leads to a panel on the field
+                    let to = pos.step(dir).unwrap();
+                    let back = dir.opposite();
+
+                    if !self[to].exits.has(back.to_exits()) {
+                        self[to].exits |= back.to_exits();
+                        report.mirrored.push(ExitChange { pos: to, exit: back, backtrack: false });
+                    }
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// Gets the direction that leads from one position to another next to it.
+pub(crate) fn direction_between(from: Pos, to: Pos) -> Option<Direction> {
+    Direction::ALL.iter()
+        .copied()
+        .find(|dir| from.step(*dir) == Some(to))
+}
+
+/// Gets the positions on the edges of a rectangle, clockwise from the
+/// top-left corner.
+///
+/// Rectangles must be at least two panels wide and tall.
+pub(crate) fn ring(rect: Rect) -> Vec<Pos> {
+    let Rect { x, y, width, height } = rect;
+    let (right, bottom) = (x + width - 1, y + height - 1);
+
+    (x..right).map(|x| Pos::new(x, y))
+        .chain((y..bottom).map(|y| Pos::new(right, y)))
+        .chain((x + 1..=right).rev().map(|x| Pos::new(x, bottom)))
+        .chain((y + 1..=bottom).rev().map(|y| Pos::new(x, y)))
+        .collect()
+}
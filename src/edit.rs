@@ -0,0 +1,837 @@
+//! Editing operations on fields.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, PanelKind::*};
+//! use citrus_common::edit::Blend;
+//! use citrus_common::pos::Rect;
+//!
+//! let mut field = field![
+//!     [Home, Empty, Empty],
+//!     [Draw, Empty, Empty],
+//! ];
+//!
+//! let stamp = field.copy_region(Rect::new(0, 0, 1, 2));
+//! field.paste(&stamp, 2, 0, Blend::Overwrite);
+//!
+//! assert_eq!(field, field![
+//!     [Home, Empty, Home],
+//!     [Draw, Empty, Draw],
+//! ]);
+//! ```
+
+use crate::{Direction, Field, Panel, PanelKind, Exits, Pos};
+use crate::pos::Rect;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::iter::FromIterator;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// A rectangular piece of a field, made by [`Field::copy_region`].
+///
+/// Exits that crossed the border of the region are removed, so a fragment
+/// is a self-contained field of its own.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FieldFragment {
+    field: Field,
+}
+
+impl FieldFragment {
+    /// Gets the panels of the fragment.
+    pub fn field(&self) -> &Field {
+        &self.field
+    }
+
+    /// Takes the panels of the fragment.
+    pub fn into_field(self) -> Field {
+        self.field
+    }
+
+    /// Gets the width of the fragment.
+    pub fn width(&self) -> usize {
+        self.field.width()
+    }
+
+    /// Gets the height of the fragment.
+    pub fn height(&self) -> usize {
+        self.field.height()
+    }
+}
+
+impl From<Field> for FieldFragment {
+    fn from(field: Field) -> FieldFragment {
+        FieldFragment { field }
+    }
+}
+
+/// How [`Field::paste`] combines a fragment with the panels under it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Blend {
+    /// Every panel of the fragment replaces the panel under it.
+    #[default]
+    Overwrite,
+    /// Empty panels of the fragment leave the panels under them alone.
+    SkipEmpty,
+}
+
+/// A set of positions on a field, such as an editor's selection.
+///
+/// Selections are built from rectangles and single positions, and combined
+/// like sets. Positions are always visited row-major.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*, Pos};
+/// use citrus_common::edit::Selection;
+/// use citrus_common::pos::Rect;
+///
+/// let mut field = field![
+///     [Home, Empty, Empty],
+///     [Empty, Empty, Empty],
+/// ];
+///
+/// let mut selection = Selection::from(Rect::new(1, 0, 2, 2));
+/// selection.remove(Pos::new(2, 1));
+///
+/// selection.fill(&mut field, Neutral);
+///
+/// assert_eq!(field, field![
+///     [Home, Neutral, Neutral],
+///     [Empty, Neutral, Empty],
+/// ]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Selection {
+    /// The positions, as `(y, x)` so they sort row-major.
+    cells: BTreeSet<(usize, usize)>,
+}
+
+impl Selection {
+    /// Creates an empty selection.
+    pub const fn new() -> Selection {
+        Selection {
+            cells: BTreeSet::new(),
+        }
+    }
+
+    /// Adds a position, returning `false` if it was already selected.
+    pub fn insert(&mut self, pos: Pos) -> bool {
+        self.cells.insert((pos.y, pos.x))
+    }
+
+    /// Removes a position, returning `false` if it was not selected.
+    pub fn remove(&mut self, pos: Pos) -> bool {
+        self.cells.remove(&(pos.y, pos.x))
+    }
+
+    /// Adds every position in a rectangle.
+    pub fn insert_rect(&mut self, rect: Rect) {
+        self.cells.extend(rect.positions().map(|pos| (pos.y, pos.x)));
+    }
+
+    /// Removes every position in a rectangle.
+    pub fn remove_rect(&mut self, rect: Rect) {
+        for pos in rect.positions() {
+            self.remove(pos);
+        }
+    }
+
+    /// Checks if a position is selected.
+    pub fn contains(&self, pos: Pos) -> bool {
+        self.cells.contains(&(pos.y, pos.x))
+    }
+
+    /// Gets how many positions are selected.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Checks if nothing is selected.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Deselects everything.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// Gets the positions selected in either selection.
+    pub fn union(&self, other: &Selection) -> Selection {
+        Selection { cells: &self.cells | &other.cells }
+    }
+
+    /// Gets the positions selected in both selections.
+    pub fn intersection(&self, other: &Selection) -> Selection {
+        Selection { cells: &self.cells & &other.cells }
+    }
+
+    /// Gets the positions selected in this selection, but not in `other`.
+    pub fn difference(&self, other: &Selection) -> Selection {
+        Selection { cells: &self.cells - &other.cells }
+    }
+
+    /// Gets the smallest rectangle holding every selected position.
+    pub fn bounds(&self) -> Option<Rect> {
+        let (top, bottom) = (self.cells.iter().next()?.0, self.cells.iter().next_back()?.0);
+        let left = self.cells.iter().map(|(_, x)| *x).min()?;
+        let right = self.cells.iter().map(|(_, x)| *x).max()?;
+
+        Some(Rect::new(left, top, right - left + 1, bottom - top + 1))
+    }
+
+    /// Gets an iterator over the selected positions, row-major.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = Pos> + ExactSizeIterator + '_ {
+        self.cells.iter().map(|(y, x)| Pos::new(*x, *y))
+    }
+
+    /// Gets an iterator over the selected panels of a field, row-major.
+    ///
+    /// Selected positions that are off the field are skipped.
+    pub fn panels<'a>(&'a self, field: &'a Field) -> impl Iterator<Item = (Pos, &'a Panel)> + 'a {
+        self.iter()
+            .filter(move |pos| field.contains(*pos))
+            .map(move |pos| (pos, &field[pos]))
+    }
+
+    /// Calls `f` with each selected panel of a field, row-major.
+    ///
+    /// Selected positions that are off the field are skipped.
+    pub fn for_each_mut<F>(&self, field: &mut Field, mut f: F)
+    where F: FnMut(Pos, &mut Panel) {
+        for pos in self.iter() {
+            if field.contains(pos) {
+                f(pos, &mut field[pos]);
+            }
+        }
+    }
+
+    /// Sets the kind of every selected panel of a field.
+    pub fn fill(&self, field: &mut Field, kind: PanelKind) {
+        self.for_each_mut(field, |_, panel| panel.kind = kind);
+    }
+
+    /// Removes the exits of every selected panel of a field.
+    pub fn clear_exits(&self, field: &mut Field) {
+        self.for_each_mut(field, |_, panel| {
+            panel.exits = Exits::none();
+            panel.exits_backtrack = Exits::none();
+        });
+    }
+}
+
+impl From<Rect> for Selection {
+    fn from(rect: Rect) -> Selection {
+        let mut selection = Selection::new();
+        selection.insert_rect(rect);
+        selection
+    }
+}
+
+impl FromIterator<Pos> for Selection {
+    fn from_iter<I>(iter: I) -> Selection
+    where I: IntoIterator<Item = Pos> {
+        Selection {
+            cells: iter.into_iter().map(|pos| (pos.y, pos.x)).collect(),
+        }
+    }
+}
+
+impl Extend<Pos> for Selection {
+    fn extend<I>(&mut self, iter: I)
+    where I: IntoIterator<Item = Pos> {
+        self.cells.extend(iter.into_iter().map(|pos| (pos.y, pos.x)));
+    }
+}
+
+/// A field that remembers its edits, so they can be undone and redone.
+///
+/// Each call to [`EditHistory::edit`] is one step of history. Only the panels
+/// an edit changed are kept, unless it changed the size of the field.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::edit::EditHistory;
+///
+/// let mut history = EditHistory::new(field![[Home, Empty]]);
+///
+/// history.edit(|field| field.get_mut((1, 0)).kind = Draw);
+/// assert_eq!(history.field().get((1, 0)).kind, Draw);
+///
+/// history.undo();
+/// assert_eq!(history.field().get((1, 0)).kind, Empty);
+///
+/// history.redo();
+/// assert_eq!(history.field().get((1, 0)).kind, Draw);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EditHistory {
+    field: Field,
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+/// A step of [`EditHistory`].
+#[derive(Clone, Debug)]
+enum Change {
+    /// Some panels changed, from the first panel to the second.
+    Panels(Vec<(Pos, Panel, Panel)>),
+    /// The size of the field changed, from the first field to the second.
+    Replace(Box<(Field, Field)>),
+}
+
+impl Change {
+    /// Finds what changed between two fields, if anything.
+    fn between(before: Field, after: &Field) -> Option<Change> {
+        if (before.width(), before.height()) != (after.width(), after.height()) {
+            return Some(Change::Replace(Box::new((before, after.clone()))));
+        }
+
+        let panels = before.panels()
+            .zip(after.panels())
+            .filter(|((_, _, old), (_, _, new))| old != new)
+            .map(|((x, y, old), (_, _, new))| (Pos::new(x, y), old.clone(), new.clone()))
+            .collect::<Vec<_>>();
+
+        if panels.is_empty() {
+            None
+        } else {
+            Some(Change::Panels(panels))
+        }
+    }
+
+    /// Applies the change to a field, forwards or backwards.
+    fn apply(&self, field: &mut Field, forwards: bool) {
+        match self {
+            Change::Panels(panels) => {
+                for (pos, old, new) in panels {
+                    field[*pos] = if forwards { new } else { old }.clone();
+                }
+            },
+            Change::Replace(fields) => {
+                *field = if forwards { &fields.1 } else { &fields.0 }.clone();
+            },
+        }
+    }
+}
+
+impl EditHistory {
+    /// Starts the history of a field.
+    pub fn new(field: Field) -> EditHistory {
+        EditHistory {
+            field,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Gets the field, as of the latest edit.
+    pub fn field(&self) -> &Field {
+        &self.field
+    }
+
+    /// Takes the field, forgetting its history.
+    pub fn into_field(self) -> Field {
+        self.field
+    }
+
+    /// Edits the field as one step of history, returning what `f` returns.
+    ///
+    /// Edits that change nothing are not recorded. Any edits that were undone
+    /// are forgotten.
+    pub fn edit<F, T>(&mut self, f: F) -> T
+    where F: FnOnce(&mut Field) -> T {
+        let before = self.field.clone();
+        let out = f(&mut self.field);
+
+        self.record(before);
+
+        out
+    }
+
+    /// Edits the field as one step of history, unless `f` fails.
+    ///
+    /// If `f` returns an error, its edits are rolled back and nothing is
+    /// recorded. See [`Field::transaction`].
+    pub fn transaction<F, T, E>(&mut self, f: F) -> Result<T, E>
+    where F: FnOnce(&mut Field) -> Result<T, E> {
+        let before = self.field.clone();
+
+        match f(&mut self.field) {
+            Ok(out) => {
+                self.record(before);
+                Ok(out)
+            },
+            Err(e) => {
+                self.field = before;
+                Err(e)
+            },
+        }
+    }
+
+    /// Records the changes from `before` to the current field as a step.
+    fn record(&mut self, before: Field) {
+        if let Some(change) = Change::between(before, &self.field) {
+            self.undo.push(change);
+            self.redo.clear();
+        }
+    }
+
+    /// Undoes the latest edit, returning `false` if there was none.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(change) => {
+                change.apply(&mut self.field, false);
+                self.redo.push(change);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Redoes the latest edit that was undone, returning `false` if there was
+    /// none.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(change) => {
+                change.apply(&mut self.field, true);
+                self.undo.push(change);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Checks if there is an edit to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Checks if there is an edit to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forgets all of the history, keeping the field as it is.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// A change to an [`ObservedField`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FieldEvent {
+    /// A panel changed.
+    PanelChanged {
+        pos: Pos,
+        old: Panel,
+        new: Panel,
+    },
+    /// The field changed size, and may have changed entirely.
+    Resized {
+        old: (usize, usize),
+        new: (usize, usize),
+    },
+}
+
+/// A field that tells observers about every change made to it.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, Pos, PanelKind::*};
+/// use citrus_common::edit::{FieldEvent, ObservedField};
+///
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// let dirty = Rc::new(RefCell::new(Vec::new()));
+///
+/// let mut field = ObservedField::new(field![[Home, Empty]]);
+/// let events = Rc::clone(&dirty);
+/// field.observe(move |event| {
+///     if let FieldEvent::PanelChanged { pos, .. } = event {
+///         events.borrow_mut().push(*pos);
+///     }
+/// });
+///
+/// field.edit(|field| field.get_mut((1, 0)).kind = Draw);
+/// assert_eq!(*dirty.borrow(), [Pos::new(1, 0)]);
+/// ```
+#[derive(Default)]
+pub struct ObservedField {
+    field: Field,
+    observers: Vec<Observer>,
+}
+
+/// A callback of an [`ObservedField`].
+type Observer = Box<dyn FnMut(&FieldEvent)>;
+
+impl ObservedField {
+    /// Wraps a field, with no observers.
+    pub fn new(field: Field) -> ObservedField {
+        ObservedField {
+            field,
+            observers: Vec::new(),
+        }
+    }
+
+    /// Adds an observer, which is called with every change from now on.
+    pub fn observe<F>(&mut self, observer: F)
+    where F: FnMut(&FieldEvent) + 'static {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Gets the field.
+    pub fn field(&self) -> &Field {
+        &self.field
+    }
+
+    /// Takes the field, dropping the observers.
+    pub fn into_field(self) -> Field {
+        self.field
+    }
+
+    /// Replaces a panel.
+    ///
+    /// Unlike [`ObservedField::edit`], this does not copy the field to find
+    /// what changed, so it is cheap enough to call for every panel a brush
+    /// passes over.
+    ///
+    /// # Panics
+    /// Will panic if the position is out of bounds.
+    pub fn set(&mut self, pos: Pos, panel: Panel) {
+        let old = std::mem::replace(&mut self.field[pos], panel);
+
+        if old != self.field[pos] {
+            let new = self.field[pos].clone();
+            self.emit(&FieldEvent::PanelChanged { pos, old, new });
+        }
+    }
+
+    /// Edits the field, then tells the observers what changed.
+    pub fn edit<F, T>(&mut self, f: F) -> T
+    where F: FnOnce(&mut Field) -> T {
+        let before = self.field.clone();
+        let out = f(&mut self.field);
+
+        match Change::between(before, &self.field) {
+            Some(Change::Panels(panels)) => {
+                for (pos, old, new) in panels {
+                    self.emit(&FieldEvent::PanelChanged { pos, old, new });
+                }
+            },
+            Some(Change::Replace(fields)) => {
+                let (old, new) = *fields;
+
+                self.emit(&FieldEvent::Resized {
+                    old: (old.width(), old.height()),
+                    new: (new.width(), new.height()),
+                });
+            },
+            None => (),
+        }
+
+        out
+    }
+
+    fn emit(&mut self, event: &FieldEvent) {
+        for observer in &mut self.observers {
+            observer(event);
+        }
+    }
+}
+
+impl Field {
+    /// Edits the field all at once, or not at all.
+    ///
+    /// If `f` returns an error, the field is rolled back to how it was before
+    /// `f` was called, so tools that make many edits never leave a field
+    /// half-edited.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    ///
+    /// let mut field = field![[Home, Empty]];
+    ///
+    /// let result = field.transaction(|field| {
+    ///     field.get_mut((0, 0)).kind = Draw;
+    ///
+    ///     match field.try_get_mut((2, 0)) {
+    ///         Some(mut panel) => Ok(panel.kind = Draw),
+    ///         None => Err("out of room"),
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(result, Err("out of room"));
+    /// assert_eq!(field.get((0, 0)).kind, Home);
+    /// ```
+    pub fn transaction<F, T, E>(&mut self, f: F) -> Result<T, E>
+    where F: FnOnce(&mut Field) -> Result<T, E> {
+        let before = self.clone();
+        let out = f(self);
+
+        if out.is_err() {
+            *self = before;
+        }
+
+        out
+    }
+
+    /// Copies a region of the field to a [`FieldFragment`].
+    ///
+    /// # Panics
+    /// Will panic if any part of the region is out of bounds.
+    pub fn copy_region(&self, rect: Rect) -> FieldFragment {
+        FieldFragment {
+            field: self.subfield(rect.x, rect.y, rect.width, rect.height),
+        }
+    }
+
+    /// Pastes a fragment onto the field, with its top-left corner at
+    /// `(x, y)`.
+    ///
+    /// Any part of the fragment that falls off the field is left out.
+    pub fn paste(&mut self, fragment: &FieldFragment, x: usize, y: usize, blend: Blend) {
+        for (fx, fy, panel) in fragment.field.panels() {
+            if blend == Blend::SkipEmpty && panel.kind == PanelKind::Empty {
+                continue;
+            }
+
+            let pos = Pos::new(fx, fy) + Pos::new(x, y);
+
+            if self.contains(pos) {
+                self[pos] = panel.clone();
+            }
+        }
+    }
+
+    /// Swaps two panels, along with their exits.
+    ///
+    /// Exits that would now lead to a different panel than before are
+    /// removed, both on the swapped panels and on the panels around them.
+    ///
+    /// # Panics
+    /// Will panic if either position is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    ///
+    /// let mut field = field![[Home + EAST, Draw + WEST, Bonus]];
+    /// field.swap(1, 0, 2, 0);
+    ///
+    /// assert_eq!(field, field![[Home, Bonus, Draw]]);
+    /// ```
+    pub fn swap(&mut self, x1: usize, y1: usize, x2: usize, y2: usize) {
+        let (a, b) = (Pos::new(x1, y1), Pos::new(x2, y2));
+
+        // do bounds checks
+        self.get(a);
+        self.get(b);
+
+        if a != b {
+            self.relocate(&[(a, b), (b, a)], &[]);
+        }
+    }
+
+    /// Moves a region of the field so its top-left corner is at `dest`.
+    ///
+    /// The panels under the destination are replaced, and the space the
+    /// region leaves behind is filled with empty panels. Exits that would now
+    /// lead to a different panel than before are removed.
+    ///
+    /// # Panics
+    /// Will panic if any part of the region or its destination is out of
+    /// bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    /// use citrus_common::Pos;
+    /// use citrus_common::pos::Rect;
+    ///
+    /// let mut field = field![[Home + EAST, Draw + WEST, Empty]];
+    /// field.move_region(Rect::new(0, 0, 2, 1), Pos::new(1, 0));
+    ///
+    /// assert_eq!(field, field![[Empty, Home + EAST, Draw + WEST]]);
+    /// ```
+    pub fn move_region(&mut self, rect: Rect, dest: Pos) {
+        let moved = Rect { x: dest.x, y: dest.y, ..rect };
+
+        assert!(rect.fits(self.width(), self.height()),
+            "region {} is out of bounds", rect);
+        assert!(moved.fits(self.width(), self.height()),
+            "destination {} is out of bounds", moved);
+
+        let moves = rect.positions()
+            .zip(moved.positions())
+            .collect::<Vec<_>>();
+        let vacated = rect.positions()
+            .filter(|pos| !moved.contains(*pos))
+            .collect::<Vec<_>>();
+
+        self.relocate(&moves, &vacated);
+    }
+
+    /// Moves panels from one position to another, emptying `vacated`.
+    ///
+    /// Afterwards, exits around the changed positions are kept only if they
+    /// still lead to the same panel, or still lead off the field.
+    fn relocate(&mut self, moves: &[(Pos, Pos)], vacated: &[Pos]) {
+        let old = self.clone();
+
+        // where the panel at each changed position came from, if anywhere
+        let mut origins = HashMap::new();
+
+        for pos in vacated {
+            self[*pos] = Panel::EMPTY;
+            origins.insert(*pos, None);
+        }
+
+        for (from, to) in moves {
+            self[*to] = old[*from].clone();
+            origins.insert(*to, Some(*from));
+        }
+
+        let origin = |pos: Pos| origins.get(&pos).copied().unwrap_or(Some(pos));
+        let step = |field: &Field, pos: Pos, dir: Direction| {
+            pos.step(dir).filter(|pos| field.contains(*pos))
+        };
+
+        // the changed positions, and the positions next to them
+        let check = origins.keys()
+            .flat_map(|pos| {
+                Direction::ALL.iter()
+                    .filter_map(move |dir| pos.step(*dir))
+                    .chain(Some(*pos))
+            })
+            .filter(|pos| self.contains(*pos))
+            .collect::<HashSet<_>>();
+
+        for pos in check {
+            let from = match origin(pos) {
+                Some(from) => from,
+                None => continue,
+            };
+
+            for dir in Direction::ALL.iter().copied() {
+                let before = step(&old, from, dir);
+                let after = step(self, pos, dir).map(origin);
+
+                let same = match (before, after) {
+                    (None, None) => true,
+                    (Some(before), Some(Some(after))) => before == after,
+                    _ => false,
+                };
+
+                if !same {
+                    let panel = &mut self[pos];
+
+                    panel.exits = panel.exits.without(dir.to_exits());
+                    panel.exits_backtrack = panel.exits_backtrack.without(dir.to_exits());
+                }
+            }
+        }
+    }
+}
+
+/// The result of [`merge`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Merged {
+    /// The merged field.
+    ///
+    /// Conflicting panels are left as they are in `ours`.
+    pub field: Field,
+    /// The panels both sides changed in different ways.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A panel that both sides of a [`merge`] changed in different ways.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub pos: Pos,
+    pub base: Panel,
+    pub ours: Panel,
+    pub theirs: Panel,
+}
+
+/// A side of a [`merge`] changed the size of the field while the other side
+/// made different changes, so their panels cannot be matched up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SizeConflict {
+    pub ours: (usize, usize),
+    pub theirs: (usize, usize),
+}
+
+impl Display for SizeConflict {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f, "cannot merge a {}x{} field with a {}x{} field",
+            self.ours.0, self.ours.1, self.theirs.0, self.theirs.1,
+        )
+    }
+}
+
+impl std::error::Error for SizeConflict {}
+
+/// Merges two edited copies of a field, given the field they both started
+/// from.
+///
+/// Panels changed on only one side take that side's change, and panels
+/// changed the same way on both sides take that change. Panels changed in
+/// different ways are reported as conflicts.
+///
+/// A side that changed the size of the field only wins if the other side is
+/// unchanged. If one side changed the size and the other edited any panels,
+/// the panels cannot be matched up, and the merge fails with a
+/// [`SizeConflict`].
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::edit;
+///
+/// let base = field![[Home, Empty, Empty]];
+/// let ours = field![[Home, Draw, Drop]];
+/// let theirs = field![[Home, Draw, Bonus]];
+///
+/// let merged = edit::merge(&base, &ours, &theirs).unwrap();
+///
+/// assert_eq!(merged.field, ours);
+/// assert_eq!(merged.conflicts.len(), 1);
+/// assert_eq!(merged.conflicts[0].theirs.kind, Bonus);
+/// ```
+pub fn merge(base: &Field, ours: &Field, theirs: &Field) -> Result<Merged, SizeConflict> {
+    let clean = |field: &Field| Merged { field: field.clone(), conflicts: Vec::new() };
+
+    if ours == base || ours == theirs {
+        return Ok(clean(theirs));
+    } else if theirs == base {
+        return Ok(clean(ours));
+    }
+
+    let size = |field: &Field| (field.width(), field.height());
+
+    if size(ours) != size(base) || size(theirs) != size(base) {
+        return Err(SizeConflict { ours: size(ours), theirs: size(theirs) });
+    }
+
+    let mut field = ours.clone();
+    let mut conflicts = Vec::new();
+
+    for pos in base.positions() {
+        let (b, o, t) = (&base[pos], &ours[pos], &theirs[pos]);
+
+        if o == b || o == t {
+            field[pos] = t.clone();
+        } else if t != b {
+            conflicts.push(Conflict { pos, base: b.clone(), ours: o.clone(), theirs: t.clone() });
+        }
+    }
+
+    Ok(Merged { field, conflicts })
+}
@@ -0,0 +1,180 @@
+//! Exporting fields to other tools.
+
+use crate::{Direction, Exits, Field, PanelKind, Pos};
+use crate::render;
+
+use std::fmt::Write as _;
+
+/// Writes a field as a Graphviz DOT graph.
+///
+/// Each non-empty panel is a node, labeled with its kind and position, and
+/// each exit between two of them is an edge. Backtrack exits are drawn as
+/// dashed edges. Nodes are pinned to their place on the field, so `neato -n`
+/// lays the graph out like the field itself.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::export;
+///
+/// let dot = export::dot(&field![[Home + EAST, Draw]]);
+///
+/// assert!(dot.starts_with("digraph field {"));
+/// assert!(dot.contains("p0_0 -> p1_0;"));
+/// ```
+pub fn dot(field: &Field) -> String {
+    let mut out = String::from("digraph field {\n    node [shape=box];\n");
+
+    let panels: Vec<Pos> = field.positions()
+        .filter(|pos| field[*pos].kind != PanelKind::Empty)
+        .collect();
+
+    for pos in panels.iter().copied() {
+        // writing to a string never fails
+        let _ = writeln!(
+            out, "    {} [label=\"{:?}\\n{}\", pos=\"{},{}!\"];",
+            node(pos), field[pos].kind, pos, pos.x * 72, (field.height() - pos.y) * 72,
+        );
+    }
+
+    for pos in panels {
+        let panel = &field[pos];
+
+        for dir in Direction::ALL.iter().copied() {
+            let to = match pos.step(dir) {
+                Some(to) if field.contains(to) && field[to].kind != PanelKind::Empty => to,
+                _ => continue,
+            };
+
+            if panel.exits.has(dir.to_exits()) {
+                let _ = writeln!(out, "    {} -> {};", node(pos), node(to));
+            }
+
+            if panel.exits_backtrack.has(dir.to_exits()) {
+                let _ = writeln!(out, "    {} -> {} [style=dashed];", node(pos), node(to));
+            }
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// How [`html`] writes a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HtmlOptions {
+    /// The class of the element holding the field.
+    pub class: String,
+    /// Whether to write each panel's kind abbreviation inside it.
+    pub labels: bool,
+    /// Whether to write a `<style>` element first, coloring each kind on the
+    /// field.
+    pub style: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> HtmlOptions {
+        HtmlOptions {
+            class: "citrus-field".into(),
+            labels: true,
+            style: true,
+        }
+    }
+}
+
+/// Writes a field as HTML, laid out with a CSS grid.
+///
+/// Every panel, Empty ones included, is a `<div>` in row-major order, so the
+/// grid places it. Each has the classes `panel` and the kind in lowercase,
+/// and data attributes for its position, kind, exits and backtrack exits.
+/// Exits are written as space-separated directions, so they can be styled
+/// with selectors like `[data-exits~="north"]`.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::export::{self, HtmlOptions};
+///
+/// let html = export::html(&field![[Home + EAST, Draw]], &HtmlOptions::default());
+///
+/// assert!(html.contains(r#"<div class="panel home" data-x="0" data-y="0" data-kind="Home" data-exits="east" data-backtrack="">@@</div>"#));
+/// ```
+pub fn html(field: &Field, options: &HtmlOptions) -> String {
+    let class = escape(&options.class);
+    let mut out = String::new();
+
+    if options.style {
+        let mut kinds: Vec<PanelKind> = Vec::new();
+
+        for (_, _, panel) in field.panels() {
+            if panel.kind != PanelKind::Empty && !kinds.contains(&panel.kind) {
+                kinds.push(panel.kind);
+            }
+        }
+
+        kinds.sort_by_key(|kind| u8::from(*kind));
+
+        out.push_str("<style>\n");
+        let _ = writeln!(out, "  .{} {{ gap: 2px; grid-auto-rows: 3em; font-family: monospace; }}", class);
+        let _ = writeln!(out, "  .{} .panel {{ display: flex; align-items: center; justify-content: center; }}", class);
+
+        for kind in kinds {
+            let _ = writeln!(
+                out, "  .{} .{} {{ background: {}; border: 1px solid #404040; }}",
+                class, class_name(kind), render::color(kind),
+            );
+        }
+
+        out.push_str("</style>\n");
+    }
+
+    let _ = writeln!(
+        out, "<div class=\"{}\" style=\"display: grid; grid-template-columns: repeat({}, 3em);\">",
+        class, field.width(),
+    );
+
+    for (x, y, panel) in field.panels() {
+        let label = if options.labels { panel.kind.abbreviation().trim() } else { "" };
+
+        let _ = writeln!(
+            out, "  <div class=\"panel {}\" data-x=\"{}\" data-y=\"{}\" data-kind=\"{:?}\" data-exits=\"{}\" data-backtrack=\"{}\">{}</div>",
+            class_name(panel.kind), x, y, panel.kind,
+            directions(panel.exits), directions(panel.exits_backtrack), escape(label),
+        );
+    }
+
+    out.push_str("</div>\n");
+    out
+}
+
+/// Gets the class of a kind's panels.
+///
+/// Unknown kinds are named by their value, as `unknown-n`, so the class is
+/// still a valid selector.
+fn class_name(kind: PanelKind) -> String {
+    match kind {
+        PanelKind::Unknown(value) => format!("unknown-{}", value),
+        kind => format!("{:?}", kind).to_lowercase(),
+    }
+}
+
+/// Lists the directions of exits, separated by spaces.
+fn directions(exits: Exits) -> String {
+    exits.directions()
+        .map(Direction::name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escapes text for HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Gets the DOT name of a panel's node.
+fn node(pos: Pos) -> String {
+    format!("p{}_{}", pos.x, pos.y)
+}
@@ -13,22 +13,27 @@
 //! ]);
 //!
 //! // ...i want that draw panel to be a draw2x...
-//! let mut draw_panel = field.get_mut(0, 0);
+//! let mut draw_panel = field.get_mut((0, 0));
 //! draw_panel.kind = Draw2x;
 //!
 //! // ...i want the panel to the south to be a bonus2x...
 //! let mut southern_panel = draw_panel.offset(0, 1).unwrap();
 //! southern_panel.kind = Bonus2x;
 //!
-//! assert_eq!(field.get(0, 0).kind, Draw2x);
-//! assert_eq!(field.get(0, 1).kind, Bonus2x);
+//! assert_eq!(field.get((0, 0)).kind, Draw2x);
+//! assert_eq!(field.get((0, 1)).kind, Bonus2x);
 //! ```
 
 use crate::panel::*;
+use crate::Pos;
+use crate::pos::Rect;
 
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Index, IndexMut};
 use std::fmt::{Debug, Formatter, Result as FmtResult};
 
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
 /// A field, stored on the heap as a row-major flattened array.
 ///
 /// Most of a field's power comes from [`PanelRef::offset`] and
@@ -44,7 +49,7 @@ use std::fmt::{Debug, Formatter, Result as FmtResult};
 /// ]);
 ///
 /// // we can grab that Drop panel from an index to the Draw panel!
-/// let mut panel = field.get_mut(0, 0)
+/// let mut panel = field.get_mut((0, 0))
 ///     .offset(1, 1).unwrap();
 ///
 /// assert_eq!(panel.kind, Drop);
@@ -52,15 +57,21 @@ use std::fmt::{Debug, Formatter, Result as FmtResult};
 /// // we can also modify the panel's kind...
 /// panel.kind = Drop2x;
 /// // ...and watch it reflect on the field!
-/// assert_eq!(field.get(1, 1).kind, Drop2x);
+/// assert_eq!(field.get((1, 1)).kind, Drop2x);
 /// ```
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Field {
     data: Vec<Panel>,
     width: usize,
     height: usize,
 }
 
+impl Default for Field {
+    fn default() -> Field {
+        Field::new()
+    }
+}
+
 impl Field {
     /// Creates a new, empty field.
     pub const fn new() -> Field {
@@ -117,13 +128,18 @@ impl Field {
     /// Will panic if the inner iterators do not all have the same length.
     pub fn new_slice(slice: &[&[Panel]]) -> Field {
         Field::new_iter(
-            slice.into_iter().map(|subslice| {
+            slice.iter().map(|subslice| {
                 // take ownership of panels
-                subslice.into_iter().map(|p| p.clone())
+                subslice.iter().cloned()
             })
         )
     }
 
+    /// Starts building a field of empty panels with [`FieldBuilder`].
+    pub fn builder(width: usize, height: usize) -> FieldBuilder {
+        FieldBuilder::new(width, height)
+    }
+
     /// Gets the width of a field.
     pub fn width(&self) -> usize {
         self.width
@@ -135,46 +151,191 @@ impl Field {
     }
 
     /// Indexes the field immutably.
-    pub fn get(&self, x: usize, y: usize) -> PanelRef {
+    ///
+    /// The position can be a [`Pos`] or an `(x, y)` tuple.
+    pub fn get(&self, pos: impl Into<Pos>) -> PanelRef<'_> {
+        let Pos { x, y } = pos.into();
         PanelRef::new(self, x, y)
     }
 
     /// Indexes the field mutably.
-    pub fn get_mut(&mut self, x: usize, y: usize) -> PanelMut {
+    ///
+    /// The position can be a [`Pos`] or an `(x, y)` tuple.
+    pub fn get_mut(&mut self, pos: impl Into<Pos>) -> PanelMut<'_> {
+        let Pos { x, y } = pos.into();
         PanelMut::new(self, x, y)
     }
 
-    /// Gets an iterator over all of the positions on the field, row-major.
-    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + DoubleEndedIterator {
-        let Field { width, height, .. } = *self;
+    /// Indexes the field immutably, returning `None` if the position is out
+    /// of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{Field, Panel, PanelKind::*};
+    ///
+    /// let field = Field::new_slice(&[
+    ///     &[Panel::new(Draw), Panel::new(Encounter)],
+    /// ]);
+    ///
+    /// assert_eq!(field.try_get((1, 0)).unwrap().kind, Encounter);
+    /// assert!(field.try_get((2, 0)).is_none());
+    /// ```
+    pub fn try_get(&self, pos: impl Into<Pos>) -> Option<PanelRef<'_>> {
+        let Pos { x, y } = pos.into();
+
+        if self.in_bounds(x, y) {
+            Some(PanelRef { field: self, x, y })
+        } else {
+            None
+        }
+    }
+
+    /// Indexes the field mutably, returning `None` if the position is out of
+    /// bounds.
+    pub fn try_get_mut(&mut self, pos: impl Into<Pos>) -> Option<PanelMut<'_>> {
+        let Pos { x, y } = pos.into();
+
+        if self.in_bounds(x, y) {
+            Some(PanelMut { field: self, x, y })
+        } else {
+            None
+        }
+    }
+
+    /// Gets an iterator over all of the positions on the field as `(x, y)`
+    /// tuples, row-major.
+    ///
+    /// Use [`Field::positions`] to get them as [`Pos`] instead.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (usize, usize)> + ExactSizeIterator {
+        let width = self.width;
+
+        (0..self.data.len())
+            .map(move |idx| (idx % width, idx / width))
+    }
+
+    /// Gets an iterator over all of the positions on the field as [`Pos`],
+    /// row-major.
+    pub fn positions(&self) -> impl DoubleEndedIterator<Item = Pos> + ExactSizeIterator {
+        self.iter().map(Pos::from)
+    }
 
-        (0..height)
-            .map(move |y| (0..width).map(move |x| {
-                (x, y)
-            }))
-            .flatten()
+    /// Checks if a position is on the field.
+    pub fn contains(&self, pos: impl Into<Pos>) -> bool {
+        let Pos { x, y } = pos.into();
+        self.in_bounds(x, y)
+    }
+
+    /// Gets an iterator over all of the panels on the field and their
+    /// positions, row-major.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{Field, Panel, PanelKind::*};
+    ///
+    /// let field = Field::new_slice(&[
+    ///     &[Panel::new(Draw), Panel::new(Encounter)],
+    ///     &[Panel::new(Bonus), Panel::new(Drop)],
+    /// ]);
+    ///
+    /// let (x, y, _) = field.panels()
+    ///     .find(|(_, _, panel)| panel.kind == Bonus)
+    ///     .unwrap();
+    ///
+    /// assert_eq!((x, y), (0, 1));
+    /// ```
+    pub fn panels(&self) -> impl DoubleEndedIterator<Item = (usize, usize, &Panel)> + ExactSizeIterator {
+        let width = self.width;
+
+        self.data.iter()
+            .enumerate()
+            .map(move |(idx, panel)| (idx % width, idx / width, panel))
+    }
+
+    /// Gets a mutable iterator over all of the panels on the field and their
+    /// positions, row-major.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{Field, Panel, PanelKind::*, Exits};
+    ///
+    /// let mut home = Panel::new(Home);
+    /// home.exits = Exits::EAST;
+    ///
+    /// let mut field = Field::new_slice(&[
+    ///     &[home, Panel::new(Draw)],
+    /// ]);
+    ///
+    /// for (_, _, panel) in field.panels_mut() {
+    ///     panel.exits = Exits::none();
+    /// }
+    ///
+    /// assert!(field.get((0, 0)).exits == Exits::none());
+    /// ```
+    pub fn panels_mut(&mut self) -> impl DoubleEndedIterator<Item = (usize, usize, &mut Panel)> + ExactSizeIterator {
+        let width = self.width;
+
+        self.data.iter_mut()
+            .enumerate()
+            .map(move |(idx, panel)| (idx % width, idx / width, panel))
     }
 
     /// Gets an iterator over all of the panels in a row.
-    pub fn row_iter(&self, y: usize) -> impl Iterator<Item = PanelRef> + DoubleEndedIterator + ExactSizeIterator {
+    pub fn row_iter(&self, y: usize) -> impl DoubleEndedIterator<Item = PanelRef<'_>> + ExactSizeIterator {
         (0..self.width)
-            .map(move |x| self.get(x, y))
+            .map(move |x| self.get((x, y)))
+    }
+
+    /// Gets a mutable iterator over all of the panels in a row.
+    ///
+    /// # Panics
+    /// Will panic if the row is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{Field, Panel, PanelKind::*};
+    ///
+    /// let mut field = Field::new_vec(vec![Panel::EMPTY; 9], 3, 3);
+    ///
+    /// for panel in field.row_iter_mut(0) {
+    ///     panel.kind = Neutral;
+    /// }
+    ///
+    /// assert!(field.row_iter(0).all(|panel| panel.kind == Neutral));
+    /// assert!(field.row_iter(1).all(|panel| panel.kind == Empty));
+    /// ```
+    pub fn row_iter_mut(&mut self, y: usize) -> impl DoubleEndedIterator<Item = &mut Panel> + ExactSizeIterator {
+        assert!(y < self.height, "y ({}) is out of bounds ", y);
+
+        let start = self.flatten_index(0, y);
+        self.data[start..start + self.width].iter_mut()
     }
 
     /// Gets an iterator over all of the rows in a field.
-    pub fn rows_iter(&self) -> impl Iterator<Item = impl Iterator<Item = PanelRef>> + DoubleEndedIterator + ExactSizeIterator {
+    pub fn rows_iter(&self) -> impl DoubleEndedIterator<Item = impl Iterator<Item = PanelRef<'_>>> + ExactSizeIterator {
         (0..self.height)
             .map(move |y| self.row_iter(y))
     }
 
     /// Gets an iterator over all of the panels in a column.
-    pub fn column_iter(&self, x: usize) -> impl Iterator<Item = PanelRef> + DoubleEndedIterator + ExactSizeIterator {
+    pub fn column_iter(&self, x: usize) -> impl DoubleEndedIterator<Item = PanelRef<'_>> + ExactSizeIterator {
         (0..self.height)
-            .map(move |y| self.get(x, y))
+            .map(move |y| self.get((x, y)))
+    }
+
+    /// Gets a mutable iterator over all of the panels in a column.
+    ///
+    /// # Panics
+    /// Will panic if the column is out of bounds.
+    pub fn column_iter_mut(&mut self, x: usize) -> impl DoubleEndedIterator<Item = &mut Panel> + ExactSizeIterator {
+        assert!(x < self.width, "x ({}) is out of bounds ", x);
+
+        self.data.iter_mut()
+            .skip(x)
+            .step_by(self.width)
     }
 
     /// Gets an iterator over all of the columns in a field.
-    pub fn columns_iter(&self) -> impl Iterator<Item = impl Iterator<Item = PanelRef>> + DoubleEndedIterator + ExactSizeIterator {
+    pub fn columns_iter(&self) -> impl DoubleEndedIterator<Item = impl Iterator<Item = PanelRef<'_>>> + ExactSizeIterator {
         (0..self.width)
             .map(move |x| self.column_iter(x))
     }
@@ -183,20 +344,20 @@ impl Field {
     pub fn build_backtrack(&mut self) {
         // reset all backtrack exits
         for (x, y) in self.iter() {
-            self.get_mut(x, y).exits_backtrack = Exits::none();
+            self.get_mut((x, y)).exits_backtrack = Exits::none();
         }
 
         for (x, y) in self.iter() {
             // get mut ref
-            let panel = self.get_mut(x, y);
+            let panel = self.get_mut((x, y));
 
             // alter adjacent panels
             // south
-            let panel = if panel.exits & Exits::SOUTH {
-                match panel.offset(0, -1) {
+            let panel = if panel.exits.has(Exits::SOUTH) {
+                match panel.offset(0, 1) {
                     Ok(mut adjacent) => {
                         adjacent.exits_backtrack |= Exits::NORTH;
-                        adjacent.offset(0, 1).unwrap()
+                        adjacent.offset(0, -1).unwrap()
                     },
                     Err(panel) => panel,
                 }
@@ -205,11 +366,11 @@ impl Field {
             };
 
             // north
-            let panel = if panel.exits & Exits::NORTH {
-                match panel.offset(0, 1) {
+            let panel = if panel.exits.has(Exits::NORTH) {
+                match panel.offset(0, -1) {
                     Ok(mut adjacent) => {
                         adjacent.exits_backtrack |= Exits::SOUTH;
-                        adjacent.offset(0, -1).unwrap()
+                        adjacent.offset(0, 1).unwrap()
                     },
                     Err(panel) => panel,
                 }
@@ -218,7 +379,7 @@ impl Field {
             };
 
             // west
-            let panel = if panel.exits & Exits::WEST {
+            let panel = if panel.exits.has(Exits::WEST) {
                 match panel.offset(-1, 0) {
                     Ok(mut adjacent) => {
                         adjacent.exits_backtrack |= Exits::EAST;
@@ -231,7 +392,7 @@ impl Field {
             };
 
             // east
-            if panel.exits & Exits::EAST {
+            if panel.exits.has(Exits::EAST) {
                 match panel.offset(1, 0) {
                     Ok(mut adjacent) => {
                         adjacent.exits_backtrack |= Exits::WEST;
@@ -245,12 +406,148 @@ impl Field {
         }
     }
 
+    fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    fn checked_index(&self, Pos { x, y }: Pos) -> usize {
+        // do bounds checks
+        assert!(x < self.width, "x ({}) is out of bounds ", x);
+        assert!(y < self.height, "y ({}) is out of bounds ", y);
+
+        self.flatten_index(x, y)
+    }
+
     fn flatten_index(&self, x: usize, y: usize) -> usize {
         // flatten
         y * self.width + x
     }
 }
 
+/// Creates a [`Field`] from rows of [`PanelKind`] names.
+///
+/// Each panel may be followed by `+` and the names of its [`Exits`]. Every row
+/// must have the same number of panels.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, Exits, PanelKind::*};
+///
+/// let field = field![
+///     [Home + EAST, Draw + SOUTH],
+///     [Empty, Bonus + WEST + NORTH],
+/// ];
+///
+/// assert_eq!(field.width(), 2);
+/// assert_eq!(field.get((1, 1)).kind, Bonus);
+/// assert!(field.get((1, 1)).exits == Exits::WEST | Exits::NORTH);
+/// ```
+///
+/// # Panics
+/// Will panic if the rows do not all have the same length.
+#[macro_export]
+macro_rules! field {
+    ($([$($kind:ident $(+ $exit:ident)*),* $(,)?]),* $(,)?) => {
+        $crate::Field::new_slice(&[
+            $(&[$({
+                #[allow(unused_mut)]
+                let mut panel = $crate::Panel::new($crate::PanelKind::$kind);
+                $(panel.exits |= $crate::Exits::$exit;)*
+                panel
+            }),*]),*
+        ])
+    };
+}
+
+/// Builds a [`Field`] one panel or rectangle at a time.
+///
+/// Every position is checked as it is given, so mistakes panic at the call
+/// that made them, instead of when the field is used.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, PanelKind::*, Exits};
+///
+/// let field = Field::builder(3, 2)
+///     .fill_rect(0, 0, 3, 1, Neutral)
+///     .set(0, 0, Home)
+///     .with_exits(0, 0, Exits::EAST)
+///     .with_exits(1, 0, Exits::EAST)
+///     .build();
+///
+/// assert_eq!(field.get((0, 0)).kind, Home);
+/// assert_eq!(field.get((2, 0)).kind, Neutral);
+/// assert_eq!(field.get((2, 1)).kind, Empty);
+/// assert!(field.get((1, 0)).exits.has(Exits::EAST));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FieldBuilder {
+    field: Field,
+}
+
+impl FieldBuilder {
+    /// Creates a builder for a field of empty panels.
+    pub fn new(width: usize, height: usize) -> FieldBuilder {
+        FieldBuilder {
+            field: Field::new_vec(vec![Panel::EMPTY; width * height], width, height),
+        }
+    }
+
+    /// Sets the kind of every panel.
+    pub fn fill(mut self, kind: PanelKind) -> FieldBuilder {
+        for (_, _, panel) in self.field.panels_mut() {
+            panel.kind = kind;
+        }
+
+        self
+    }
+
+    /// Sets the kind of a panel.
+    ///
+    /// # Panics
+    /// Will panic if the position is out of bounds.
+    pub fn set(mut self, x: usize, y: usize, kind: PanelKind) -> FieldBuilder {
+        self.field.get_mut((x, y)).kind = kind;
+        self
+    }
+
+    /// Sets the kind of every panel in a rectangle, with its top-left corner
+    /// at `(x, y)`.
+    ///
+    /// # Panics
+    /// Will panic if any part of the rectangle is out of bounds.
+    pub fn fill_rect(
+        mut self,
+        x: usize, y: usize,
+        width: usize, height: usize,
+        kind: PanelKind,
+    ) -> FieldBuilder {
+        let rect = Rect::new(x, y, width, height);
+        assert!(rect.fits(self.field.width(), self.field.height()),
+            "rectangle {} is out of bounds", rect);
+
+        for pos in rect.positions() {
+            self.field[pos].kind = kind;
+        }
+
+        self
+    }
+
+    /// Sets the exits of a panel.
+    ///
+    /// # Panics
+    /// Will panic if the position is out of bounds.
+    pub fn with_exits(mut self, x: usize, y: usize, exits: Exits) -> FieldBuilder {
+        self.field.get_mut((x, y)).exits = exits;
+        self
+    }
+
+    /// Finishes the field.
+    pub fn build(self) -> Field {
+        self.field
+    }
+}
+
 /// Used to refer to a panel on a field.
 pub struct PanelRef<'a> {
     field: &'a Field,
@@ -276,6 +573,11 @@ impl<'a> PanelRef<'a> {
 
         PanelRef { field, x, y }
     }
+
+    /// Gets the position of the panel.
+    pub fn pos(&self) -> Pos {
+        Pos::new(self.x, self.y)
+    }
     
     /// Offsets a `PanelRef` by a certain vector, returning `Err(self)` if it 
     /// would index out of bounds.
@@ -288,6 +590,74 @@ impl<'a> PanelRef<'a> {
             None => Err(PanelRef { field, x, y }),
         }
     }
+
+    /// Gets the panel to the north, if there is one.
+    pub fn north(&self) -> Option<PanelRef<'a>> {
+        self.neighbor(Direction::North)
+    }
+
+    /// Gets the panel to the south, if there is one.
+    pub fn south(&self) -> Option<PanelRef<'a>> {
+        self.neighbor(Direction::South)
+    }
+
+    /// Gets the panel to the east, if there is one.
+    pub fn east(&self) -> Option<PanelRef<'a>> {
+        self.neighbor(Direction::East)
+    }
+
+    /// Gets the panel to the west, if there is one.
+    pub fn west(&self) -> Option<PanelRef<'a>> {
+        self.neighbor(Direction::West)
+    }
+
+    /// Gets an iterator over the adjacent panels, in the order north, east,
+    /// south, west.
+    ///
+    /// Panels on the edges of the field have fewer neighbors.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{Field, Panel, PanelKind::*};
+    ///
+    /// let field = Field::new_slice(&[
+    ///     &[Panel::new(Draw), Panel::new(Encounter)],
+    ///     &[Panel::new(Bonus), Panel::new(Drop)],
+    /// ]);
+    ///
+    /// let kinds = field.get((0, 0))
+    ///     .neighbors()
+    ///     .map(|panel| panel.kind)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(kinds, [Encounter, Bonus]);
+    /// ```
+    pub fn neighbors(&self) -> impl Iterator<Item = PanelRef<'a>> {
+        let panel = PanelRef { field: self.field, x: self.x, y: self.y };
+
+        NEIGHBORS.iter()
+            .filter_map(move |dir| panel.neighbor(*dir))
+    }
+
+    /// Gets the panel a step away in a direction, if there is one.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{Direction, Field, Panel, PanelKind::*};
+    ///
+    /// let field = Field::new_slice(&[
+    ///     &[Panel::new(Draw), Panel::new(Encounter)],
+    /// ]);
+    ///
+    /// assert_eq!(field.get((0, 0)).neighbor(Direction::East).unwrap().kind, Encounter);
+    /// assert!(field.get((0, 0)).neighbor(Direction::West).is_none());
+    /// ```
+    pub fn neighbor(&self, dir: Direction) -> Option<PanelRef<'a>> {
+        let (x_offset, y_offset) = dir.delta();
+
+        offset_common(self.field, self.x, self.y, x_offset, y_offset)
+            .map(|(x, y)| PanelRef { field: self.field, x, y })
+    }
 }
 
 impl<'a> Deref for PanelRef<'a> {
@@ -310,6 +680,11 @@ impl<'a> PanelMut<'a> {
 
         PanelMut { field, x, y }
     }
+
+    /// Gets the position of the panel.
+    pub fn pos(&self) -> Pos {
+        Pos::new(self.x, self.y)
+    }
     
     /// Offsets a `PanelMut` by a certain vector, returning `Err(self)` if it 
     /// would index out of bounds.
@@ -322,6 +697,43 @@ impl<'a> PanelMut<'a> {
             None => Err(PanelMut { field, x, y }),
         }
     }
+
+    /// Gets the panel to the north mutably, if there is one.
+    pub fn north(&mut self) -> Option<PanelMut<'_>> {
+        self.neighbor(Direction::North)
+    }
+
+    /// Gets the panel to the south mutably, if there is one.
+    pub fn south(&mut self) -> Option<PanelMut<'_>> {
+        self.neighbor(Direction::South)
+    }
+
+    /// Gets the panel to the east mutably, if there is one.
+    pub fn east(&mut self) -> Option<PanelMut<'_>> {
+        self.neighbor(Direction::East)
+    }
+
+    /// Gets the panel to the west mutably, if there is one.
+    pub fn west(&mut self) -> Option<PanelMut<'_>> {
+        self.neighbor(Direction::West)
+    }
+
+    /// Gets an iterator over the adjacent panels, in the order north, east,
+    /// south, west.
+    ///
+    /// Only one panel can be borrowed mutably at a time, so the neighbors are
+    /// immutable; use [`PanelMut::north`] and friends to modify them.
+    pub fn neighbors(&self) -> impl Iterator<Item = PanelRef<'_>> {
+        PanelRef { field: &*self.field, x: self.x, y: self.y }.neighbors()
+    }
+
+    /// Gets the panel a step away in a direction mutably, if there is one.
+    pub fn neighbor(&mut self, dir: Direction) -> Option<PanelMut<'_>> {
+        let (x_offset, y_offset) = dir.delta();
+
+        offset_common(self.field, self.x, self.y, x_offset, y_offset)
+            .map(move |(x, y)| PanelMut { field: &mut *self.field, x, y })
+    }
 }
 
 impl<'a> Deref for PanelMut<'a> {
@@ -340,6 +752,53 @@ impl<'a> DerefMut for PanelMut<'a> {
     }
 }
 
+impl Index<Pos> for Field {
+    type Output = Panel;
+
+    fn index(&self, pos: Pos) -> &Panel {
+        &self.data[self.checked_index(pos)]
+    }
+}
+
+impl IndexMut<Pos> for Field {
+    fn index_mut(&mut self, pos: Pos) -> &mut Panel {
+        let idx = self.checked_index(pos);
+        &mut self.data[idx]
+    }
+}
+
+/// Shows the dimensions of the field, and each row as the bytes of its panels
+/// in the [`.fldx`](crate::format::fldx) format: the kind, then the exits.
+impl Debug for Field {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        struct Row<'a>(&'a [Panel]);
+
+        impl Debug for Row<'_> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult {
+                f.write_str("\"")?;
+
+                for (idx, panel) in self.0.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(" ")?;
+                    }
+
+                    write!(f, "{:02x}{:02x}", u8::from(panel.kind), panel.exits_internal())?;
+                }
+
+                f.write_str("\"")
+            }
+        }
+
+        let rows = self.data.chunks(self.width.max(1)).map(Row).collect::<Vec<_>>();
+
+        f.debug_struct("Field")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("rows", &rows)
+            .finish()
+    }
+}
+
 impl<'a> Debug for PanelRef<'a> {
     fn fmt(&self, f: &mut Formatter) -> FmtResult { f.write_str("PanelRef") }
 }
@@ -348,26 +807,132 @@ impl<'a> Debug for PanelMut<'a> {
     fn fmt(&self, f: &mut Formatter) -> FmtResult { f.write_str("PanelMut") }
 }
 
+/// Serialized form of a [`Field`].
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(rename = "Field")]
+struct FieldSer<'a> {
+    width: usize,
+    height: usize,
+    panels: &'a [Panel],
+}
+
+/// Deserialized form of a [`Field`], before it is validated.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "Field")]
+struct FieldDe {
+    width: usize,
+    height: usize,
+    panels: Vec<Panel>,
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Field {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        FieldSer {
+            width: self.width,
+            height: self.height,
+            panels: &self.data,
+        }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D>(deserializer: D) -> Result<Field, D::Error>
+    where D: Deserializer<'de> {
+        use serde::de::Error as _;
+
+        let FieldDe { width, height, panels } = FieldDe::deserialize(deserializer)?;
+
+        // verify we can make a field from this
+        match width.checked_mul(height) {
+            Some(len) if panels.len() == len => Ok(Field::new_vec(panels, width, height)),
+            Some(len) => Err(D::Error::custom(format!(
+                "expected {} panels for a {}x{} field, got {}",
+                len, width, height, panels.len(),
+            ))),
+            None => Err(D::Error::custom(format!(
+                "a {}x{} field is too large",
+                width, height,
+            ))),
+        }
+    }
+}
+
+/// The offsets of a panel's neighbors, in the order north, east, south,
+/// west.
+const NEIGHBORS: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
 #[inline]
 fn offset_common(
     field: &Field, 
     x: usize, y: usize, 
     xo: i64, yo: i64,
 ) -> Option<(usize, usize)> {
-    // offset
-    let x = (x as i64) + xo;
-    let y = (y as i64) + yo;
+    Pos::new(x, y)
+        .offset(xo, yo)
+        .filter(|pos| field.contains(*pos))
+        .map(Pos::into)
+}
 
-    if x >= 0 && y >= 0 {
-        let x = x as usize;
-        let y = y as usize;
+/// The largest fields made by the `arbitrary` and `proptest` features, in
+/// either direction, unless told otherwise.
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub const ARBITRARY_MAX_SIZE: usize = 12;
 
-        if x < field.width() && y < field.height() {
-            Some((x, y))
-        } else {
-            None
+/// Makes fields from one to [`ARBITRARY_MAX_SIZE`] panels wide and tall.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Field {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Field> {
+        let width = u.int_in_range(1..=ARBITRARY_MAX_SIZE)?;
+        let height = u.int_in_range(1..=ARBITRARY_MAX_SIZE)?;
+
+        let data = (0..width * height)
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<Vec<Panel>>>()?;
+
+        Ok(Field::new_vec(data, width, height))
+    }
+}
+
+/// The largest fields to make with `proptest`.
+#[cfg(feature = "proptest")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldParams {
+    pub max_width: usize,
+    pub max_height: usize,
+}
+
+#[cfg(feature = "proptest")]
+impl Default for FieldParams {
+    fn default() -> FieldParams {
+        FieldParams {
+            max_width: ARBITRARY_MAX_SIZE,
+            max_height: ARBITRARY_MAX_SIZE,
         }
-    } else {
-        None
+    }
+}
+
+/// Makes fields at least one panel wide and tall, up to the size in the
+/// [`FieldParams`].
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Field {
+    type Parameters = FieldParams;
+    type Strategy = proptest::strategy::BoxedStrategy<Field>;
+
+    fn arbitrary_with(params: FieldParams) -> Self::Strategy {
+        use proptest::arbitrary::any;
+        use proptest::collection::vec;
+        use proptest::strategy::Strategy as _;
+
+        (1..=params.max_width.max(1), 1..=params.max_height.max(1))
+            .prop_flat_map(|(width, height)| {
+                vec(any::<Panel>(), width * height)
+                    .prop_map(move |data| Field::new_vec(data, width, height))
+            })
+            .boxed()
     }
 }
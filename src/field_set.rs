@@ -0,0 +1,188 @@
+//! Fields with multiple states.
+//!
+//! Some boards change their layout in the middle of a game. A [`FieldSet`]
+//! keeps every layout of such a board together, along with labeled
+//! transitions between them.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{Field, FieldSet, Panel, PanelKind::*};
+//!
+//! let mut set = FieldSet::new("day", Field::new_slice(&[
+//!     &[Panel::new(Home), Panel::new(Bonus)],
+//! ]));
+//!
+//! let night = set.push_state("night", Field::new_slice(&[
+//!     &[Panel::new(Home), Panel::new(Encounter)],
+//! ]));
+//!
+//! set.add_transition(0, night, "sunset");
+//! set.add_transition(night, 0, "sunrise");
+//!
+//! // the sun sets...
+//! set.transition("sunset").unwrap();
+//! assert_eq!(set.current().name, "night");
+//! assert_eq!(set.current().field.get((1, 0)).kind, Encounter);
+//! ```
+
+use crate::Field;
+
+/// A single layout of a [`FieldSet`].
+#[derive(Clone)]
+pub struct State {
+    /// The name of the state.
+    pub name: String,
+    /// The layout of the field in this state.
+    pub field: Field,
+}
+
+/// A labeled transition between two states of a [`FieldSet`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+    /// The index of the state this transition starts from.
+    pub from: usize,
+    /// The index of the state this transition ends at.
+    pub to: usize,
+    /// The label of the transition.
+    pub label: String,
+}
+
+/// A field with multiple states.
+///
+/// A set always has at least one state, and keeps track of which state is
+/// current.
+#[derive(Clone)]
+pub struct FieldSet {
+    states: Vec<State>,
+    transitions: Vec<Transition>,
+    current: usize,
+}
+
+impl FieldSet {
+    /// Creates a new set with an initial state.
+    pub fn new<S>(name: S, field: Field) -> FieldSet
+    where S: Into<String> {
+        FieldSet {
+            states: vec![State { name: name.into(), field }],
+            transitions: Vec::new(),
+            current: 0,
+        }
+    }
+
+    /// Creates a set from its parts.
+    ///
+    /// # Panics
+    /// Will panic if there are no states, or if `current` or any transition
+    /// refers to a state that does not exist.
+    pub fn from_parts(states: Vec<State>, transitions: Vec<Transition>, current: usize) -> FieldSet {
+        assert!(!states.is_empty(), "a field set must have at least one state");
+        assert!(current < states.len(), "current state ({}) is out of bounds", current);
+
+        for transition in transitions.iter() {
+            assert!(
+                transition.from < states.len() && transition.to < states.len(),
+                "transition {:?} is out of bounds", transition.label,
+            );
+        }
+
+        FieldSet { states, transitions, current }
+    }
+
+    /// Adds a new state, returning its index.
+    pub fn push_state<S>(&mut self, name: S, field: Field) -> usize
+    where S: Into<String> {
+        self.states.push(State { name: name.into(), field });
+        self.states.len() - 1
+    }
+
+    /// Adds a labeled transition between two states.
+    ///
+    /// # Panics
+    /// Will panic if either state does not exist.
+    pub fn add_transition<S>(&mut self, from: usize, to: usize, label: S)
+    where S: Into<String> {
+        assert!(from < self.states.len(), "from ({}) is out of bounds", from);
+        assert!(to < self.states.len(), "to ({}) is out of bounds", to);
+
+        self.transitions.push(Transition { from, to, label: label.into() });
+    }
+
+    /// Gets how many states are in the set.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Always `false`; a set has at least one state.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Gets a state by index.
+    pub fn get(&self, index: usize) -> Option<&State> {
+        self.states.get(index)
+    }
+
+    /// Gets a state by index mutably.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut State> {
+        self.states.get_mut(index)
+    }
+
+    /// Finds the index of a state by name.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|state| state.name == name)
+    }
+
+    /// Gets the index of the current state.
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Gets the current state.
+    pub fn current(&self) -> &State {
+        &self.states[self.current]
+    }
+
+    /// Gets the current state mutably.
+    pub fn current_mut(&mut self) -> &mut State {
+        &mut self.states[self.current]
+    }
+
+    /// Switches directly to a state, ignoring transitions.
+    ///
+    /// # Panics
+    /// Will panic if the state does not exist.
+    pub fn switch_to(&mut self, index: usize) -> &State {
+        assert!(index < self.states.len(), "index ({}) is out of bounds", index);
+
+        self.current = index;
+        self.current()
+    }
+
+    /// Follows a transition from the current state by its label.
+    ///
+    /// Returns `None` and stays in the current state if there is no such
+    /// transition.
+    pub fn transition(&mut self, label: &str) -> Option<&State> {
+        let to = self.transitions_from(self.current)
+            .find(|transition| transition.label == label)?
+            .to;
+
+        Some(self.switch_to(to))
+    }
+
+    /// Gets an iterator over all of the states, in order.
+    pub fn states(&self) -> impl DoubleEndedIterator<Item = &State> + ExactSizeIterator {
+        self.states.iter()
+    }
+
+    /// Gets an iterator over all of the transitions.
+    pub fn transitions(&self) -> impl DoubleEndedIterator<Item = &Transition> + ExactSizeIterator {
+        self.transitions.iter()
+    }
+
+    /// Gets an iterator over the transitions starting from a state.
+    pub fn transitions_from(&self, index: usize) -> impl DoubleEndedIterator<Item = &Transition> {
+        self.transitions.iter()
+            .filter(move |transition| transition.from == index)
+    }
+}
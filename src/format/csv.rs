@@ -0,0 +1,262 @@
+//! Support for CSV panel grids.
+//!
+//! The CSV format is meant as a bridge to spreadsheet software. It is made up
+//! of two sections, separated by an empty line; the second section is
+//! optional.
+//!
+//! The first section has one row per field row, and each cell holds the
+//! [abbreviation](PanelKind::abbreviation) of the panel's kind, the same one
+//! the [`Display`][1] impl of [`Field`] uses. Empty panels are empty cells,
+//! written as `""` when they are the only cell of a row, so the row is not
+//! mistaken for the empty line between sections. Kinds this crate does not
+//! know have no abbreviation, so they are written as their numeric value, like
+//! `254`.
+//!
+//! The second section has the same shape, and each cell holds the panel's
+//! exits as a string of direction letters (`N`, `E`, `S`, `W`). If the panel
+//! has any Backtrack exits, they follow a `/`. For example, `NE/S` is a panel
+//! that exits north and east, and exits south during Backtrack.
+//!
+//! ```text
+//! @@,da,bs
+//! en,,dr
+//!
+//! E,EW,S
+//! N,,W
+//! ```
+//!
+//! The sections can also be encoded and decoded separately, for users that
+//! prefer keeping them as two files.
+//!
+//! [1]: std::fmt::Display
+
+use crate::{Direction, Field, Panel, PanelKind, Exits};
+
+use std::io::{Read, Write, Error, ErrorKind};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::convert::TryFrom;
+
+
+/// An error that indicates a cell that could not be understood.
+#[derive(Debug)]
+pub struct InvalidCell {
+    /// The zero-indexed row of the cell, relative to its section.
+    pub row: usize,
+    /// The zero-indexed column of the cell.
+    pub column: usize,
+    /// The contents of the cell.
+    pub value: String,
+}
+
+impl Display for InvalidCell {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f, "invalid cell {:?} at row {}, column {}",
+            self.value, self.row, self.column,
+        )
+    }
+}
+
+impl std::error::Error for InvalidCell { }
+
+/// Encode a field to CSV, with both the kind and exits sections.
+pub fn encode<T>(field: &Field, mut output: T) -> Result<(), Error>
+where T: Write {
+    encode_kinds(field, &mut output)?;
+    output.write_all(b"\n")?;
+    encode_exits(field, &mut output)
+}
+
+/// Encode only the kind section of a field to CSV.
+pub fn encode_kinds<T>(field: &Field, output: T) -> Result<(), Error>
+where T: Write {
+    encode_section(field, output, |panel, cell| {
+        match panel.kind {
+            PanelKind::Empty => (),
+            PanelKind::Unknown(value) => cell.push_str(&value.to_string()),
+            kind => cell.push_str(kind.abbreviation()),
+        }
+    })
+}
+
+/// Encode only the exits section of a field to CSV.
+pub fn encode_exits<T>(field: &Field, output: T) -> Result<(), Error>
+where T: Write {
+    encode_section(field, output, |panel, cell| {
+        push_exits(cell, panel.exits);
+
+        if panel.exits_backtrack != Exits::none() {
+            cell.push('/');
+            push_exits(cell, panel.exits_backtrack);
+        }
+    })
+}
+
+/// Decode a field from CSV.
+///
+/// If the exits section is missing, all panels will have no exits.
+pub fn decode<T>(mut input: T) -> Result<Field, Error>
+where T: Read {
+    let mut data = String::new();
+    input.read_to_string(&mut data)?;
+
+    // split sections at the first empty line
+    let mut lines = data.lines();
+    let kinds = lines.by_ref()
+        .take_while(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+    let exits = lines
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>();
+
+    decode_sections(&kinds, if exits.is_empty() { None } else { Some(&exits) })
+}
+
+/// Decode a field from separate kind and exits CSV sections.
+pub fn decode_separate<K, E>(mut kinds: K, mut exits: E) -> Result<Field, Error>
+where K: Read, E: Read {
+    let mut kinds_data = String::new();
+    kinds.read_to_string(&mut kinds_data)?;
+    let mut exits_data = String::new();
+    exits.read_to_string(&mut exits_data)?;
+
+    let kinds = non_empty_lines(&kinds_data);
+    let exits = non_empty_lines(&exits_data);
+
+    decode_sections(&kinds, Some(&exits))
+}
+
+fn decode_sections(kinds: &[&str], exits: Option<&[&str]>) -> Result<Field, Error> {
+    // parse kinds
+    let kinds = parse_section(kinds, |cell| {
+        match cell {
+            "" => Some(PanelKind::Empty),
+            cell => PanelKind::from_abbreviation(cell)
+                .or_else(|| cell.parse::<u8>().ok().map(PanelKind::from)),
+        }
+    })?;
+
+    let height = kinds.len();
+    let width = kinds.first().map(|row| row.len()).unwrap_or(0);
+
+    let mut data = kinds.into_iter()
+        .flatten()
+        .map(Panel::new)
+        .collect::<Vec<_>>();
+
+    // parse exits
+    if let Some(exits) = exits {
+        let exits = parse_section(exits, |cell| {
+            let mut parts = cell.splitn(2, '/');
+            let exits = parse_exits(parts.next().unwrap_or(""))?;
+            let exits_backtrack = parse_exits(parts.next().unwrap_or(""))?;
+
+            Some((exits, exits_backtrack))
+        })?;
+
+        let exits_height = exits.len();
+        let exits_width = exits.first().map(|row| row.len()).unwrap_or(0);
+
+        if exits_width != width || exits_height != height {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "exits section is {}x{}, but kinds section is {}x{}",
+                    exits_width, exits_height, width, height,
+                ),
+            ));
+        }
+
+        for (panel, (exits, exits_backtrack)) in data.iter_mut().zip(exits.into_iter().flatten()) {
+            panel.exits = exits;
+            panel.exits_backtrack = exits_backtrack;
+        }
+    }
+
+    Ok(Field::new_vec(data, width, height))
+}
+
+fn encode_section<T, F>(field: &Field, mut output: T, mut f: F) -> Result<(), Error>
+where T: Write, F: FnMut(&Panel, &mut String) {
+    let mut line = String::new();
+
+    for y in 0..field.height() {
+        line.clear();
+
+        for x in 0..field.width() {
+            if x > 0 {
+                line.push(',');
+            }
+
+            f(&field.get((x, y)), &mut line);
+        }
+
+        // a blank line would end the section, so quote the empty cell of a
+        // row one cell wide
+        if line.is_empty() {
+            line.push_str("\"\"");
+        }
+
+        line.push('\n');
+        output.write_all(line.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn parse_section<T, F>(lines: &[&str], f: F) -> Result<Vec<Vec<T>>, Error>
+where F: Fn(&str) -> Option<T> {
+    let mut rows = Vec::with_capacity(lines.len());
+
+    for (row, line) in lines.iter().enumerate() {
+        let cells = line.split(',')
+            .enumerate()
+            .map(|(column, cell)| {
+                // spreadsheets may quote cells
+                let cell = cell.trim().trim_matches('"').trim();
+
+                f(cell).ok_or_else(|| Error::new(
+                    ErrorKind::InvalidData,
+                    InvalidCell { row, column, value: cell.to_owned() },
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // verify the rows are all the same length
+        if let Some(first) = rows.first().map(|r: &Vec<T>| r.len()) {
+            if cells.len() != first {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "row {} has {} cells, expected {}",
+                        row, cells.len(), first,
+                    ),
+                ));
+            }
+        }
+
+        rows.push(cells);
+    }
+
+    Ok(rows)
+}
+
+fn non_empty_lines(data: &str) -> Vec<&str> {
+    data.lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect()
+}
+
+fn push_exits(cell: &mut String, exits: Exits) {
+    cell.extend(exits.written().map(Direction::letter));
+}
+
+fn parse_exits(data: &str) -> Option<Exits> {
+    let mut exits = Exits::none();
+
+    for c in data.chars() {
+        exits |= Direction::try_from(c).ok()?.to_exits();
+    }
+
+    Some(exits)
+}
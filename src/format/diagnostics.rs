@@ -0,0 +1,162 @@
+use super::Substitution;
+
+use crate::{Direction, Field, PanelKind, Pos};
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// A problem with a field that did not stop it from being decoded.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A panel of an unknown kind was replaced by
+    /// [`DecodeOptions::placeholder`](super::DecodeOptions::placeholder).
+    Substituted(Substitution),
+    /// There was data after the end of the field.
+    TrailingData {
+        offset: u64,
+        len: u64,
+    },
+    /// An empty panel has exits.
+    EmptyWithExits {
+        x: usize,
+        y: usize,
+    },
+    /// An empty panel is surrounded by panels on all four sides.
+    Hole {
+        x: usize,
+        y: usize,
+    },
+    /// A panel has an exit that leads off the field or into an empty panel.
+    DanglingExit {
+        x: usize,
+        y: usize,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Warning::Substituted(s) => write!(
+                f, "panel at ({}, {}) has unknown kind {}, and was replaced",
+                s.x, s.y, s.value
+            ),
+            Warning::TrailingData { offset, len } => {
+                write!(f, "{} bytes of unexpected data after the field at byte {}", len, offset)
+            },
+            Warning::EmptyWithExits { x, y } => {
+                write!(f, "empty panel at ({}, {}) has exits", x, y)
+            },
+            Warning::Hole { x, y } => {
+                write!(f, "empty panel at ({}, {}) is surrounded by panels", x, y)
+            },
+            Warning::DanglingExit { x, y } => {
+                write!(f, "panel at ({}, {}) has an exit that leads nowhere", x, y)
+            },
+        }
+    }
+}
+
+/// A collection of [`Warning`]s found while decoding fields.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*, Exits};
+/// use citrus_common::format::{fldx, DecodeOptions, Diagnostics, Warning};
+///
+/// let mut home = Panel::new(Home);
+/// home.exits = Exits::NORTH;
+///
+/// let field = Field::new_slice(&[
+///     &[home, Panel::new(Draw)],
+/// ]);
+///
+/// let mut data = Vec::new();
+/// fldx::encode(&field, &mut data).unwrap();
+///
+/// let mut diagnostics = Diagnostics::new();
+/// fldx::decode_with_diagnostics(&data[..], &DecodeOptions::default(), &mut diagnostics)
+///     .unwrap();
+///
+/// assert_eq!(diagnostics.warnings(), &[Warning::DanglingExit { x: 0, y: 0 }]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    warnings: Vec<Warning>,
+}
+
+impl Diagnostics {
+    /// Creates a new, empty collection.
+    pub const fn new() -> Diagnostics {
+        Diagnostics {
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Adds a warning.
+    pub fn push(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    /// Gets all of the warnings, in the order they were found.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Gets how many warnings there are.
+    pub fn len(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// Checks if there are no warnings.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Removes all of the warnings.
+    pub fn clear(&mut self) {
+        self.warnings.clear();
+    }
+
+    /// Checks a decoded field for suspicious panels.
+    pub fn inspect(&mut self, field: &Field) {
+        // positions off the field count as empty
+        let is_empty = |pos: Pos, dir: Direction| match pos.step(dir) {
+            Some(to) if field.contains(to) => field[to].kind == PanelKind::Empty,
+            _ => true,
+        };
+
+        for pos in field.positions() {
+            let panel = &field[pos];
+            let exits = panel.exits | panel.exits_backtrack;
+            let Pos { x, y } = pos;
+
+            if panel.kind == PanelKind::Empty {
+                if !exits.is_empty() {
+                    self.push(Warning::EmptyWithExits { x, y });
+                }
+
+                if Direction::ALL.iter().all(|dir| !is_empty(pos, *dir)) {
+                    self.push(Warning::Hole { x, y });
+                }
+            } else if exits.directions().any(|dir| is_empty(pos, dir)) {
+                self.push(Warning::DanglingExit { x, y });
+            }
+        }
+    }
+}
+
+impl Extend<Warning> for Diagnostics {
+    fn extend<I>(&mut self, iter: I)
+    where I: IntoIterator<Item = Warning> {
+        self.warnings.extend(iter);
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Warning;
+    type IntoIter = std::slice::Iter<'a, Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.iter()
+    }
+}
@@ -0,0 +1,140 @@
+use std::io;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// An error that occurred while decoding field data.
+///
+/// Most errors carry the byte offset of the failure from the start of the
+/// input, so broken files can be inspected with a hex editor. For compressed
+/// or text-wrapped data, offsets count the decoded bytes.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input ended before the data was complete.
+    UnexpectedEof {
+        offset: u64,
+    },
+    /// A panel has a kind this crate does not know.
+    UnknownPanelKind {
+        offset: u64,
+        value: u8,
+    },
+    /// The data has a different number of panels than its dimensions say.
+    SizeMismatch {
+        offset: u64,
+        expected: usize,
+        got: usize,
+    },
+    /// The data does not match its checksum, and was likely corrupted or
+    /// truncated.
+    ChecksumMismatch {
+        offset: u64,
+        expected: u32,
+        got: u32,
+    },
+    /// The data was written by a newer version of the format.
+    UnsupportedVersion {
+        offset: u64,
+        version: u32,
+        /// The latest version this crate supports.
+        supported: u32,
+    },
+    /// The field is larger than [`DecodeOptions`](super::DecodeOptions)
+    /// allow.
+    TooLarge {
+        offset: u64,
+    },
+    /// There is more data after the end of the field, and
+    /// [`DecodeOptions::strict`](super::DecodeOptions::strict) is set.
+    TrailingData {
+        offset: u64,
+    },
+    /// The data is malformed in some other way.
+    InvalidData {
+        offset: u64,
+        reason: String,
+    },
+    /// The underlying reader failed.
+    Io(io::Error),
+}
+
+impl DecodeError {
+    /// Gets the byte offset of the failure, if it is known.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            DecodeError::UnexpectedEof { offset }
+            | DecodeError::UnknownPanelKind { offset, .. }
+            | DecodeError::SizeMismatch { offset, .. }
+            | DecodeError::ChecksumMismatch { offset, .. }
+            | DecodeError::UnsupportedVersion { offset, .. }
+            | DecodeError::TooLarge { offset }
+            | DecodeError::TrailingData { offset }
+            | DecodeError::InvalidData { offset, .. } => Some(*offset),
+            DecodeError::Io(_) => None,
+        }
+    }
+
+    pub(super) fn invalid<S>(offset: u64, reason: S) -> DecodeError
+    where S: Into<String> {
+        DecodeError::InvalidData { offset, reason: reason.into() }
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            DecodeError::UnexpectedEof { offset } => {
+                write!(f, "unexpected end of data at byte {}", offset)
+            },
+            DecodeError::UnknownPanelKind { offset, value } => {
+                write!(f, "unknown panel kind {} at byte {}", value, offset)
+            },
+            DecodeError::SizeMismatch { offset, expected, got } => write!(
+                f, "invalid size of data at byte {}, expected {} panels, got {} panels",
+                offset, expected, got
+            ),
+            DecodeError::ChecksumMismatch { offset, expected, got } => write!(
+                f, "data is corrupted at byte {}, expected checksum {:#010x}, got {:#010x}",
+                offset, expected, got
+            ),
+            DecodeError::UnsupportedVersion { offset, version, supported } => write!(
+                f, "unsupported version {} at byte {}, expected at most {}",
+                version, offset, supported
+            ),
+            DecodeError::TooLarge { offset } => {
+                write!(f, "field at byte {} is larger than allowed", offset)
+            },
+            DecodeError::TrailingData { offset } => {
+                write!(f, "unexpected data after the field at byte {}", offset)
+            },
+            DecodeError::InvalidData { offset, reason } => {
+                write!(f, "{} at byte {}", reason, offset)
+            },
+            DecodeError::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> DecodeError {
+        DecodeError::Io(e)
+    }
+}
+
+impl From<DecodeError> for io::Error {
+    fn from(e: DecodeError) -> io::Error {
+        match e {
+            DecodeError::Io(e) => e,
+            DecodeError::UnexpectedEof { .. } => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
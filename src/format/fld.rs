@@ -28,76 +28,383 @@ use super::*;
 
 use crate::{Field, Panel, PanelKind};
 
-use std::io::{Read, Write, Error, ErrorKind};
-use std::convert::TryFrom as _;
+use std::io::{Read, Write, Error, BufReader};
+use std::fs::File;
+use std::path::Path;
 
 /// A square field with the dimensions `15x15`.
 ///
 /// Applies to Training Program,
 pub const S15: (usize, usize) = (15, 15);
 
+/// The dimensions of official fields, preferred when guessing dimensions.
+const KNOWN_DIMS: &[(usize, usize)] = &[S15];
+
 /// Encode a field to the `.fld` format.
 ///
 /// If successful, returns a tuple of the field's dimensions.
-pub fn encode<T>(field: &Field, mut output: T) -> Result<(usize, usize), Error>
+pub fn encode<T>(field: &Field, output: T) -> Result<(usize, usize), Error>
+where T: Write {
+    encode_panels(field, None, output)
+}
+
+/// Encode a field to the `.fld` format, restoring the unused bytes of a field
+/// decoded with [`decode_raw`].
+///
+/// If the field has not changed, the output is identical to the original
+/// file. If its dimensions have changed, the unused bytes are zeroed.
+pub fn encode_raw<T>(raw: &RawField, output: T) -> Result<(usize, usize), Error>
+where T: Write {
+    let same_dims = (raw.field.width(), raw.field.height()) == raw.dims;
+
+    encode_panels(&raw.field, Some(&raw.unused).filter(|_| same_dims), output)
+}
+
+fn encode_panels<T>(
+    field: &Field,
+    unused: Option<&Vec<[u8; 6]>>,
+    mut output: T,
+) -> Result<(usize, usize), Error>
 where T: Write {
     // encode the field data
-    for (x, y) in field.iter() {
-        let panel = field.get(x, y);
+    for (idx, (x, y)) in field.iter().enumerate() {
+        let panel = field.get((x, y));
+        let u = unused.map(|unused| unused[idx]).unwrap_or_default();
 
-        output.write(&[
-            panel.kind.into(), 0, 0, 0,
-            panel.exits_internal(), 0, 0, 0,
+        output.write_all(&[
+            panel.kind.into(), u[0], u[1], u[2],
+            panel.exits_internal(), u[3], u[4], u[5],
         ])?;
     }
 
     Ok((field.width(), field.height()))
 }
 
+/// Encode a field to a new `Vec` in the `.fld` format.
+pub fn encode_to_vec(field: &Field) -> Vec<u8> {
+    let mut data = Vec::with_capacity(field.width() * field.height() * 8);
+    encode(field, &mut data).expect("writing to a Vec cannot fail");
+
+    data
+}
+
+/// A field decoded from the `.fld` format, along with the bytes of each panel
+/// the format does not use.
+///
+/// Game files do not always zero these bytes, so keeping them allows
+/// [`encode_raw`] to write byte-identical files.
+///
+/// # Examples
+/// ```
+/// use citrus_common::PanelKind;
+/// use citrus_common::format::fld;
+///
+/// let data = [0x02, 0xAA, 0, 0, 0x00, 0, 0, 0xBB];
+///
+/// let mut raw = fld::decode_raw((1, 1), &data[..]).unwrap();
+/// raw.field.get_mut((0, 0)).kind = PanelKind::Draw;
+///
+/// let mut out = Vec::new();
+/// fld::encode_raw(&raw, &mut out).unwrap();
+///
+/// assert_eq!(out, [0x04, 0xAA, 0, 0, 0x00, 0, 0, 0xBB]);
+/// ```
+#[derive(Clone)]
+pub struct RawField {
+    /// The decoded field.
+    pub field: Field,
+    /// The dimensions the field was decoded with.
+    dims: (usize, usize),
+    /// The unused bytes of each panel, row-major.
+    unused: Vec<[u8; 6]>,
+}
+
+impl RawField {
+    /// Gets the unused bytes of a panel, as it was decoded.
+    ///
+    /// Returns `None` if the position was out of bounds of the decoded field.
+    pub fn unused(&self, x: usize, y: usize) -> Option<[u8; 6]> {
+        let (width, height) = self.dims;
+
+        if x < width && y < height {
+            Some(self.unused[y * width + x])
+        } else {
+            None
+        }
+    }
+}
+
 /// Decode a field from the `.fld` format.
 ///
 /// Requires a width and height, as the `.fld` format does not contain this
 /// data. Uses a tuple, so constants can be defined and use for different field 
 /// dimensions.
-pub fn decode<T>(dims: (usize, usize), mut input: T) -> Result<Field, Error>
+pub fn decode<T>(dims: (usize, usize), input: T) -> Result<Field, DecodeError>
+where T: Read {
+    decode_with(dims, input, &DecodeOptions::default())
+}
+
+/// Decode a field from `.fld` data already in memory.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::fld;
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let data = fld::encode_to_vec(&field);
+/// let decoded = fld::decode_from_slice((2, 1), &data).unwrap();
+///
+/// assert_eq!(decoded.get((1, 0)).kind, Draw);
+/// ```
+pub fn decode_from_slice(dims: (usize, usize), data: &[u8]) -> Result<Field, DecodeError> {
+    decode(dims, data)
+}
+
+/// Decode a field from a `.fld` file.
+pub fn decode_file<P>(dims: (usize, usize), path: P) -> Result<Field, DecodeError>
+where P: AsRef<Path> {
+    decode(dims, BufReader::new(File::open(path)?))
+}
+
+/// Decode a field from the `.fld` format, with options.
+pub fn decode_with<T>(
+    dims: (usize, usize),
+    input: T,
+    options: &DecodeOptions,
+) -> Result<Field, DecodeError>
+where T: Read {
+    decode_with_substitutions(dims, input, options).map(|(field, _)| field)
+}
+
+/// Decode a field from the `.fld` format, with options, reporting the panels
+/// replaced by [`DecodeOptions::placeholder`].
+pub fn decode_with_substitutions<T>(
+    dims: (usize, usize),
+    input: T,
+    options: &DecodeOptions,
+) -> Result<(Field, Vec<Substitution>), DecodeError>
+where T: Read {
+    let mut state = State::new(options);
+
+    decode_from(dims, &mut Reader::new(input), options, &mut state, None)
+        .map(|field| (field, state.substitutions))
+}
+
+/// Decode a field from the `.fld` format, with options, collecting warnings
+/// about anything unusual in it.
+pub fn decode_with_diagnostics<T>(
+    dims: (usize, usize),
+    input: T,
+    options: &DecodeOptions,
+    diagnostics: &mut Diagnostics,
+) -> Result<Field, DecodeError>
+where T: Read {
+    let mut state = State::diagnosing(options);
+
+    let field = decode_from(dims, &mut Reader::new(input), options, &mut state, None)?;
+    state.finish(&field, diagnostics);
+
+    Ok(field)
+}
+
+/// Decode a field from the `.fld` format, keeping the bytes the format does
+/// not use.
+pub fn decode_raw<T>(dims: (usize, usize), input: T) -> Result<RawField, DecodeError>
+where T: Read {
+    let options = DecodeOptions::default();
+    let mut unused = Vec::new();
+
+    let field = decode_from(
+        dims,
+        &mut Reader::new(input),
+        &options,
+        &mut State::new(&options),
+        Some(&mut unused),
+    )?;
+
+    Ok(RawField { field, dims, unused })
+}
+
+fn decode_from<T>(
+    dims: (usize, usize),
+    input: &mut Reader<T>,
+    options: &DecodeOptions,
+    state: &mut State,
+    mut unused: Option<&mut Vec<[u8; 6]>>,
+) -> Result<Field, DecodeError>
 where T: Read {
     let (width, height) = dims;
+    let len = options.check_size(width, height, 0)?;
 
     // read data
     let mut data = Vec::<Panel>::new();
 
-    let mut panel_buf = [0u8; 8];
-    
-    while input.read(&mut panel_buf)? != 0 {
-        let panel_kind = match PanelKind::try_from(panel_buf[0]) {
-            Ok(kind) => kind,
-            // throw
-            Err(e) => return Err(Error::new(ErrorKind::InvalidData, e)),
-        };
+    input.read_items(8, len, |panel, offset| {
+        let idx = data.len();
+        let panel_kind = state.parse(panel[0], offset, idx..idx + 1, width)?;
 
         data.push(
-            Panel::from_internal(panel_kind, panel_buf[4])
+            Panel::from_internal(panel_kind, panel[4])
         );
-    }
+
+        if let Some(unused) = unused.as_mut() {
+            unused.push([panel[1], panel[2], panel[3], panel[5], panel[6], panel[7]]);
+        }
+
+        Ok(())
+    })?;
+
+    // count any extra panels without keeping them
+    let offset = input.offset();
+    let got = data.len().saturating_add(input.count_rest(8)?);
     
     // verify we can make a field from this
-    if data.len() == width * height {
+    if got == len {
         Ok(Field::new_vec(data, width, height))
     } else {
-        Err(Error::new(
-            ErrorKind::InvalidData, 
-            InvalidSize::new(width * height, data.len()),
-        ))
+        Err(DecodeError::SizeMismatch {
+            offset,
+            expected: len,
+            got,
+        })
     }
 }
 
+/// Decode a field from the `.fld` format, guessing its dimensions.
+///
+/// The dimensions are guessed from the length of the data with
+/// [`candidate_dims`], and the most plausible guess is used. All of the
+/// candidates are returned along with the field, so the guess can be
+/// corrected.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel};
+/// use citrus_common::format::fld;
+///
+/// let field = Field::new_vec(vec![Panel::EMPTY; 15 * 15], 15, 15);
+///
+/// let mut data = Vec::new();
+/// fld::encode(&field, &mut data).unwrap();
+///
+/// let (decoded, candidates) = fld::decode_auto(&data[..]).unwrap();
+///
+/// assert_eq!((decoded.width(), decoded.height()), (15, 15));
+/// assert!(candidates.contains(&(5, 45)));
+/// ```
+pub fn decode_auto<T>(mut input: T) -> Result<(Field, Vec<(usize, usize)>), DecodeError>
+where T: Read {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    if !data.len().is_multiple_of(8) {
+        return Err(DecodeError::UnexpectedEof { offset: data.len() as u64 });
+    }
+
+    let candidates = candidate_dims(data.len());
+
+    let dims = *candidates.first()
+        .ok_or_else(|| DecodeError::invalid(0, "fld data has no panels"))?;
+
+    decode(dims, &data[..]).map(|field| (field, candidates))
+}
+
+/// Lists the dimensions `.fld` data of `len` bytes could have, most plausible
+/// first.
+///
+/// Dimensions of official fields come first, then the squarest dimensions,
+/// with wider fields before taller ones. The list is empty if the length is
+/// not a whole, non-zero number of panels.
+pub fn candidate_dims(len: usize) -> Vec<(usize, usize)> {
+    if len == 0 || !len.is_multiple_of(8) {
+        return Vec::new();
+    }
+
+    let panels = len / 8;
+
+    let mut candidates = (1..=panels)
+        .filter(|width| panels.is_multiple_of(*width))
+        .map(|width| (width, panels / width))
+        .collect::<Vec<_>>();
+
+    candidates.sort_by_key(|(width, height)| {
+        (!KNOWN_DIMS.contains(&(*width, *height)), width.abs_diff(*height), *height)
+    });
+
+    candidates
+}
+
+/// The `.fld` format, as a [`FieldFormat`].
+///
+/// As the `.fld` format does not carry the field's dimensions, they must be
+/// known ahead of time. Defaults to [`S15`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fld {
+    /// The dimensions of fields decoded with this format.
+    pub dims: (usize, usize),
+}
+
+impl Fld {
+    /// Creates a new `Fld` format for fields of the given dimensions.
+    pub const fn new(dims: (usize, usize)) -> Fld {
+        Fld { dims }
+    }
+}
+
+impl Default for Fld {
+    fn default() -> Fld {
+        Fld::new(S15)
+    }
+}
+
+impl FieldFormat for Fld {
+    fn name(&self) -> &str {
+        "fld"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["fld"]
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        // the data must also fit our dimensions
+        sniff(data) && data.len() == self.dims.0 * self.dims.1 * 8
+    }
+
+    fn encode(&self, field: &Field, output: &mut dyn Write) -> Result<(), Error> {
+        encode(field, output).map(|_| ())
+    }
+
+    fn decode(&self, input: &mut dyn Read) -> Result<Field, DecodeError> {
+        decode(self.dims, input)
+    }
+}
+
+/// Checks if some data looks like a `.fld` file.
+///
+/// The data must be a whole number of panels, where every panel has a known
+/// kind and the unused bytes are zeroed.
+pub fn sniff(data: &[u8]) -> bool {
+    !data.is_empty()
+        && data.len().is_multiple_of(8)
+        && data.chunks(8).all(|panel| {
+            PanelKind::from(panel[0]).is_known()
+                && panel[1..4].iter().all(|b| *b == 0)
+                && panel[5..8].iter().all(|b| *b == 0)
+        })
+}
+
 #[cfg(feature = "base64")]
 use base64::{
     write::EncoderStringWriter,
     read::DecoderReader,
 };
 #[cfg(feature = "base64")]
-use std::io::Cursor;
+use std::io::{Cursor, ErrorKind};
 
 /// Encodes a field to a Base64 string.
 #[cfg(feature = "base64")]
@@ -114,9 +421,75 @@ pub fn encode_base64(field: &Field) -> Result<String, Error> {
 /// data. Uses a tuple, so constants can be defined and use for different field 
 /// dimensions.
 #[cfg(feature = "base64")]
-pub fn decode_base64(dims: (usize, usize), data: &str) -> Result<Field, Error> {
+pub fn decode_base64(dims: (usize, usize), data: &str) -> Result<Field, DecodeError> {
     let mut cursor = Cursor::new(data);
     let mut sr = DecoderReader::new(&mut cursor, BASE64_CONFIG);
 
     decode(dims, &mut sr)
 }
+
+/// The version of share codes written by [`encode_share_code`].
+#[cfg(feature = "base64")]
+pub const SHARE_CODE_VERSION: u8 = 1;
+
+/// Encodes a field to a self-describing Base64 share code.
+///
+/// Unlike [`encode_base64`], a share code carries the field's dimensions: the
+/// `.fld` data is prefixed with a `byte` version number, and the width and
+/// height as Little Endian `ushort`s.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::fld;
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let code = fld::encode_share_code(&field).unwrap();
+/// let decoded = fld::decode_share_code(&code).unwrap();
+///
+/// assert_eq!(decoded.width(), 2);
+/// assert_eq!(decoded.get((1, 0)).kind, Draw);
+/// ```
+#[cfg(feature = "base64")]
+pub fn encode_share_code(field: &Field) -> Result<String, Error> {
+    if field.width() > u16::MAX as usize || field.height() > u16::MAX as usize {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "share codes can only hold fields up to 65535x65535",
+        ));
+    }
+
+    let mut sw = EncoderStringWriter::new(BASE64_CONFIG);
+
+    sw.write_all(&[SHARE_CODE_VERSION])?;
+    write_u16(&mut sw, field.width() as u16)?;
+    write_u16(&mut sw, field.height() as u16)?;
+
+    encode(field, &mut sw)
+        .map(|_| sw.into_inner())
+}
+
+/// Decodes a field from a share code made by [`encode_share_code`].
+#[cfg(feature = "base64")]
+pub fn decode_share_code(data: &str) -> Result<Field, DecodeError> {
+    let mut cursor = Cursor::new(data.trim());
+    let mut sr = Reader::new(DecoderReader::new(&mut cursor, BASE64_CONFIG));
+
+    let version = sr.read_u8()?;
+
+    if version != SHARE_CODE_VERSION {
+        return Err(DecodeError::UnsupportedVersion {
+            offset: 0,
+            version: version as u32,
+            supported: SHARE_CODE_VERSION as u32,
+        });
+    }
+
+    let width = sr.read_u16(Endian::Little)? as usize;
+    let height = sr.read_u16(Endian::Little)? as usize;
+
+    decode_from((width, height), &mut sr, &DecodeOptions::default(), &mut State::default(), None)
+}
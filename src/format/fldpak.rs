@@ -0,0 +1,327 @@
+//! Support for the `.fldpak` archive format.
+//!
+//! A `.fldpak` bundles several named fields into one file, so map packs and
+//! campaigns can be shared as a single artifact. **All integers use Little
+//! Endian.**
+//!
+//! All `.fldpak` files start with the magic bytes `FPAK`, followed by a `byte`
+//! version number (`1`) and a `ushort` count of fields. Then, an index
+//! follows, with an entry for each field:
+//!
+//! * the field's name, as a `ushort` length followed by that many bytes of
+//!   UTF-8.
+//! * the offset of the field's data, as a `uint`, from the start of the file.
+//! * the length of the field's data, as a `uint`.
+//!
+//! Each field's data is a complete [`.fldx`][1] file.
+//!
+//! [`Pack`] holds an entire archive in memory, while [`PackReader`] reads the
+//! index up front and decodes fields on demand.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{Field, Panel, PanelKind::*};
+//! use citrus_common::format::fldpak::{self, Pack};
+//!
+//! let mut pack = Pack::new();
+//! pack.insert("first", Field::new_slice(&[&[Panel::new(Home)]]));
+//! pack.insert("second", Field::new_slice(&[&[Panel::new(Draw)]]));
+//!
+//! let mut data = Vec::new();
+//! fldpak::encode(&pack, &mut data).unwrap();
+//!
+//! let decoded = fldpak::decode(&data[..]).unwrap();
+//! assert_eq!(decoded.get("second").unwrap().get((0, 0)).kind, Draw);
+//! ```
+//!
+//! [1]: ../fldx/index.html
+
+use super::*;
+
+use crate::Field;
+
+use std::io::{Read, Write, Seek, SeekFrom, Error, ErrorKind};
+
+/// The magic bytes at the start of every file.
+const MAGIC: &[u8; 4] = b"FPAK";
+
+/// The version of the format this crate writes.
+pub const VERSION: u8 = 1;
+
+/// An archive of named fields, in order.
+#[derive(Clone, Default)]
+pub struct Pack {
+    entries: Vec<(String, Field)>,
+}
+
+impl Pack {
+    /// Creates a new, empty archive.
+    pub const fn new() -> Pack {
+        Pack {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Gets how many fields are in the archive.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the archive has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a field to the end of the archive.
+    ///
+    /// If a field with the same name already exists, it is replaced in place
+    /// and returned.
+    pub fn insert<S>(&mut self, name: S, field: Field) -> Option<Field>
+    where S: Into<String> {
+        let name = name.into();
+
+        match self.entries.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => Some(std::mem::replace(existing, field)),
+            None => {
+                self.entries.push((name, field));
+                None
+            }
+        }
+    }
+
+    /// Removes a field from the archive by name.
+    pub fn remove(&mut self, name: &str) -> Option<Field> {
+        let idx = self.entries.iter().position(|(n, _)| n == name)?;
+
+        Some(self.entries.remove(idx).1)
+    }
+
+    /// Gets a field by name.
+    pub fn get(&self, name: &str) -> Option<&Field> {
+        self.entries.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, field)| field)
+    }
+
+    /// Gets a field by name mutably.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Field> {
+        self.entries.iter_mut()
+            .find(|(n, _)| n == name)
+            .map(|(_, field)| field)
+    }
+
+    /// Gets an iterator over the names of the fields in the archive.
+    pub fn names(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Gets an iterator over the fields in the archive, with their names.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&str, &Field)> + ExactSizeIterator {
+        self.entries.iter().map(|(name, field)| (name.as_str(), field))
+    }
+}
+
+/// An entry in an archive's index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// The name of the field.
+    pub name: String,
+    /// The offset of the field's data from the start of the file.
+    pub offset: u32,
+    /// The length of the field's data.
+    pub len: u32,
+}
+
+/// Reads fields from an archive on demand.
+pub struct PackReader<R> {
+    input: R,
+    index: Vec<Entry>,
+}
+
+impl<R> PackReader<R>
+where R: Read + Seek {
+    /// Opens an archive, reading its index.
+    pub fn new(mut input: R) -> Result<PackReader<R>, DecodeError> {
+        input.seek(SeekFrom::Start(0))?;
+        let index = read_index(&mut Reader::new(&mut input))?;
+
+        Ok(PackReader { input, index })
+    }
+
+    /// Gets the archive's index.
+    pub fn index(&self) -> &[Entry] {
+        &self.index
+    }
+
+    /// Gets an iterator over the names of the fields in the archive.
+    pub fn names(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator {
+        self.index.iter().map(|entry| entry.name.as_str())
+    }
+
+    /// Reads a field by name.
+    ///
+    /// Returns `Ok(None)` if no field has that name.
+    pub fn read(&mut self, name: &str) -> Result<Option<Field>, DecodeError> {
+        let entry = match self.index.iter().find(|entry| entry.name == name) {
+            Some(entry) => entry.clone(),
+            None => return Ok(None),
+        };
+
+        self.read_entry(&entry).map(Some)
+    }
+
+    /// Reads the field an index entry points to.
+    pub fn read_entry(&mut self, entry: &Entry) -> Result<Field, DecodeError> {
+        let offset = entry.offset as u64;
+        self.input.seek(SeekFrom::Start(offset))?;
+
+        let mut input = Reader::with_offset((&mut self.input).take(entry.len as u64), offset);
+
+        fldx::decode_part(&mut input, &DecodeOptions::default())
+    }
+
+    /// Consumes the reader, returning the underlying input.
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+}
+
+/// Encode an archive to the `.fldpak` format.
+pub fn encode<T>(pack: &Pack, mut output: T) -> Result<(), Error>
+where T: Write {
+    if pack.len() > u16::MAX as usize {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "archives can have at most 65535 fields",
+        ));
+    }
+
+    // encode all of the fields first, so we know where they go
+    let blobs = pack.iter()
+        .map(|(_, field)| {
+            let mut data = Vec::new();
+            fldx::encode(field, &mut data).map(|_| data)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // the header and index come before any data
+    let index_len = pack.names()
+        .map(|name| 2 + name.len() + 4 + 4)
+        .sum::<usize>();
+    let lens = blobs.iter().map(Vec::len).collect::<Vec<_>>();
+    let index = layout(MAGIC.len() + 1 + 2 + index_len, &lens)?;
+
+    // write the header
+    output.write_all(MAGIC)?;
+    output.write_all(&[VERSION])?;
+    write_u16(&mut output, pack.len() as u16)?;
+
+    // write the index
+    for (name, (offset, len)) in pack.names().zip(index) {
+        write_string(&mut output, name)?;
+        write_u32(&mut output, offset)?;
+        write_u32(&mut output, len)?;
+    }
+
+    // write the data
+    for data in blobs.iter() {
+        output.write_all(data)?;
+    }
+
+    Ok(())
+}
+
+/// Gets the offset and length of each field's data in the index, given
+/// where the data starts and how long each field's data is.
+///
+/// Fails if any of the data would be past 4 GiB, which offsets cannot reach.
+pub(crate) fn layout(start: usize, lens: &[usize]) -> Result<Vec<(u32, u32)>, Error> {
+    let mut offset = start;
+    let mut index = Vec::with_capacity(lens.len());
+
+    for len in lens.iter().copied() {
+        let end = offset.checked_add(len)
+            .filter(|end| *end <= u32::MAX as usize)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "archives can be at most 4 GiB"))?;
+
+        // both fit, as neither is more than the end
+        index.push((offset as u32, len as u32));
+        offset = end;
+    }
+
+    Ok(index)
+}
+
+/// Decode an entire archive from the `.fldpak` format.
+pub fn decode<T>(mut input: T) -> Result<Pack, DecodeError>
+where T: Read {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    let index = read_index(&mut Reader::new(&data[..]))?;
+
+    let mut pack = Pack::new();
+
+    for Entry { name, offset, len } in index {
+        let start = offset as usize;
+        let end = start + len as usize;
+
+        let field_data = data.get(start..end)
+            .ok_or(DecodeError::UnexpectedEof { offset: data.len() as u64 })?;
+
+        let mut input = Reader::with_offset(field_data, offset as u64);
+        let field = fldx::decode_part(&mut input, &DecodeOptions::default())?;
+
+        pack.insert(name, field);
+    }
+
+    Ok(pack)
+}
+
+fn read_index<T>(input: &mut Reader<T>) -> Result<Vec<Entry>, DecodeError>
+where T: Read {
+    // read the header
+    let mut magic = [0u8; 4];
+    input.read_bytes(&mut magic)?;
+
+    if &magic != MAGIC {
+        return Err(DecodeError::invalid(0, "not a fldpak file"));
+    }
+
+    let version = input.read_u8()?;
+
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion {
+            offset: 4,
+            version: version as u32,
+            supported: VERSION as u32,
+        });
+    }
+
+    let count = input.read_u16(Endian::Little)? as usize;
+
+    // read the index
+    let mut index: Vec<Entry> = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let offset = input.offset();
+        let name = input.read_string(Endian::Little)?;
+
+        if index.iter().any(|entry| entry.name == name) {
+            return Err(DecodeError::invalid(offset, format!("duplicate field name {:?}", name)));
+        }
+
+        index.push(Entry {
+            name,
+            offset: input.read_u32(Endian::Little)?,
+            len: input.read_u32(Endian::Little)?,
+        });
+    }
+
+    Ok(index)
+}
+
+/// Checks if some data looks like a `.fldpak` file.
+pub fn sniff(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) && data.get(4) == Some(&VERSION)
+}
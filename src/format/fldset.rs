@@ -0,0 +1,137 @@
+//! Support for the `.fldset` format.
+//!
+//! A `.fldset` stores a [`FieldSet`], every layout of a board that changes
+//! mid-game. **All integers use Little Endian.**
+//!
+//! All `.fldset` files start with the magic bytes `FSET`, followed by a `byte`
+//! version number (`1`). Then follows a `ushort` count of states, a `ushort`
+//! count of transitions, and the `ushort` index of the current state.
+//!
+//! Each state is its name, as a `ushort` length followed by that many bytes
+//! of UTF-8, then a `uint` length and that many bytes of a complete
+//! [`.fldx`][1] file.
+//!
+//! Each transition is the `ushort` index of the state it starts from, the
+//! `ushort` index of the state it ends at, and its label as a string.
+//!
+//! [1]: ../fldx/index.html
+
+use super::*;
+
+use crate::FieldSet;
+use crate::field_set::{State, Transition};
+
+use std::io::{Read, Write, Error, ErrorKind};
+
+/// The magic bytes at the start of every file.
+const MAGIC: &[u8; 4] = b"FSET";
+
+/// The version of the format this crate writes.
+pub const VERSION: u8 = 1;
+
+/// Encode a field set to the `.fldset` format.
+pub fn encode<T>(set: &FieldSet, mut output: T) -> Result<(), Error>
+where T: Write {
+    if set.len() > u16::MAX as usize || set.transitions().len() > u16::MAX as usize {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "field sets can have at most 65535 states and transitions",
+        ));
+    }
+
+    // write the header
+    output.write_all(MAGIC)?;
+    output.write_all(&[VERSION])?;
+    write_u16(&mut output, set.len() as u16)?;
+    write_u16(&mut output, set.transitions().len() as u16)?;
+    write_u16(&mut output, set.current_index() as u16)?;
+
+    // write the states
+    for state in set.states() {
+        write_string(&mut output, &state.name)?;
+
+        let mut data = Vec::new();
+        fldx::encode(&state.field, &mut data)?;
+
+        write_u32(&mut output, data.len() as u32)?;
+        output.write_all(&data)?;
+    }
+
+    // write the transitions
+    for transition in set.transitions() {
+        write_u16(&mut output, transition.from as u16)?;
+        write_u16(&mut output, transition.to as u16)?;
+        write_string(&mut output, &transition.label)?;
+    }
+
+    Ok(())
+}
+
+/// Decode a field set from the `.fldset` format.
+pub fn decode<T>(input: T) -> Result<FieldSet, DecodeError>
+where T: Read {
+    let mut input = Reader::new(input);
+
+    // read the header
+    let mut magic = [0u8; 4];
+    input.read_bytes(&mut magic)?;
+
+    if &magic != MAGIC {
+        return Err(DecodeError::invalid(0, "not a fldset file"));
+    }
+
+    let version = input.read_u8()?;
+
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion {
+            offset: 4,
+            version: version as u32,
+            supported: VERSION as u32,
+        });
+    }
+
+    let state_count = input.read_u16(Endian::Little)? as usize;
+    let transition_count = input.read_u16(Endian::Little)? as usize;
+    let current = input.read_u16(Endian::Little)? as usize;
+
+    // read the states
+    let states = (0..state_count)
+        .map(|_| {
+            let name = input.read_string(Endian::Little)?;
+            let len = input.read_u32(Endian::Little)? as u64;
+
+            let offset = input.offset();
+            let mut data = Reader::with_offset((&mut input).take(len), offset);
+            let field = fldx::decode_part(&mut data, &DecodeOptions::default())?;
+
+            Ok(State { name, field })
+        })
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+
+    // read the transitions
+    let transitions = (0..transition_count)
+        .map(|_| {
+            Ok(Transition {
+                from: input.read_u16(Endian::Little)? as usize,
+                to: input.read_u16(Endian::Little)? as usize,
+                label: input.read_string(Endian::Little)?,
+            })
+        })
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+
+    // validate before building the set
+    let in_bounds = |idx: usize| idx < states.len();
+
+    if states.is_empty()
+        || !in_bounds(current)
+        || !transitions.iter().all(|t| in_bounds(t.from) && in_bounds(t.to)) {
+        return Err(input.invalid("fldset refers to a state that does not exist"));
+    }
+
+    Ok(FieldSet::from_parts(states, transitions, current))
+}
+
+/// Checks if some data looks like a `.fldset` file.
+pub fn sniff(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) && data.get(4) == Some(&VERSION)
+}
@@ -2,7 +2,9 @@
 //!
 //! The functions [`encode()`] and [`decode()`] encode and decode
 //! fields in the `.fldx` format, a format deferring from the original `.fld`
-//! format. **All integers, unless specified, use Little Endian.**
+//! format. **All integers, unless specified, use Little Endian.** Some tools
+//! write Big Endian files instead; [`encode_with()`] and [`decode_with()`] can
+//! be configured to handle them.
 //!
 //! All `.fldx` files start with a four byte header. This header is comprised
 //! of two `ushort`s describing the width, and then the height in that order. 
@@ -13,71 +15,1364 @@
 //! byte details the panel's directional information [as detailed in the `.fld`
 //! format][1].
 //!
+//! # Version 2
+//! Version 2 files can carry metadata about the field, such as its name and
+//! author. They are written by [`encode_with_metadata()`], and [`decode()`]
+//! reads both versions transparently.
+//!
+//! Version 2 files start with the magic bytes `FLDX`, followed by a `byte`
+//! version number (`2`) and a `byte` of flags. If the flags have the
+//! `0x01` bit set, a metadata section follows. The rest of the file is
+//! identical to a version 1 file, except when the flags have the `0x02` bit
+//! set: then, a `uint` CRC-32 checksum of everything before it trails the
+//! file.
+//!
+//! If the flags have the `0x04` bit set, the panel data after the size data is
+//! compressed with raw DEFLATE. Encoding and decoding compressed files
+//! requires the `deflate` feature.
+//!
+//! If the flags have the `0x10` bit set, all integers in the rest of the file
+//! use Big Endian.
+//!
+//! If the flags have the `0x08` bit set, the file is run-length encoded: the
+//! width and height are unsigned LEB128 varints instead of `ushort`s, and the
+//! panel data is a sequence of runs. Each run is a varint count, followed by
+//! the two bytes of the panel repeated that many times. As most fields are
+//! mostly empty, this is usually much smaller. When combined with
+//! compression, the runs are compressed.
+//!
+//! The metadata section starts with a `byte` of bitflags describing which
+//! fields are present, followed by each present field in order:
+//!
+//! * `0x01`: the field's name, as a string.
+//! * `0x02`: the field's author, as a string.
+//! * `0x04`: the field's description, as a string.
+//! * `0x08`: the field's creation date, as a `ulong` of seconds since the Unix
+//!   epoch.
+//! * `0x10`: [annotations](crate::annotate) of the field's panels, as a `uint`
+//!   count, followed by each annotation.
+//!
+//! Each annotation is the panel's position as two `uint`s, `x` then `y`,
+//! followed by a `byte` of bitflags and each present part in order:
+//!
+//! * `0x01`: where the panel warps to, as two `uint`s.
+//! * `0x02`: a note, as a string.
+//! * `0x04`: the name of the panel's region, as a string.
+//!
+//! Strings are a `ushort` length in bytes, followed by that many bytes of
+//! UTF-8.
+//!
 //! [1]: ../fld/index.html
 
 use super::*;
 
-use crate::{Field, Panel, PanelKind};
+use crate::{Field, Panel, PanelKind, Pos};
+use crate::annotate::{Annotation, FieldAnnotations};
 
-use std::io::{Read, Write, Error, ErrorKind};
+use std::io::{Read, Write, Error, ErrorKind, BufReader};
 use std::convert::TryFrom as _;
+use std::fs::File;
+use std::path::Path;
+
+/// The magic bytes at the start of version 2 files.
+const MAGIC: &[u8; 4] = b"FLDX";
+
+/// The latest version of the format this crate understands.
+pub const VERSION: u8 = 2;
+
+const FLAG_METADATA: u8 = 0x01;
+const FLAG_CHECKSUM: u8 = 0x02;
+const FLAG_DEFLATE: u8 = 0x04;
+const FLAG_RLE: u8 = 0x08;
+const FLAG_BIG_ENDIAN: u8 = 0x10;
+
+const FLAGS_KNOWN: u8 = FLAG_METADATA
+    | FLAG_CHECKSUM
+    | FLAG_DEFLATE
+    | FLAG_RLE
+    | FLAG_BIG_ENDIAN;
+
+/// Flags that require a version 2 file.
+const FLAGS_V2: u8 = FLAG_METADATA | FLAG_CHECKSUM | FLAG_DEFLATE | FLAG_RLE;
+
+const METADATA_NAME: u8 = 0x01;
+const METADATA_AUTHOR: u8 = 0x02;
+const METADATA_DESCRIPTION: u8 = 0x04;
+const METADATA_CREATED: u8 = 0x08;
+const METADATA_ANNOTATIONS: u8 = 0x10;
+
+const ANNOTATION_WARP: u8 = 0x01;
+const ANNOTATION_NOTE: u8 = 0x02;
+const ANNOTATION_REGION: u8 = 0x04;
+
+/// Metadata about a field, carried by version 2 files.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metadata {
+    /// The field's name.
+    pub name: Option<String>,
+    /// The field's author.
+    pub author: Option<String>,
+    /// A description of the field.
+    pub description: Option<String>,
+    /// When the field was created, in seconds since the Unix epoch.
+    pub created: Option<u64>,
+    /// Notes about the field's panels.
+    pub annotations: FieldAnnotations,
+}
+
+impl Metadata {
+    /// Checks if no metadata is present.
+    pub fn is_empty(&self) -> bool {
+        *self == Metadata::default()
+    }
+}
+
+/// Options for encoding a `.fldx` file.
+#[derive(Clone, Debug)]
+pub struct EncodeOptions {
+    /// The version of the file to write. Defaults to [`VERSION`].
+    ///
+    /// Version 1 files can only be written without metadata, checksums,
+    /// run-length encoding or compression.
+    pub version: u8,
+    /// The byte order of integers in the file.
+    ///
+    /// Version 1 files do not record their byte order, so they must be
+    /// decoded with the same [`DecodeOptions::endian`].
+    pub endian: Endian,
+    /// The metadata to write.
+    pub metadata: Metadata,
+    /// Whether to write a checksum trailer, so corruption can be detected.
+    pub checksum: bool,
+    /// Whether to run-length encode the panel data.
+    pub run_length: bool,
+    /// Whether to compress the panel data.
+    #[cfg(feature = "deflate")]
+    pub compress: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> EncodeOptions {
+        EncodeOptions {
+            version: VERSION,
+            endian: Endian::default(),
+            metadata: Metadata::default(),
+            checksum: false,
+            run_length: false,
+            #[cfg(feature = "deflate")]
+            compress: false,
+        }
+    }
+}
 
 /// Encode a field to the `.fldx` format.
+///
+/// This writes a version 1 file, which can be read by older tools.
 pub fn encode<T>(field: &Field, mut output: T) -> Result<(), Error>
+where T: Write {
+    write_body(field, 0, Endian::Little, &mut output)
+}
+
+/// Encode a field to a new `Vec` in the `.fldx` format.
+///
+/// Like [`encode()`], this writes a version 1 file.
+pub fn encode_to_vec(field: &Field) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + field.width() * field.height() * 2);
+    encode(field, &mut data).expect("writing to a Vec cannot fail");
+
+    data
+}
+
+/// Encode a field to the version 2 `.fldx` format, with metadata.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::fldx::{self, Metadata};
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let metadata = Metadata {
+///     name: Some("Tiny Field".to_owned()),
+///     ..Metadata::default()
+/// };
+///
+/// let mut data = Vec::new();
+/// fldx::encode_with_metadata(&field, &metadata, &mut data).unwrap();
+///
+/// let (_, decoded) = fldx::decode_with_metadata(&data[..]).unwrap();
+/// assert_eq!(decoded.name.as_deref(), Some("Tiny Field"));
+/// ```
+pub fn encode_with_metadata<T>(
+    field: &Field,
+    metadata: &Metadata,
+    output: T,
+) -> Result<(), Error>
+where T: Write {
+    let options = EncodeOptions {
+        metadata: metadata.clone(),
+        ..EncodeOptions::default()
+    };
+
+    encode_with(field, &options, output)
+}
+
+/// Encode a field to the `.fldx` format, with options.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::{fldx::{self, EncodeOptions}, DecodeOptions, Endian};
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// // write a big endian version 1 file
+/// let options = EncodeOptions {
+///     version: 1,
+///     endian: Endian::Big,
+///     ..EncodeOptions::default()
+/// };
+///
+/// let mut data = Vec::new();
+/// fldx::encode_with(&field, &options, &mut data).unwrap();
+/// assert_eq!(&data[..4], &[0, 2, 0, 1]);
+///
+/// let options = DecodeOptions {
+///     endian: Endian::Big,
+///     ..DecodeOptions::default()
+/// };
+///
+/// let decoded = fldx::decode_with(&data[..], &options).unwrap();
+/// assert_eq!(decoded.get((1, 0)).kind, Draw);
+/// ```
+pub fn encode_with<T>(
+    field: &Field,
+    options: &EncodeOptions,
+    mut output: T,
+) -> Result<(), Error>
+where T: Write {
+    let mut flags = 0;
+    if !options.metadata.is_empty() { flags |= FLAG_METADATA; }
+    if options.checksum { flags |= FLAG_CHECKSUM; }
+    if options.run_length { flags |= FLAG_RLE; }
+    #[cfg(feature = "deflate")]
+    if options.compress { flags |= FLAG_DEFLATE; }
+    if options.endian == Endian::Big { flags |= FLAG_BIG_ENDIAN; }
+
+    match options.version {
+        1 if flags & FLAGS_V2 == 0 => {
+            return write_body(field, 0, options.endian, output);
+        },
+        1 => return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "version 1 fldx files only support the endian option",
+        )),
+        VERSION => (),
+        version => return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("unsupported fldx version {}", version),
+        )),
+    }
+
+    // buffer the file, so we can checksum it
+    let mut data = Vec::new();
+
+    // write the header
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&[VERSION, flags]);
+
+    if flags & FLAG_METADATA != 0 {
+        write_metadata(&options.metadata, options.endian, &mut data)?;
+    }
+
+    write_body(field, flags, options.endian, &mut data)?;
+
+    if flags & FLAG_CHECKSUM != 0 {
+        let checksum = crc32(&data);
+        options.endian.write_u32(&mut data, checksum)?;
+    }
+
+    output.write_all(&data)
+}
+
+fn write_body<T>(field: &Field, flags: u8, endian: Endian, mut output: T) -> Result<(), Error>
 where T: Write {
     // write the size data
-    // write width
-    write_u16(&mut output, field.width() as u16)?;
-    // write height
-    write_u16(&mut output, field.height() as u16)?;
+    if flags & FLAG_RLE != 0 {
+        write_varint(&mut output, field.width() as u64)?;
+        write_varint(&mut output, field.height() as u64)?;
+    } else {
+        // write width
+        endian.write_u16(&mut output, field.width() as u16)?;
+        // write height
+        endian.write_u16(&mut output, field.height() as u16)?;
+    }
+
+    if flags & FLAG_DEFLATE != 0 {
+        #[cfg(feature = "deflate")]
+        {
+            use flate2::{write::DeflateEncoder, Compression};
+
+            let mut encoder = DeflateEncoder::new(output, Compression::best());
+            write_panels(field, flags, &mut encoder)?;
+            encoder.finish()?;
+
+            return Ok(());
+        }
+    }
+
+    write_panels(field, flags, output)
+}
+
+fn write_panels<T>(field: &Field, flags: u8, mut output: T) -> Result<(), Error>
+where T: Write {
+    if flags & FLAG_RLE != 0 {
+        // write runs of identical panels
+        let mut panels = field.iter()
+            .map(|(x, y)| {
+                let panel = field.get((x, y));
+                [panel.kind.into(), panel.exits_internal()]
+            })
+            .peekable();
+
+        while let Some(panel) = panels.next() {
+            let mut count = 1u64;
+
+            while panels.next_if_eq(&panel).is_some() {
+                count += 1;
+            }
+
+            write_varint(&mut output, count)?;
+            output.write_all(&panel)?;
+        }
+
+        return Ok(());
+    }
 
     // write data
     for (x, y) in field.iter() {
-        let panel = field.get(x, y);
+        let panel = field.get((x, y));
 
         // we can do this because the panel's kind already reflects the OJ
         // format.
-        output.write(&[panel.kind.into(), panel.exits_internal()])?;
+        output.write_all(&[panel.kind.into(), panel.exits_internal()])?;
     }
 
     Ok(())
 }
 
 /// Decode a field from the `.fldx` format.
-pub fn decode<T>(mut input: T) -> Result<Field, Error>
+///
+/// Both version 1 and version 2 files are accepted; any metadata is
+/// discarded.
+pub fn decode<T>(input: T) -> Result<Field, DecodeError>
+where T: Read {
+    decode_with(input, &DecodeOptions::default())
+}
+
+/// Decode a field from `.fldx` data already in memory.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::fldx;
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let data = fldx::encode_to_vec(&field);
+/// let decoded = fldx::decode_from_slice(&data).unwrap();
+///
+/// assert_eq!(decoded.get((1, 0)).kind, Draw);
+/// ```
+pub fn decode_from_slice(data: &[u8]) -> Result<Field, DecodeError> {
+    decode(data)
+}
+
+/// Decode a field from a `.fldx` file.
+pub fn decode_file<P>(path: P) -> Result<Field, DecodeError>
+where P: AsRef<Path> {
+    decode(BufReader::new(File::open(path)?))
+}
+
+/// Decode a field from the `.fldx` format, with options.
+pub fn decode_with<T>(input: T, options: &DecodeOptions) -> Result<Field, DecodeError>
+where T: Read {
+    decode_with_substitutions(input, options).map(|(field, _)| field)
+}
+
+/// Decode a field from the `.fldx` format, with options, reporting the panels
+/// replaced by [`DecodeOptions::placeholder`].
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::{fldx, DecodeOptions};
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let mut data = Vec::new();
+/// fldx::encode(&field, &mut data).unwrap();
+///
+/// // pretend the draw panel is from a newer game update
+/// data[6] = 0xFE;
+///
+/// let options = DecodeOptions {
+///     placeholder: Some(Neutral),
+///     ..DecodeOptions::default()
+/// };
+///
+/// let (decoded, substitutions) = fldx::decode_with_substitutions(&data[..], &options).unwrap();
+///
+/// assert_eq!(decoded.get((1, 0)).kind, Neutral);
+/// assert_eq!(substitutions[0].x, 1);
+/// assert_eq!(substitutions[0].value, 0xFE);
+/// ```
+pub fn decode_with_substitutions<T>(
+    input: T,
+    options: &DecodeOptions,
+) -> Result<(Field, Vec<Substitution>), DecodeError>
+where T: Read {
+    let mut state = State::new(options);
+
+    decode_from(&mut Reader::new(input), options, &mut state)
+        .map(|(field, _)| (field, state.substitutions))
+}
+
+/// Decode a field from the `.fldx` format, with options, collecting warnings
+/// about anything unusual in it.
+///
+/// Unlike with [`decode_with_substitutions`], trailing data is skipped when
+/// it is not an error, so it can be warned about.
+pub fn decode_with_diagnostics<T>(
+    input: T,
+    options: &DecodeOptions,
+    diagnostics: &mut Diagnostics,
+) -> Result<Field, DecodeError>
+where T: Read {
+    let mut state = State::diagnosing(options);
+
+    let (field, _) = decode_from(&mut Reader::new(input), options, &mut state)?;
+    state.finish(&field, diagnostics);
+
+    Ok(field)
+}
+
+/// Decode a field and its metadata from the `.fldx` format.
+///
+/// Version 1 files have no metadata, so the returned metadata will be empty.
+pub fn decode_with_metadata<T>(input: T) -> Result<(Field, Metadata), DecodeError>
+where T: Read {
+    decode_from(&mut Reader::new(input), &DecodeOptions::default(), &mut State::default())
+}
+
+/// The most bytes the parts of a version 2 file other than its panels can
+/// take.
+const MAX_OVERHEAD: u64 = 6 + 1 + 3 * (2 + u16::MAX as u64) + 8 + 2 * 10 + 4;
+
+/// Decodes a field from a reader that may be partway through another file,
+/// so errors report offsets from the start of that file.
+pub(super) fn decode_from<T>(
+    input: &mut Reader<T>,
+    options: &DecodeOptions,
+    state: &mut State,
+) -> Result<(Field, Metadata), DecodeError>
 where T: Read {
+    let start = input.offset();
+
+    let mut header = [0u8; 4];
+    input.read_bytes(&mut header)?;
+
+    if &header == MAGIC {
+        let flags = read_header(input)?;
+
+        if flags & FLAG_CHECKSUM != 0 {
+            // verify the checksum before trusting any of the data, but don't
+            // read more than the limits could allow; runs take at most three
+            // bytes a panel, and compression adds very little to that
+            let max_len = options.panel_limit()
+                .map(|limit| MAX_OVERHEAD.saturating_add((limit as u64).saturating_mul(4)))
+                .unwrap_or(u64::MAX - 1);
+
+            let mut data = Vec::new();
+            data.extend_from_slice(MAGIC);
+            data.extend_from_slice(&[VERSION, flags]);
+
+            if input.by_ref().take(max_len + 1).read_to_end(&mut data)? as u64 > max_len {
+                return Err(DecodeError::TooLarge { offset: start });
+            }
+
+            if data.len() < 10 {
+                return Err(DecodeError::UnexpectedEof { offset: input.offset() });
+            }
+
+            let (contents, trailer) = data.split_at(data.len() - 4);
+            let expected = Reader::new(trailer).read_u32(endian_of(flags))?;
+            let got = crc32(contents);
+
+            if expected != got {
+                return Err(DecodeError::ChecksumMismatch {
+                    offset: input.offset() - 4,
+                    expected,
+                    got,
+                });
+            }
+
+            let mut contents = Reader::with_offset(&contents[6..], start + 6);
+            let result = read_v2(flags, &mut contents, options, state)?;
+            options.check_end(&mut contents, state)?;
+
+            Ok(result)
+        } else {
+            let result = read_v2(flags, input, options, state)?;
+            options.check_end(input, state)?;
+
+            Ok(result)
+        }
+    } else {
+        // this is a version 1 file, and the header is the size data
+        let mut header = Reader::with_offset(&header[..], start);
+        let width = header.read_u16(options.endian)? as usize;
+        let height = header.read_u16(options.endian)? as usize;
+
+        let len = options.check_size(width, height, start)?;
+        let field = read_body(width, height, 0, input, state)?;
+
+        // version 1 files end with their panels, so more panels mean the
+        // header is wrong
+        let offset = input.offset();
+        let extra = input.count_rest(2)?;
+
+        if extra > 0 {
+            return Err(DecodeError::SizeMismatch {
+                offset,
+                expected: len,
+                got: len.saturating_add(extra),
+            });
+        }
+
+        Ok((field, Metadata::default()))
+    }
+}
+
+/// Decodes a field embedded in another format.
+pub(super) fn decode_part<T>(input: &mut Reader<T>, options: &DecodeOptions) -> Result<Field, DecodeError>
+where T: Read {
+    decode_from(input, options, &mut State::new(options))
+        .map(|(field, _)| field)
+}
+
+/// Reads the version and flags of a version 2 file, after the magic bytes.
+fn read_header<T>(input: &mut Reader<T>) -> Result<u8, DecodeError>
+where T: Read {
+    let offset = input.offset();
+    let version = input.read_u8()?;
+
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion {
+            offset,
+            version: version as u32,
+            supported: VERSION as u32,
+        });
+    }
+
+    let flags = input.read_u8()?;
+
+    if flags & !FLAGS_KNOWN != 0 {
+        return Err(DecodeError::invalid(
+            offset + 1,
+            format!("unknown fldx flags {:#04x}", flags),
+        ));
+    }
+
+    Ok(flags)
+}
+
+/// Gets the byte order of a version 2 file from its flags.
+fn endian_of(flags: u8) -> Endian {
+    if flags & FLAG_BIG_ENDIAN != 0 {
+        Endian::Big
+    } else {
+        Endian::Little
+    }
+}
+
+fn read_v2<T>(
+    flags: u8,
+    input: &mut Reader<T>,
+    options: &DecodeOptions,
+    state: &mut State,
+) -> Result<(Field, Metadata), DecodeError>
+where T: Read {
+    let endian = endian_of(flags);
+
+    let metadata = if flags & FLAG_METADATA != 0 {
+        read_metadata(endian, input)?
+    } else {
+        Metadata::default()
+    };
+
     // read the size data
-    // read width
-    let width = read_u16(&mut input)? as usize;
-    // read height
-    let height = read_u16(&mut input)? as usize;
+    let offset = input.offset();
+
+    let (width, height) = if flags & FLAG_RLE != 0 {
+        (input.read_varint_usize()?, input.read_varint_usize()?)
+    } else {
+        (input.read_u16(endian)? as usize, input.read_u16(endian)? as usize)
+    };
+
+    options.check_size(width, height, offset)?;
+
+    if flags & FLAG_DEFLATE != 0 {
+        #[cfg(feature = "deflate")]
+        {
+            use std::io::BufReader;
+
+            // past here, offsets count decompressed bytes
+            let offset = input.offset();
+            let decoder = flate2::bufread::DeflateDecoder::new(BufReader::new(&mut *input));
+            let mut decoder = Reader::with_offset(decoder, offset);
+
+            let field = read_body(width, height, flags, &mut decoder, state)?;
+            options.check_end(&mut decoder, state)?;
+
+            // the decoder only takes the compressed data from its buffer, so
+            // anything left in it came after
+            let buffered = decoder.get_ref().get_ref().buffer().len() as u64;
+            let offset = input.offset() - buffered;
+
+            if buffered > 0 {
+                if options.strict {
+                    return Err(DecodeError::TrailingData { offset });
+                } else if let Some(warnings) = state.warnings.as_mut() {
+                    warnings.push(Warning::TrailingData { offset, len: buffered });
+                }
+            }
+
+            return Ok((field, metadata));
+        }
+
+        #[cfg(not(feature = "deflate"))]
+        return Err(input.invalid("compressed fldx files require the `deflate` feature"));
+    }
+
+    read_body(width, height, flags, input, state).map(|field| (field, metadata))
+}
+
+/// Reads the panels of a field, whose size has already been checked.
+fn read_body<T>(
+    width: usize,
+    height: usize,
+    flags: u8,
+    input: &mut Reader<T>,
+    state: &mut State,
+) -> Result<Field, DecodeError>
+where T: Read {
+    if flags & FLAG_RLE != 0 {
+        return read_runs(width, height, input, state);
+    }
+
+    let len = width * height;
 
     // read data
     let mut data = Vec::<Panel>::new();
 
-    let mut panel_buf = [0u8; 2];
-    
-    while input.read(&mut panel_buf)? != 0 {
-        let panel_kind = match PanelKind::try_from(panel_buf[0]) {
-            Ok(kind) => kind,
-            // throw
-            Err(e) => return Err(Error::new(ErrorKind::InvalidData, e)),
-        };
+    input.read_items(2, len, |panel, offset| {
+        let idx = data.len();
+        let panel_kind = state.parse(panel[0], offset, idx..idx + 1, width)?;
 
         data.push(
-            Panel::from_internal(panel_kind, panel_buf[1])
+            Panel::from_internal(panel_kind, panel[1])
         );
-    }
-    
-    // verify we can make a field from this
-    if data.len() == width * height {
+
+        Ok(())
+    })?;
+
+    if data.len() == len {
         Ok(Field::new_vec(data, width, height))
     } else {
-        Err(Error::new(
-            ErrorKind::InvalidData, 
-            InvalidSize::new(width * height, data.len()),
-        ))
+        Err(DecodeError::SizeMismatch {
+            offset: input.offset(),
+            expected: len,
+            got: data.len(),
+        })
+    }
+}
+
+fn read_runs<T>(
+    width: usize,
+    height: usize,
+    input: &mut Reader<T>,
+    state: &mut State,
+) -> Result<Field, DecodeError>
+where T: Read {
+    let len = width * height;
+
+    let mut data = Vec::<Panel>::new();
+
+    let mut panel_buf = [0u8; 2];
+
+    while data.len() < len {
+        let offset = input.offset();
+        let count = input.read_varint_usize()?;
+        input.read_bytes(&mut panel_buf)?;
+
+        // don't let a bad run allocate too much
+        if count > len - data.len() {
+            return Err(DecodeError::SizeMismatch {
+                offset,
+                expected: len,
+                got: data.len().saturating_add(count),
+            });
+        }
+
+        let idx = data.len();
+        let panel_kind = state.parse(panel_buf[0], input.offset() - 2, idx..idx + count, width)?;
+
+        // the size was checked against the limits, but there may be none, so
+        // fail instead of aborting if a run is too big to allocate
+        data.try_reserve(count).map_err(|_| DecodeError::TooLarge { offset })?;
+
+        let panel = Panel::from_internal(panel_kind, panel_buf[1]);
+        data.extend(std::iter::repeat_n(panel, count));
+    }
+
+    Ok(Field::new_vec(data, width, height))
+}
+
+/// Writes a run-length encoded field, without any header, for formats built
+/// on top of this one.
+pub(super) fn write_rle_body<T>(field: &Field, output: T) -> Result<(), Error>
+where T: Write {
+    write_body(field, FLAG_RLE, Endian::Little, output)
+}
+
+/// Reads a field written by [`write_rle_body`].
+pub(super) fn read_rle_body<T>(input: T, options: &DecodeOptions) -> Result<Field, DecodeError>
+where T: Read {
+    let mut input = Reader::new(input);
+    let mut state = State::new(options);
+
+    let (field, _) = read_v2(FLAG_RLE, &mut input, options, &mut state)?;
+    options.check_end(&mut input, &mut state)?;
+
+    Ok(field)
+}
+
+fn write_metadata<T>(metadata: &Metadata, endian: Endian, mut output: T) -> Result<(), Error>
+where T: Write {
+    let Metadata { name, author, description, created, annotations } = metadata;
+
+    // write which fields are present
+    let mut present = 0;
+    if name.is_some() { present |= METADATA_NAME; }
+    if author.is_some() { present |= METADATA_AUTHOR; }
+    if description.is_some() { present |= METADATA_DESCRIPTION; }
+    if created.is_some() { present |= METADATA_CREATED; }
+    if !annotations.is_empty() { present |= METADATA_ANNOTATIONS; }
+
+    output.write_all(&[present])?;
+
+    // write the fields
+    for s in [name, author, description].iter().copied().flatten() {
+        endian.write_string(&mut output, s)?;
+    }
+
+    if let Some(created) = created {
+        endian.write_u64(&mut output, *created)?;
+    }
+
+    if !annotations.is_empty() {
+        write_annotations(annotations, endian, &mut output)?;
+    }
+
+    Ok(())
+}
+
+fn write_annotations<T>(annotations: &FieldAnnotations, endian: Endian, mut output: T) -> Result<(), Error>
+where T: Write {
+    let uint = |n: usize| u32::try_from(n).map_err(|_| Error::new(
+        ErrorKind::InvalidInput,
+        "annotations must have at most 4294967295 entries and positions",
+    ));
+
+    endian.write_u32(&mut output, uint(annotations.len())?)?;
+
+    for (pos, annotation) in annotations.iter() {
+        let Annotation { warp_target, note, region } = annotation;
+
+        endian.write_u32(&mut output, uint(pos.x)?)?;
+        endian.write_u32(&mut output, uint(pos.y)?)?;
+
+        let mut present = 0;
+        if warp_target.is_some() { present |= ANNOTATION_WARP; }
+        if note.is_some() { present |= ANNOTATION_NOTE; }
+        if region.is_some() { present |= ANNOTATION_REGION; }
+
+        output.write_all(&[present])?;
+
+        if let Some(target) = warp_target {
+            endian.write_u32(&mut output, uint(target.x)?)?;
+            endian.write_u32(&mut output, uint(target.y)?)?;
+        }
+
+        for s in [note, region].iter().copied().flatten() {
+            endian.write_string(&mut output, s)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_metadata<T>(endian: Endian, input: &mut Reader<T>) -> Result<Metadata, DecodeError>
+where T: Read {
+    let present = input.read_u8()?;
+
+    let mut metadata = Metadata::default();
+
+    if present & METADATA_NAME != 0 {
+        metadata.name = Some(input.read_string(endian)?);
+    }
+    if present & METADATA_AUTHOR != 0 {
+        metadata.author = Some(input.read_string(endian)?);
+    }
+    if present & METADATA_DESCRIPTION != 0 {
+        metadata.description = Some(input.read_string(endian)?);
+    }
+    if present & METADATA_CREATED != 0 {
+        metadata.created = Some(input.read_u64(endian)?);
+    }
+    if present & METADATA_ANNOTATIONS != 0 {
+        metadata.annotations = read_annotations(endian, input)?;
+    }
+
+    Ok(metadata)
+}
+
+fn read_annotations<T>(endian: Endian, input: &mut Reader<T>) -> Result<FieldAnnotations, DecodeError>
+where T: Read {
+    let count = input.read_u32(endian)?;
+    let mut annotations = FieldAnnotations::new();
+
+    let read_pos = |input: &mut Reader<T>| -> Result<Pos, DecodeError> {
+        let x = input.read_u32(endian)? as usize;
+        let y = input.read_u32(endian)? as usize;
+
+        Ok(Pos::new(x, y))
+    };
+
+    for _ in 0..count {
+        let pos = read_pos(input)?;
+
+        let offset = input.offset();
+        let present = input.read_u8()?;
+
+        if present & !(ANNOTATION_WARP | ANNOTATION_NOTE | ANNOTATION_REGION) != 0 {
+            return Err(DecodeError::invalid(offset, format!(
+                "unknown annotation flags {:#04x}", present
+            )));
+        }
+
+        let mut annotation = Annotation::default();
+
+        if present & ANNOTATION_WARP != 0 {
+            annotation.warp_target = Some(read_pos(input)?);
+        }
+        if present & ANNOTATION_NOTE != 0 {
+            annotation.note = Some(input.read_string(endian)?);
+        }
+        if present & ANNOTATION_REGION != 0 {
+            annotation.region = Some(input.read_string(endian)?);
+        }
+
+        annotations.insert(pos, annotation);
+    }
+
+    Ok(annotations)
+}
+
+/// A streaming decoder for the `.fldx` format.
+///
+/// The decoder reads the header up front, and then yields each panel with its
+/// position as it is read, in row-major order. Compressed files cannot be
+/// streamed; use [`decode()`] for them.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::fldx::{self, Decoder};
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let mut data = Vec::new();
+/// fldx::encode(&field, &mut data).unwrap();
+///
+/// let decoder = Decoder::new(&data[..]).unwrap();
+/// assert_eq!(decoder.width(), 2);
+///
+/// for panel in decoder {
+///     let (x, y, panel) = panel.unwrap();
+///     assert_eq!(panel.kind, field.get((x, y)).kind);
+/// }
+/// ```
+pub struct Decoder<R> {
+    input: Reader<Crc32Reader<R>>,
+    /// Whether this is a version 1 file, which has no flags.
+    v1: bool,
+    flags: u8,
+    width: usize,
+    height: usize,
+    metadata: Metadata,
+    /// The flattened index of the next panel.
+    next: usize,
+    /// The panel of the current run, and how many are left.
+    run: Option<(Panel, usize)>,
+    state: State,
+    strict: bool,
+    done: bool,
+}
+
+impl<R> Decoder<R>
+where R: Read {
+    /// Creates a new decoder, reading the header of the file.
+    pub fn new(input: R) -> Result<Decoder<R>, DecodeError> {
+        Decoder::with_options(input, &DecodeOptions::default())
+    }
+
+    /// Creates a new decoder with options, reading the header of the file.
+    ///
+    /// Panels replaced by [`DecodeOptions::placeholder`] are reported by
+    /// [`substitutions`](Decoder::substitutions) as they are read.
+    pub fn with_options(input: R, options: &DecodeOptions) -> Result<Decoder<R>, DecodeError> {
+        let mut input = Reader::new(Crc32Reader::new(input));
+
+        let mut header = [0u8; 4];
+        input.read_bytes(&mut header)?;
+
+        let v1 = &header != MAGIC;
+
+        let (flags, metadata, width, height) = if !v1 {
+            let flags = read_header(&mut input)?;
+
+            if flags & FLAG_DEFLATE != 0 {
+                return Err(DecodeError::invalid(5, "cannot stream compressed fldx files"));
+            }
+
+            let endian = endian_of(flags);
+
+            let metadata = if flags & FLAG_METADATA != 0 {
+                read_metadata(endian, &mut input)?
+            } else {
+                Metadata::default()
+            };
+
+            // read the size data
+            let offset = input.offset();
+
+            let (width, height) = if flags & FLAG_RLE != 0 {
+                (input.read_varint_usize()?, input.read_varint_usize()?)
+            } else {
+                (input.read_u16(endian)? as usize, input.read_u16(endian)? as usize)
+            };
+
+            options.check_size(width, height, offset)?;
+
+            (flags, metadata, width, height)
+        } else {
+            // this is a version 1 file, and the header is the size data
+            let mut header = Reader::new(&header[..]);
+            let width = header.read_u16(options.endian)? as usize;
+            let height = header.read_u16(options.endian)? as usize;
+
+            options.check_size(width, height, 0)?;
+
+            (0, Metadata::default(), width, height)
+        };
+
+        Ok(Decoder {
+            input,
+            v1,
+            flags,
+            width,
+            height,
+            metadata,
+            next: 0,
+            run: None,
+            state: State::new(options),
+            strict: options.strict,
+            done: false,
+        })
+    }
+
+    /// Gets the width of the field.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the height of the field.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets the metadata of the field.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Gets the panels of unknown kinds that have been replaced so far.
+    pub fn substitutions(&self) -> &[Substitution] {
+        &self.state.substitutions
+    }
+
+    fn read_next(&mut self) -> Result<Option<(usize, usize, Panel)>, DecodeError> {
+        let len = self.width * self.height;
+
+        if self.next >= len {
+            self.finish()?;
+            return Ok(None);
+        }
+
+        let panel = if self.flags & FLAG_RLE != 0 {
+            // start a new run if the last one ran out, skipping empty runs
+            // like the whole-buffer decoder does
+            while self.run.is_none() {
+                let offset = self.input.offset();
+                let count = self.input.read_varint_usize()?;
+
+                let mut panel_buf = [0u8; 2];
+                self.input.read_bytes(&mut panel_buf)?;
+
+                if count > len - self.next {
+                    return Err(DecodeError::SizeMismatch {
+                        offset,
+                        expected: len,
+                        got: self.next.saturating_add(count),
+                    });
+                }
+
+                let panel = self.parse_panel(panel_buf, count)?;
+
+                if count > 0 {
+                    self.run = Some((panel, count));
+                }
+            }
+
+            let (panel, count) = self.run.take().expect("run was started");
+
+            if count > 1 {
+                self.run = Some((panel.clone(), count - 1));
+            }
+
+            panel
+        } else {
+            self.read_panel()?
+        };
+
+        let idx = self.next;
+        self.next += 1;
+
+        Ok(Some((idx % self.width, idx / self.width, panel)))
+    }
+
+    /// Reads a single panel.
+    fn read_panel(&mut self) -> Result<Panel, DecodeError> {
+        let mut panel_buf = [0u8; 2];
+
+        if !self.input.fill(&mut panel_buf)? {
+            return Err(DecodeError::SizeMismatch {
+                offset: self.input.offset(),
+                expected: self.width * self.height,
+                got: self.next,
+            });
+        }
+
+        self.parse_panel(panel_buf, 1)
+    }
+
+    /// Parses a panel that repeats `count` times.
+    fn parse_panel(&mut self, panel_buf: [u8; 2], count: usize) -> Result<Panel, DecodeError> {
+        let kind = self.state.parse(
+            panel_buf[0],
+            self.input.offset() - 2,
+            self.next..self.next + count,
+            self.width,
+        )?;
+
+        Ok(Panel::from_internal(kind, panel_buf[1]))
     }
+
+    fn finish(&mut self) -> Result<(), DecodeError> {
+        if self.flags & FLAG_CHECKSUM != 0 {
+            let got = self.input.get_ref().crc;
+            let offset = self.input.offset();
+
+            // the trailer is not part of the checksum
+            let mut rest = Reader::with_offset(&mut self.input.get_mut().inner, offset);
+            let expected = rest.read_u32(endian_of(self.flags))?;
+
+            if expected != got {
+                return Err(DecodeError::ChecksumMismatch { offset, expected, got });
+            }
+
+            if self.strict {
+                rest.expect_end()?;
+            }
+        } else if self.v1 {
+            // version 1 files end with their panels, so more panels mean the
+            // header is wrong
+            let offset = self.input.offset();
+            let extra = self.input.count_rest(2)?;
+
+            if extra > 0 {
+                return Err(DecodeError::SizeMismatch {
+                    offset,
+                    expected: self.width * self.height,
+                    got: self.next.saturating_add(extra),
+                });
+            }
+        } else if self.strict {
+            self.input.expect_end()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Iterator for Decoder<R>
+where R: Read {
+    type Item = Result<(usize, usize, Panel), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_next() {
+            Ok(Some(panel)) => Some(Ok(panel)),
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            (0, Some(0))
+        } else {
+            let left = self.width * self.height - self.next;
+            // an error may be yielded in place of the panels
+            (left.min(1), Some(left.max(1)))
+        }
+    }
+}
+
+/// A read-only view of a `.fldx` file in memory.
+///
+/// Unlike [`decode()`], a view does not copy the panels out of the data, so it
+/// is cheap to create for many files at once. Run-length encoded and
+/// compressed files cannot be viewed.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::fldx::{self, FieldView};
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let mut data = Vec::new();
+/// fldx::encode(&field, &mut data).unwrap();
+///
+/// let view = FieldView::new(&data).unwrap();
+/// assert_eq!(view.width(), 2);
+/// assert_eq!(view.get(1, 0).kind, Draw);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FieldView<'a> {
+    panels: &'a [u8],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> FieldView<'a> {
+    /// Creates a new view, checking that the data is a valid `.fldx` file.
+    ///
+    /// Version 1 files are assumed to be Little Endian.
+    pub fn new(data: &'a [u8]) -> Result<FieldView<'a>, DecodeError> {
+        let mut input = Reader::new(data);
+
+        let mut header = [0u8; 4];
+        input.read_bytes(&mut header)?;
+
+        let (width, height, trailer) = if &header == MAGIC {
+            let flags = read_header(&mut input)?;
+
+            if flags & (FLAG_RLE | FLAG_DEFLATE) != 0 {
+                return Err(DecodeError::invalid(
+                    5,
+                    "cannot view run-length encoded or compressed fldx files",
+                ));
+            }
+
+            let endian = endian_of(flags);
+
+            // read the metadata like the decoder does, so the view never
+            // misses a section
+            if flags & FLAG_METADATA != 0 {
+                read_metadata(endian, &mut input)?;
+            }
+
+            let width = input.read_u16(endian)? as usize;
+            let height = input.read_u16(endian)? as usize;
+
+            let trailer = if flags & FLAG_CHECKSUM != 0 {
+                let (contents, trailer) = data.split_at(data.len().saturating_sub(4));
+                let offset = contents.len() as u64;
+                let expected = Reader::with_offset(trailer, offset).read_u32(endian)?;
+                let got = crc32(contents);
+
+                if expected != got {
+                    return Err(DecodeError::ChecksumMismatch { offset, expected, got });
+                }
+
+                4
+            } else {
+                0
+            };
+
+            (width, height, trailer)
+        } else {
+            let mut header = Reader::new(&header[..]);
+            let width = header.read_u16(Endian::Little)? as usize;
+            let height = header.read_u16(Endian::Little)? as usize;
+
+            (width, height, 0)
+        };
+
+        // verify the panel data is all there
+        let start = input.offset();
+        let rest = *input.get_ref();
+        let got = rest.len().saturating_sub(trailer) / 2;
+
+        if rest.len() != width * height * 2 + trailer {
+            return Err(DecodeError::SizeMismatch {
+                offset: data.len().saturating_sub(trailer) as u64,
+                expected: width * height,
+                got,
+            });
+        }
+
+        let panels = &rest[..width * height * 2];
+
+        // verify the panel kinds now, so `get` cannot fail
+        for (idx, panel) in panels.chunks(2).enumerate() {
+            panel_kind(panel[0], start + idx as u64 * 2)?;
+        }
+
+        Ok(FieldView { panels, width, height })
+    }
+
+    /// Gets the width of the field.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the height of the field.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets a panel of the field.
+    ///
+    /// # Panics
+    /// Will panic if the position is out of bounds.
+    pub fn get(&self, x: usize, y: usize) -> Panel {
+        assert!(x < self.width, "x ({}) is out of bounds", x);
+        assert!(y < self.height, "y ({}) is out of bounds", y);
+
+        let idx = (y * self.width + x) * 2;
+        self.panel_at(idx)
+    }
+
+    /// Gets an iterator over all of the panels of the field, row-major.
+    pub fn panels(&self) -> impl DoubleEndedIterator<Item = Panel> + ExactSizeIterator + 'a {
+        let view = *self;
+
+        (0..self.width * self.height)
+            .map(move |idx| view.panel_at(idx * 2))
+    }
+
+    /// Copies the view into an owned [`Field`].
+    pub fn to_field(&self) -> Field {
+        Field::new_vec(self.panels().collect(), self.width, self.height)
+    }
+
+    fn panel_at(&self, idx: usize) -> Panel {
+        // the kind was checked when the view was made
+        Panel::from_internal(PanelKind::from(self.panels[idx]), self.panels[idx + 1])
+    }
+}
+
+/// The `.fldx` format, as a [`FieldFormat`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fldx;
+
+impl FieldFormat for Fldx {
+    fn name(&self) -> &str {
+        "fldx"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["fldx"]
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        sniff(data)
+    }
+
+    fn encode(&self, field: &Field, output: &mut dyn Write) -> Result<(), Error> {
+        encode(field, output)
+    }
+
+    fn decode(&self, input: &mut dyn Read) -> Result<Field, DecodeError> {
+        decode(input)
+    }
+}
+
+/// Checks if some data looks like a `.fldx` file.
+///
+/// The data's length must match the dimensions in its header, and every
+/// panel must have a known kind.
+pub fn sniff(data: &[u8]) -> bool {
+    if data.len() < 4 {
+        return false;
+    }
+
+    // version 2 files are easy to spot
+    if data.starts_with(MAGIC) {
+        return data.get(4) == Some(&VERSION);
+    }
+
+    let width = u16::from_le_bytes([data[0], data[1]]) as usize;
+    let height = u16::from_le_bytes([data[2], data[3]]) as usize;
+
+    data.len() == 4 + width * height * 2
+        && data[4..].chunks(2).all(|panel| PanelKind::from(panel[0]).is_known())
 }
 
 #[cfg(feature = "base64")]
@@ -99,7 +1394,7 @@ pub fn encode_base64(field: &Field) -> Result<String, Error> {
 
 /// Decodes a field from a Base64 string.
 #[cfg(feature = "base64")]
-pub fn decode_base64(data: &str) -> Result<Field, Error> {
+pub fn decode_base64(data: &str) -> Result<Field, DecodeError> {
     let mut cursor = Cursor::new(data);
     let mut sr = DecoderReader::new(&mut cursor, BASE64_CONFIG);
 
@@ -0,0 +1,67 @@
+//! Support for encoding fields as JSON.
+//!
+//! Unlike the binary formats, the JSON format is meant to be read and written
+//! by humans and web clients. A field is encoded as an object with its
+//! `width`, `height`, and a row-major array of `panels`. Each panel is an
+//! object with its `kind` by name, and its `exits` and `exits_backtrack` as
+//! lists of direction names.
+//!
+//! ```json
+//! {
+//!   "width": 2,
+//!   "height": 1,
+//!   "panels": [
+//!     { "kind": "Home", "exits": ["east"], "exits_backtrack": [] },
+//!     { "kind": "Draw", "exits": [], "exits_backtrack": ["west"] }
+//!   ]
+//! }
+//! ```
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{Field, Panel, PanelKind::*};
+//! use citrus_common::format::json;
+//!
+//! let field = Field::new_slice(&[
+//!     &[Panel::new(Home), Panel::new(Draw)],
+//! ]);
+//!
+//! let data = json::encode_string(&field).unwrap();
+//! let decoded = json::decode_str(&data).unwrap();
+//!
+//! assert_eq!(decoded.get((1, 0)).kind, Draw);
+//! ```
+
+use crate::Field;
+
+use std::io::{Read, Write, Error};
+
+/// Encode a field to compact JSON.
+pub fn encode<T>(field: &Field, output: T) -> Result<(), Error>
+where T: Write {
+    serde_json::to_writer(output, field)?;
+    Ok(())
+}
+
+/// Encode a field to indented JSON.
+pub fn encode_pretty<T>(field: &Field, output: T) -> Result<(), Error>
+where T: Write {
+    serde_json::to_writer_pretty(output, field)?;
+    Ok(())
+}
+
+/// Decode a field from JSON.
+pub fn decode<T>(input: T) -> Result<Field, Error>
+where T: Read {
+    Ok(serde_json::from_reader(input)?)
+}
+
+/// Encodes a field to a compact JSON string.
+pub fn encode_string(field: &Field) -> Result<String, Error> {
+    Ok(serde_json::to_string(field)?)
+}
+
+/// Decodes a field from a JSON string.
+pub fn decode_str(data: &str) -> Result<Field, Error> {
+    Ok(serde_json::from_str(data)?)
+}
@@ -1,14 +1,46 @@
-//! Tools to encode and decode field data to binary representations.
+//! Tools to encode and decode field data to binary and text representations.
 //!
 //! * [`fldx`]: the community `.fldx` format, with support for dynamic width
 //!   and height values.
 //! * [`fld`]: 100% OJ's own `.fld` format.
+//! * [`csv`]: a spreadsheet-friendly grid of kind abbreviations.
+//! * [`fldpak`]: an archive of several named fields.
+//! * [`fldset`]: a [`FieldSet`](crate::FieldSet) of several field states.
+//! * [`patch`]: compact binary deltas between two fields.
+//! * [`sharecode`]: short, versioned text codes for sharing fields in chat
+//!   and URLs.
+//! * [`json`]: a human-readable JSON format, behind the `json` feature.
+//! * [`yaml`]: a human-readable YAML format, behind the `yaml` feature.
+//!
+//! Binary formats also implement [`FieldFormat`], so they can be used through
+//! a [`Registry`] without knowing the format ahead of time.
 
 pub mod fldx;
 pub mod fld;
+pub mod csv;
+pub mod fldpak;
+pub mod fldset;
+pub mod patch;
+pub mod sharecode;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+mod diagnostics;
+mod error;
+mod reader;
+mod registry;
+
+pub use diagnostics::{Diagnostics, Warning};
+pub use error::DecodeError;
+pub use registry::{FieldFormat, Registry};
+
+use reader::{Reader, State, panel_kind};
+
+use crate::PanelKind;
 
 use std::io::{Read, Write, Error, ErrorKind};
-use std::fmt::{Display, Formatter, Result as FmtResult};
 
 #[cfg(feature = "base64")]
 use base64::{Config, CharacterSet};
@@ -16,44 +48,299 @@ use base64::{Config, CharacterSet};
 #[cfg(feature = "base64")]
 const BASE64_CONFIG: Config = Config::new(CharacterSet::UrlSafe, true);
 
-/// An error that indicates an invalid size of the input data.
-#[derive(Debug)]
-pub struct InvalidSize {
-    pub expected: usize,
-    pub got: usize
+/// A field format, as guessed by [`detect`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Format {
+    /// The official [`.fld`](fld) format.
+    Fld,
+    /// The community [`.fldx`](fldx) format.
+    Fldx,
+    /// The [`.fldpak`](fldpak) archive format.
+    Fldpak,
+    /// The [`.fldset`](fldset) multi-state format.
+    Fldset,
+    /// The data does not look like any known format.
+    Unknown,
+}
+
+/// Guesses the format of some field data.
+///
+/// This reads all of `input`, as some formats can only be told apart by the
+/// length of the data.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::{self, fldx, Format};
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let mut data = Vec::new();
+/// fldx::encode(&field, &mut data).unwrap();
+///
+/// assert_eq!(format::detect(&data[..]).unwrap(), Format::Fldx);
+/// ```
+pub fn detect<T>(mut input: T) -> Result<Format, Error>
+where T: Read {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    Ok(detect_bytes(&data))
+}
+
+/// Guesses the format of some field data already in memory.
+pub fn detect_bytes(data: &[u8]) -> Format {
+    // formats with magic bytes are unambiguous, so check them first; after
+    // that, the fldx check is stricter than the fld check
+    if fldpak::sniff(data) {
+        Format::Fldpak
+    } else if fldset::sniff(data) {
+        Format::Fldset
+    } else if fldx::sniff(data) {
+        Format::Fldx
+    } else if fld::sniff(data) {
+        Format::Fld
+    } else {
+        Format::Unknown
+    }
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+
+        table[i] = c;
+        i += 1;
+    }
+
+    table
+}
+
+/// Computes the CRC-32 (ISO-HDLC) checksum of some data.
+fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// Continues a CRC-32 checksum with more data.
+fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    !data.iter().fold(!crc, |c, b| {
+        CRC32_TABLE[((c ^ *b as u32) & 0xFF) as usize] ^ (c >> 8)
+    })
+}
+
+/// A reader that checksums everything read through it.
+struct Crc32Reader<R> {
+    inner: R,
+    crc: u32,
 }
 
-impl InvalidSize {  
-    pub const fn new(expected: usize, got: usize) -> InvalidSize {
-        InvalidSize { expected, got }
+impl<R> Crc32Reader<R> {
+    fn new(inner: R) -> Crc32Reader<R> {
+        Crc32Reader { inner, crc: 0 }
     }
 }
 
-impl Display for InvalidSize {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(
-            f, "invalid size of data, expected {} bytes, got {} bytes",
-            self.expected, self.got
-        )
+impl<R> Read for Crc32Reader<R>
+where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let n = self.inner.read(buf)?;
+        self.crc = crc32_update(self.crc, &buf[..n]);
+        Ok(n)
     }
 }
 
-impl std::error::Error for InvalidSize { }
+/// The byte order of multi-byte integers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+/// Options for decoding fields.
+///
+/// Not every option applies to every format.
+#[derive(Clone, Debug, Default)]
+pub struct DecodeOptions {
+    /// The byte order of integers, for formats that do not record it.
+    pub endian: Endian,
+    /// The kind to decode panels of unknown kinds as, instead of failing.
+    ///
+    /// Game updates add new panel kinds before this crate knows about them,
+    /// so this allows loading newer fields anyway. Functions like
+    /// [`fldx::decode_with_substitutions`] report which panels were replaced.
+    pub placeholder: Option<PanelKind>,
+    /// Whether to decode panels of unknown kinds as
+    /// [`PanelKind::Unknown`], instead of failing.
+    ///
+    /// Unknown kinds keep their bytes, so the field encodes back the same.
+    /// This takes precedence over `placeholder`.
+    pub keep_unknown: bool,
+    /// The largest width or height to accept.
+    pub max_dimension: Option<usize>,
+    /// The largest number of panels to accept.
+    pub max_panels: Option<usize>,
+    /// Whether to fail if there is data after the end of the field.
+    ///
+    /// Files that end with their panels, like [`fld`] and version 1 [`fldx`]
+    /// files, always fail on extra data, as it means their dimensions are
+    /// wrong.
+    pub strict: bool,
+}
 
-fn read_u16<T>(mut input: T) -> Result<u16, Error> 
-where T: Read {
-    let mut num_buf = [0u8; 2];
-    
-    if input.read(&mut num_buf)? < 2 {
-        Err(Error::new(ErrorKind::UnexpectedEof, "unexpected end of file"))
-    } else {
-        Ok(u16::from_le_bytes(num_buf))
+impl DecodeOptions {
+    /// Creates options suited to decoding files from untrusted sources.
+    ///
+    /// Fields are limited to 256x256 panels, and trailing data is rejected,
+    /// so absurd headers and padded files fail early instead of exhausting
+    /// memory.
+    pub fn untrusted() -> DecodeOptions {
+        DecodeOptions {
+            max_dimension: Some(256),
+            max_panels: Some(256 * 256),
+            strict: true,
+            ..DecodeOptions::default()
+        }
     }
+
+    /// Checks that a field's dimensions are within the limits, returning how
+    /// many panels it has.
+    fn check_size(&self, width: usize, height: usize, offset: u64) -> Result<usize, DecodeError> {
+        let max_dimension = self.max_dimension.unwrap_or(usize::MAX);
+        let max_panels = self.max_panels.unwrap_or(usize::MAX);
+
+        width.checked_mul(height)
+            .filter(|len| *len <= max_panels && width <= max_dimension && height <= max_dimension)
+            .ok_or(DecodeError::TooLarge { offset })
+    }
+
+    /// Gets the most panels a field may have.
+    fn panel_limit(&self) -> Option<usize> {
+        let by_dimension = self.max_dimension.map(|max| max.saturating_mul(max));
+
+        match (self.max_panels, by_dimension) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Fails if there is more input, and trailing data is not allowed.
+    ///
+    /// Otherwise, any trailing data is skipped and warned about, if warnings
+    /// are being collected.
+    fn check_end<T>(&self, input: &mut Reader<T>, state: &mut State) -> Result<(), DecodeError>
+    where T: Read {
+        if self.strict {
+            input.expect_end()
+        } else if let Some(warnings) = state.warnings.as_mut() {
+            let offset = input.offset();
+            let len = std::io::copy(input, &mut std::io::sink())?;
+
+            if len > 0 {
+                warnings.push(Warning::TrailingData { offset, len });
+            }
+
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A panel of an unknown kind that was replaced by
+/// [`DecodeOptions::placeholder`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Substitution {
+    /// The x position of the panel.
+    pub x: usize,
+    /// The y position of the panel.
+    pub y: usize,
+    /// The byte offset of the panel's kind in the input.
+    pub offset: u64,
+    /// The unknown kind.
+    pub value: u8,
+}
+
+impl Endian {
+    fn write_u16<T>(self, mut output: T, data: u16) -> Result<(), Error>
+    where T: Write {
+        output.write_all(&match self {
+            Endian::Little => data.to_le_bytes(),
+            Endian::Big => data.to_be_bytes(),
+        })
+    }
+
+    fn write_u32<T>(self, mut output: T, data: u32) -> Result<(), Error>
+    where T: Write {
+        output.write_all(&match self {
+            Endian::Little => data.to_le_bytes(),
+            Endian::Big => data.to_be_bytes(),
+        })
+    }
+
+    fn write_u64<T>(self, mut output: T, data: u64) -> Result<(), Error>
+    where T: Write {
+        output.write_all(&match self {
+            Endian::Little => data.to_le_bytes(),
+            Endian::Big => data.to_be_bytes(),
+        })
+    }
+
+    /// Writes a string as a `ushort` length followed by its UTF-8 bytes.
+    fn write_string<T>(self, mut output: T, s: &str) -> Result<(), Error>
+    where T: Write {
+        if s.len() > u16::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "strings must be at most 65535 bytes long",
+            ));
+        }
+
+        self.write_u16(&mut output, s.len() as u16)?;
+        output.write_all(s.as_bytes())
+    }
+}
+
+fn write_u16<T>(output: T, data: u16) -> Result<(), Error>
+where T: Write {
+    Endian::Little.write_u16(output, data)
+}
+
+fn write_u32<T>(output: T, data: u32) -> Result<(), Error>
+where T: Write {
+    Endian::Little.write_u32(output, data)
 }
 
-fn write_u16<T>(mut output: T, data: u16) -> Result<(), Error> 
+fn write_string<T>(output: T, s: &str) -> Result<(), Error>
 where T: Write {
-    output.write(&data.to_le_bytes())?;
-    Ok(())
+    Endian::Little.write_string(output, s)
 }
 
+/// Writes an unsigned LEB128 varint.
+fn write_varint<T>(mut output: T, mut data: u64) -> Result<(), Error>
+where T: Write {
+    loop {
+        let byte = (data & 0x7F) as u8;
+        data >>= 7;
+
+        if data == 0 {
+            return output.write_all(&[byte]);
+        } else {
+            output.write_all(&[byte | 0x80])?;
+        }
+    }
+}
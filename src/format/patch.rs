@@ -0,0 +1,304 @@
+//! Binary deltas between fields.
+//!
+//! A [`Patch`] records how to turn one field into another. Patches are much
+//! smaller than the fields themselves when only a few panels change, so they
+//! are well suited to edit histories and syncing changes over a network.
+//!
+//! # Format
+//! **All integers, unless specified, are unsigned LEB128 varints.**
+//!
+//! All patches start with the magic bytes `FPAT`, followed by a `byte`
+//! version number (`1`). Then follows the width and height of the field the
+//! patch applies to, and a Little Endian `uint` CRC-32 checksum of that
+//! field's panels, each encoded as [two bytes like in the `.fldx`
+//! format][1]. A patch can only be applied to a field with the same
+//! dimensions and checksum.
+//!
+//! Then, a `byte` describes the kind of patch:
+//!
+//! * `0`: the field keeps its dimensions. A count of changed panels follows,
+//!   and then each change: the number of panels skipped since the last
+//!   change, followed by the two bytes of the new panel.
+//! * `1`: the field changes dimensions. A complete [`.fldx`][1] file of the
+//!   new field follows.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{Field, Panel, PanelKind::*};
+//! use citrus_common::format::patch::{self, Patch};
+//!
+//! let old = Field::new_slice(&[
+//!     &[Panel::new(Home), Panel::new(Draw)],
+//! ]);
+//!
+//! let mut new = old.clone();
+//! new.get_mut((1, 0)).kind = Bonus;
+//!
+//! let mut data = Vec::new();
+//! patch::encode(&Patch::diff(&old, &new), &mut data).unwrap();
+//!
+//! // ...later, or elsewhere...
+//! let mut field = old.clone();
+//! patch::decode(&data[..]).unwrap()
+//!     .apply(&mut field)
+//!     .unwrap();
+//!
+//! assert_eq!(field.get((1, 0)).kind, Bonus);
+//! ```
+//!
+//! [1]: ../fldx/index.html
+
+use super::*;
+
+use crate::{Field, Panel};
+
+use std::io::{Read, Write, Error, ErrorKind};
+
+/// The magic bytes at the start of every patch.
+const MAGIC: &[u8; 4] = b"FPAT";
+
+/// The version of the format this crate writes.
+pub const VERSION: u8 = 1;
+
+const KIND_CHANGES: u8 = 0;
+const KIND_REPLACE: u8 = 1;
+
+/// A delta between two fields.
+#[derive(Clone)]
+pub struct Patch {
+    base_dims: (usize, usize),
+    base_checksum: u32,
+    body: Body,
+}
+
+#[derive(Clone)]
+enum Body {
+    /// Panels to change, by flattened index, in ascending order.
+    Changes(Vec<(usize, Panel)>),
+    /// A field to replace the old field with.
+    Replace(Field),
+}
+
+impl Patch {
+    /// Computes the patch that turns `old` into `new`.
+    pub fn diff(old: &Field, new: &Field) -> Patch {
+        let body = if old.width() == new.width() && old.height() == new.height() {
+            Body::Changes(
+                old.iter()
+                    .enumerate()
+                    .filter(|(_, (x, y))| *old.get((*x, *y)) != *new.get((*x, *y)))
+                    .map(|(idx, (x, y))| (idx, new.get((x, y)).clone()))
+                    .collect()
+            )
+        } else {
+            Body::Replace(new.clone())
+        };
+
+        Patch {
+            base_dims: (old.width(), old.height()),
+            base_checksum: checksum(old),
+            body,
+        }
+    }
+
+    /// Checks if applying the patch would change nothing.
+    pub fn is_empty(&self) -> bool {
+        match &self.body {
+            Body::Changes(changes) => changes.is_empty(),
+            Body::Replace(_) => false,
+        }
+    }
+
+    /// Gets the dimensions of the field this patch applies to.
+    pub fn base_dims(&self) -> (usize, usize) {
+        self.base_dims
+    }
+
+    /// Checks if this patch can be applied to a field.
+    pub fn applies_to(&self, field: &Field) -> bool {
+        (field.width(), field.height()) == self.base_dims
+            && checksum(field) == self.base_checksum
+    }
+
+    /// Applies the patch to a field.
+    ///
+    /// Fails without changing the field if the field is not the one the patch
+    /// was made from.
+    pub fn apply(&self, field: &mut Field) -> Result<(), Error> {
+        if (field.width(), field.height()) != self.base_dims {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "patch applies to a {}x{} field, got a {}x{} field",
+                    self.base_dims.0, self.base_dims.1,
+                    field.width(), field.height(),
+                ),
+            ));
+        }
+
+        let got = checksum(field);
+
+        if got != self.base_checksum {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "patch applies to a field with checksum {:#010x}, got {:#010x}",
+                    self.base_checksum, got,
+                ),
+            ));
+        }
+
+        match &self.body {
+            Body::Changes(changes) => {
+                let width = field.width();
+
+                for (idx, panel) in changes.iter() {
+                    *field.get_mut((idx % width, idx / width)) = panel.clone();
+                }
+            },
+            Body::Replace(new) => *field = new.clone(),
+        }
+
+        Ok(())
+    }
+}
+
+/// Encode a patch.
+pub fn encode<T>(patch: &Patch, mut output: T) -> Result<(), Error>
+where T: Write {
+    // write the header
+    output.write_all(MAGIC)?;
+    output.write_all(&[VERSION])?;
+    write_varint(&mut output, patch.base_dims.0 as u64)?;
+    write_varint(&mut output, patch.base_dims.1 as u64)?;
+    write_u32(&mut output, patch.base_checksum)?;
+
+    match &patch.body {
+        Body::Changes(changes) => {
+            output.write_all(&[KIND_CHANGES])?;
+            write_varint(&mut output, changes.len() as u64)?;
+
+            let mut next = 0;
+
+            for (idx, panel) in changes.iter() {
+                write_varint(&mut output, (idx - next) as u64)?;
+                output.write_all(&[panel.kind.into(), panel.exits_internal()])?;
+
+                next = idx + 1;
+            }
+
+            Ok(())
+        },
+        Body::Replace(field) => {
+            output.write_all(&[KIND_REPLACE])?;
+
+            let options = fldx::EncodeOptions {
+                run_length: true,
+                ..fldx::EncodeOptions::default()
+            };
+
+            fldx::encode_with(field, &options, output)
+        },
+    }
+}
+
+/// Decode a patch.
+pub fn decode<T>(input: T) -> Result<Patch, DecodeError>
+where T: Read {
+    decode_with(input, &DecodeOptions::default())
+}
+
+/// Decode a patch, with the limits of `options` applying to both the field
+/// it applies to and any field it replaces that with.
+pub fn decode_with<T>(input: T, options: &DecodeOptions) -> Result<Patch, DecodeError>
+where T: Read {
+    let mut input = Reader::new(input);
+
+    // read the header
+    let mut magic = [0u8; 4];
+    input.read_bytes(&mut magic)?;
+
+    if &magic != MAGIC {
+        return Err(DecodeError::invalid(0, "not a field patch"));
+    }
+
+    let version = input.read_u8()?;
+
+    if version != VERSION {
+        return Err(DecodeError::UnsupportedVersion {
+            offset: 4,
+            version: version as u32,
+            supported: VERSION as u32,
+        });
+    }
+
+    let offset = input.offset();
+    let width = input.read_varint_usize()?;
+    let height = input.read_varint_usize()?;
+    let len = options.check_size(width, height, offset)?;
+
+    let base_checksum = input.read_u32(Endian::Little)?;
+
+    let kind_offset = input.offset();
+    let kind = input.read_u8()?;
+
+    let body = match kind {
+        KIND_CHANGES => {
+            let offset = input.offset();
+            let count = input.read_varint_usize()?;
+
+            if count > len {
+                return Err(DecodeError::SizeMismatch { offset, expected: len, got: count });
+            }
+
+            // the count is only checked against the dimensions, so the
+            // changes are not allocated up front
+            let mut changes = Vec::new();
+            let mut state = State::new(options);
+            let mut next = 0usize;
+            let mut panel_buf = [0u8; 2];
+
+            for _ in 0..count {
+                let offset = input.offset();
+                let idx = next.checked_add(input.read_varint_usize()?)
+                    .filter(|idx| *idx < len)
+                    .ok_or_else(|| DecodeError::invalid(
+                        offset,
+                        "patch changes a panel out of bounds",
+                    ))?;
+
+                input.read_bytes(&mut panel_buf)?;
+
+                let panel_kind = state.parse(panel_buf[0], input.offset() - 2, idx..idx + 1, width)?;
+
+                changes.push((idx, Panel::from_internal(panel_kind, panel_buf[1])));
+                next = idx + 1;
+            }
+
+            Body::Changes(changes)
+        },
+        KIND_REPLACE => Body::Replace(fldx::decode_part(&mut input, options)?),
+        kind => return Err(DecodeError::invalid(
+            kind_offset,
+            format!("unknown patch kind {}", kind),
+        )),
+    };
+
+    Ok(Patch {
+        base_dims: (width, height),
+        base_checksum,
+        body,
+    })
+}
+
+/// Computes the checksum of a field's panels.
+fn checksum(field: &Field) -> u32 {
+    let data = field.iter()
+        .flat_map(|(x, y)| {
+            let panel = field.get((x, y));
+            [panel.kind.into(), panel.exits_internal()]
+        })
+        .collect::<Vec<u8>>();
+
+    crc32(&data)
+}
@@ -0,0 +1,309 @@
+use super::{DecodeError, DecodeOptions, Diagnostics, Endian, Substitution, Warning};
+
+use crate::{Field, PanelKind};
+
+use std::io::{Read, Error, ErrorKind};
+use std::convert::TryFrom as _;
+use std::ops::Range;
+
+/// How many bytes of fixed size items to read at once.
+const CHUNK_LEN: usize = 8 * 1024;
+
+/// A reader that keeps track of how far into the input it is, so decoding
+/// errors can say where they happened.
+///
+/// Every read is exact: readers may return fewer bytes than asked for, such
+/// as sockets and pipes do, so reads are retried until they are complete or
+/// the input ends. The reader never reads past what a format asks for, so
+/// formats can be embedded in other streams.
+pub(super) struct Reader<R> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R> Reader<R> {
+    pub fn new(inner: R) -> Reader<R> {
+        Reader::with_offset(inner, 0)
+    }
+
+    /// Creates a reader for input that starts partway through a file.
+    pub fn with_offset(inner: R, offset: u64) -> Reader<R> {
+        Reader { inner, offset }
+    }
+
+    /// Gets how many bytes into the input the reader is.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Creates an error for malformed data at the current offset.
+    pub fn invalid<S>(&self, reason: S) -> DecodeError
+    where S: Into<String> {
+        DecodeError::invalid(self.offset, reason)
+    }
+}
+
+impl<R> Reader<R>
+where R: Read {
+    /// Fills a buffer, returning `false` if the input ended before any of it
+    /// was read.
+    ///
+    /// Readers may return fewer bytes than asked for, so this keeps reading
+    /// until the buffer is full.
+    pub fn fill(&mut self, buf: &mut [u8]) -> Result<bool, DecodeError> {
+        let mut filled = 0;
+
+        while filled < buf.len() {
+            match self.read(&mut buf[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(DecodeError::UnexpectedEof { offset: self.offset }),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Reads up to `count` items of `size` bytes each, calling `f` with each
+    /// item and its offset.
+    ///
+    /// Items are read in large chunks, rather than one read for each. Returns
+    /// how many items were read before the input ended; the input may only
+    /// end between items.
+    pub fn read_items<F>(&mut self, size: usize, count: usize, mut f: F) -> Result<usize, DecodeError>
+    where F: FnMut(&[u8], u64) -> Result<(), DecodeError> {
+        let per_chunk = (CHUNK_LEN / size).max(1);
+        let mut buf = vec![0u8; size * per_chunk.min(count)];
+        let mut read = 0;
+
+        while read < count {
+            let want = size * per_chunk.min(count - read);
+            let start = self.offset;
+
+            // fill as much of the chunk as the input has
+            let mut filled = 0;
+
+            while filled < want {
+                match self.read(&mut buf[filled..want]) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            for (idx, item) in buf[..filled].chunks(size).enumerate() {
+                if item.len() < size {
+                    return Err(DecodeError::UnexpectedEof { offset: self.offset });
+                }
+
+                f(item, start + (idx * size) as u64)?;
+            }
+
+            read += filled / size;
+
+            if filled < want {
+                break;
+            }
+        }
+
+        Ok(read)
+    }
+
+    pub fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), DecodeError> {
+        if buf.is_empty() || self.fill(buf)? {
+            Ok(())
+        } else {
+            Err(DecodeError::UnexpectedEof { offset: self.offset })
+        }
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let mut buf = [0u8; 1];
+        self.read_bytes(&mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    pub fn read_u16(&mut self, endian: Endian) -> Result<u16, DecodeError> {
+        let mut buf = [0u8; 2];
+        self.read_bytes(&mut buf)?;
+
+        Ok(match endian {
+            Endian::Little => u16::from_le_bytes(buf),
+            Endian::Big => u16::from_be_bytes(buf),
+        })
+    }
+
+    pub fn read_u32(&mut self, endian: Endian) -> Result<u32, DecodeError> {
+        let mut buf = [0u8; 4];
+        self.read_bytes(&mut buf)?;
+
+        Ok(match endian {
+            Endian::Little => u32::from_le_bytes(buf),
+            Endian::Big => u32::from_be_bytes(buf),
+        })
+    }
+
+    pub fn read_u64(&mut self, endian: Endian) -> Result<u64, DecodeError> {
+        let mut buf = [0u8; 8];
+        self.read_bytes(&mut buf)?;
+
+        Ok(match endian {
+            Endian::Little => u64::from_le_bytes(buf),
+            Endian::Big => u64::from_be_bytes(buf),
+        })
+    }
+
+    /// Reads a string as a `ushort` length followed by its UTF-8 bytes.
+    pub fn read_string(&mut self, endian: Endian) -> Result<String, DecodeError> {
+        let len = self.read_u16(endian)? as usize;
+        let start = self.offset;
+
+        let mut buf = vec![0u8; len];
+        self.read_bytes(&mut buf)?;
+
+        String::from_utf8(buf)
+            .map_err(|e| DecodeError::invalid(start, e.to_string()))
+    }
+
+    /// Reads an unsigned LEB128 varint.
+    pub fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let start = self.offset;
+        let mut data = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            data |= ((byte & 0x7F) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(data);
+            }
+        }
+
+        Err(DecodeError::invalid(start, "varint is too long"))
+    }
+
+    pub fn read_varint_usize(&mut self) -> Result<usize, DecodeError> {
+        let start = self.offset;
+        let data = self.read_varint()?;
+
+        usize::try_from(data)
+            .map_err(|_| DecodeError::invalid(start, "varint is too large"))
+    }
+
+    /// Counts how many more `size` byte items are left in the input, without
+    /// keeping them.
+    pub fn count_rest(&mut self, size: usize) -> Result<usize, DecodeError> {
+        let mut buf = vec![0u8; size];
+        let mut count = 0;
+
+        while self.fill(&mut buf)? {
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Fails if there is more input.
+    pub fn expect_end(&mut self) -> Result<(), DecodeError> {
+        let offset = self.offset;
+
+        if self.fill(&mut [0u8; 1])? {
+            Err(DecodeError::TrailingData { offset })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<R> Read for Reader<R>
+where R: Read {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let n = self.inner.read(buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+/// Parses a panel kind read from `offset`.
+pub(super) fn panel_kind(value: u8, offset: u64) -> Result<PanelKind, DecodeError> {
+    match PanelKind::from(value) {
+        PanelKind::Unknown(value) => Err(DecodeError::UnknownPanelKind { offset, value }),
+        kind => Ok(kind),
+    }
+}
+
+/// What a decoder has found so far, besides the field.
+#[derive(Default)]
+pub(super) struct State {
+    placeholder: Option<PanelKind>,
+    keep_unknown: bool,
+    pub substitutions: Vec<Substitution>,
+    /// The warnings found, if they are being collected.
+    pub warnings: Option<Vec<Warning>>,
+}
+
+impl State {
+    pub fn new(options: &DecodeOptions) -> State {
+        State {
+            placeholder: options.placeholder,
+            keep_unknown: options.keep_unknown,
+            ..State::default()
+        }
+    }
+
+    /// Creates a state that collects warnings.
+    pub fn diagnosing(options: &DecodeOptions) -> State {
+        State {
+            warnings: Some(Vec::new()),
+            ..State::new(options)
+        }
+    }
+
+    /// Moves the warnings found into `diagnostics`, and checks the decoded
+    /// field.
+    pub fn finish(self, field: &Field, diagnostics: &mut Diagnostics) {
+        diagnostics.extend(self.substitutions.into_iter().map(Warning::Substituted));
+        diagnostics.extend(self.warnings.into_iter().flatten());
+        diagnostics.inspect(field);
+    }
+
+    /// Parses the kind of a panel, keeping or replacing unknown kinds if the
+    /// options allow it.
+    ///
+    /// The panels are at the flattened indices `panels`, and their kind was
+    /// read from `offset`.
+    pub fn parse(
+        &mut self,
+        value: u8,
+        offset: u64,
+        panels: Range<usize>,
+        width: usize,
+    ) -> Result<PanelKind, DecodeError> {
+        match (panel_kind(value, offset), self.placeholder) {
+            (Err(_), _) if self.keep_unknown => Ok(PanelKind::Unknown(value)),
+            (Err(_), Some(placeholder)) => {
+                self.substitutions.extend(panels.map(|idx| Substitution {
+                    x: idx.checked_rem(width).unwrap_or(idx),
+                    y: idx.checked_div(width).unwrap_or(0),
+                    offset,
+                    value,
+                }));
+
+                Ok(placeholder)
+            },
+            (result, _) => result,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::Field;
+
+use super::{DecodeError, fld::Fld, fldx::Fldx};
+
+use std::io::{Read, Write, Error};
+
+/// A field format that can encode and decode fields.
+///
+/// Implementing this for a custom format allows it to be added to a
+/// [`Registry`], alongside the builtin formats.
+pub trait FieldFormat {
+    /// The name of the format.
+    fn name(&self) -> &str;
+
+    /// The file extensions of the format, without the leading dot.
+    fn extensions(&self) -> &[&str];
+
+    /// Checks if some data looks like it is in this format.
+    ///
+    /// This should be cheap and conservative; a format that cannot tell should
+    /// return `false`.
+    fn sniff(&self, data: &[u8]) -> bool;
+
+    /// Encodes a field in this format.
+    fn encode(&self, field: &Field, output: &mut dyn Write) -> Result<(), Error>;
+
+    /// Decodes a field in this format.
+    fn decode(&self, input: &mut dyn Read) -> Result<Field, DecodeError>;
+}
+
+/// A collection of known [`FieldFormat`]s.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Field, Panel, PanelKind::*};
+/// use citrus_common::format::{fldx, Registry};
+///
+/// let field = Field::new_slice(&[
+///     &[Panel::new(Home), Panel::new(Draw)],
+/// ]);
+///
+/// let mut data = Vec::new();
+/// fldx::encode(&field, &mut data).unwrap();
+///
+/// // we can decode the field without knowing what format it is in
+/// let registry = Registry::default();
+/// let decoded = registry.decode(&data).unwrap();
+///
+/// assert_eq!(decoded.get((1, 0)).kind, Draw);
+/// ```
+pub struct Registry {
+    formats: Vec<Box<dyn FieldFormat>>,
+}
+
+impl Registry {
+    /// Creates a new, empty registry.
+    pub fn new() -> Registry {
+        Registry {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Adds a format to the registry.
+    ///
+    /// Formats added earlier take priority when sniffing or looking up by
+    /// name or extension, so a format added twice is found as the first one.
+    pub fn register<F>(&mut self, format: F)
+    where F: FieldFormat + 'static {
+        self.formats.push(Box::new(format));
+    }
+
+    /// Gets an iterator over all of the formats in the registry.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &dyn FieldFormat> + ExactSizeIterator {
+        self.formats.iter().map(|f| f.as_ref())
+    }
+
+    /// Finds a format by its name.
+    pub fn by_name(&self, name: &str) -> Option<&dyn FieldFormat> {
+        self.iter().find(|f| f.name() == name)
+    }
+
+    /// Finds a format by a file extension, without the leading dot.
+    ///
+    /// Extensions are compared case-insensitively.
+    pub fn by_extension(&self, extension: &str) -> Option<&dyn FieldFormat> {
+        self.iter()
+            .find(|f| {
+                f.extensions().iter().any(|e| e.eq_ignore_ascii_case(extension))
+            })
+    }
+
+    /// Finds the first format that recognizes some data.
+    pub fn sniff(&self, data: &[u8]) -> Option<&dyn FieldFormat> {
+        self.iter().find(|f| f.sniff(data))
+    }
+
+    /// Decodes a field, using the first format that recognizes the data.
+    pub fn decode(&self, mut data: &[u8]) -> Result<Field, DecodeError> {
+        match self.sniff(data) {
+            Some(format) => format.decode(&mut data),
+            None => Err(DecodeError::invalid(0, "unknown field format")),
+        }
+    }
+}
+
+impl Default for Registry {
+    /// Creates a registry with all of the builtin binary formats.
+    fn default() -> Registry {
+        let mut registry = Registry::new();
+
+        registry.register(Fldx);
+        registry.register(Fld::default());
+
+        registry
+    }
+}
@@ -0,0 +1,157 @@
+//! Short share codes for fields.
+//!
+//! Share codes are compact text strings meant to be pasted in chat and URLs.
+//! They look like `OJ1-` followed by the field data in [Crockford's
+//! Base32][1], and only use digits, uppercase letters and hyphens.
+//!
+//! The `1` after the `OJ` prefix is the version of the code. Codes made by a
+//! newer version of this crate fail to decode with a
+//! [`DecodeError::UnsupportedVersion`] error, so applications can tell users
+//! to update instead of showing them garbage.
+//!
+//! Decoding is forgiving: case is ignored, hyphens and whitespace may appear
+//! anywhere in the data, and the letters `O`, `I` and `L` are read as the
+//! digits they look like.
+//!
+//! The field data is [run-length encoded like in the `.fldx` format][2], so
+//! codes stay short for mostly empty fields.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{Field, Panel, PanelKind::*};
+//! use citrus_common::format::sharecode;
+//!
+//! let field = Field::new_slice(&[
+//!     &[Panel::new(Home), Panel::new(Draw)],
+//! ]);
+//!
+//! let code = sharecode::encode(&field).unwrap();
+//! assert!(code.starts_with("OJ1-"));
+//!
+//! let decoded = sharecode::decode(&code.to_lowercase()).unwrap();
+//! assert_eq!(decoded.get((1, 0)).kind, Draw);
+//! ```
+//!
+//! [1]: https://www.crockford.com/base32.html
+//! [2]: ../fldx/index.html
+
+use super::*;
+
+use crate::Field;
+
+use std::io::Error;
+
+/// The prefix of every share code.
+const PREFIX: &str = "OJ";
+
+/// The version of share codes this crate writes.
+pub const VERSION: u32 = 1;
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Encodes a field to a share code.
+pub fn encode(field: &Field) -> Result<String, Error> {
+    let mut data = Vec::new();
+    fldx::write_rle_body(field, &mut data)?;
+
+    let mut code = format!("{}{}-", PREFIX, VERSION);
+    encode_base32(&data, &mut code);
+
+    Ok(code)
+}
+
+/// Decodes a field from a share code.
+///
+/// Share codes come from chat and URLs, so this applies the limits of
+/// [`DecodeOptions::untrusted`]. Errors in the field data report offsets
+/// into the decoded bytes, not the code itself.
+pub fn decode(code: &str) -> Result<Field, DecodeError> {
+    decode_with(code, &DecodeOptions::untrusted())
+}
+
+/// Decodes a field from a share code, with the given options.
+pub fn decode_with(code: &str, options: &DecodeOptions) -> Result<Field, DecodeError> {
+    let code = code.trim();
+
+    // read the prefix
+    let rest = code.get(..PREFIX.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(PREFIX))
+        .map(|_| &code[PREFIX.len()..])
+        .ok_or_else(|| DecodeError::invalid(0, "not a share code"))?;
+
+    // read the version
+    let (version, data) = match rest.find('-') {
+        Some(idx) => (&rest[..idx], &rest[idx + 1..]),
+        None => return Err(DecodeError::invalid(0, "not a share code")),
+    };
+
+    let version = version.parse::<u32>()
+        .ok()
+        .filter(|version| *version > 0)
+        .ok_or_else(|| DecodeError::invalid(0, "not a share code"))?;
+
+    if version > VERSION {
+        return Err(DecodeError::UnsupportedVersion {
+            offset: PREFIX.len() as u64,
+            version,
+            supported: VERSION,
+        });
+    }
+
+    let data = decode_base32(data)?;
+
+    fldx::read_rle_body(&data[..], options)
+}
+
+fn encode_base32(data: &[u8], output: &mut String) {
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            output.push(ALPHABET[((buffer >> bits) & 0x1F) as usize] as char);
+        }
+    }
+
+    // flush the leftover bits
+    if bits > 0 {
+        output.push(ALPHABET[((buffer << (5 - bits)) & 0x1F) as usize] as char);
+    }
+}
+
+fn decode_base32(data: &str) -> Result<Vec<u8>, DecodeError> {
+    let mut output = Vec::with_capacity(data.len() * 5 / 8);
+
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for (offset, c) in data.char_indices() {
+        let value = match c.to_ascii_uppercase() {
+            '-' => continue,
+            c if c.is_whitespace() => continue,
+            'O' => 0,
+            'I' | 'L' => 1,
+            c => match ALPHABET.iter().position(|a| *a as char == c) {
+                Some(value) => value as u32,
+                None => return Err(DecodeError::invalid(
+                    offset as u64,
+                    format!("invalid character {:?} in share code", c),
+                )),
+            },
+        };
+
+        buffer = (buffer << 5) | value;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+
+    Ok(output)
+}
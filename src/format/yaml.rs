@@ -0,0 +1,67 @@
+//! Support for encoding fields as YAML.
+//!
+//! The YAML format shares its structure with the [JSON format][1], but puts
+//! every property on its own line, which makes it friendly to version control
+//! and diffs.
+//!
+//! ```yaml
+//! ---
+//! width: 2
+//! height: 1
+//! panels:
+//!   - kind: Home
+//!     exits:
+//!       - east
+//!     exits_backtrack: []
+//!   - kind: Draw
+//!     exits: []
+//!     exits_backtrack:
+//!       - west
+//! ```
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{Field, Panel, PanelKind::*};
+//! use citrus_common::format::yaml;
+//!
+//! let field = Field::new_slice(&[
+//!     &[Panel::new(Home), Panel::new(Draw)],
+//! ]);
+//!
+//! let data = yaml::encode_string(&field).unwrap();
+//! let decoded = yaml::decode_str(&data).unwrap();
+//!
+//! assert_eq!(decoded.get((1, 0)).kind, Draw);
+//! ```
+//!
+//! [1]: ../json/index.html
+
+use crate::Field;
+
+use std::io::{Read, Write, Error, ErrorKind};
+
+/// Encode a field to YAML.
+pub fn encode<T>(field: &Field, output: T) -> Result<(), Error>
+where T: Write {
+    serde_yaml::to_writer(output, field)
+        .map_err(Error::other)
+}
+
+/// Decode a field from YAML.
+pub fn decode<T>(input: T) -> Result<Field, Error>
+where T: Read {
+    serde_yaml::from_reader(input)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Encodes a field to a YAML string.
+pub fn encode_string(field: &Field) -> Result<String, Error> {
+    serde_yaml::to_string(field)
+        .map_err(Error::other)
+}
+
+/// Decodes a field from a YAML string.
+pub fn decode_str(data: &str) -> Result<Field, Error> {
+    serde_yaml::from_str(data)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
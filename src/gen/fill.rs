@@ -0,0 +1,137 @@
+use crate::{Field, PanelKind, Pos};
+use crate::rng::Rng;
+
+/// How a kind is laid out by [`fill`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Spread {
+    /// Placed anywhere, regardless of the panels around it.
+    Uniform,
+    /// Gathered into patches.
+    Clustered,
+    /// Kept apart from others of its kind.
+    Scattered,
+}
+
+/// How [`fill`] picks kinds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FillConfig {
+    /// How likely each kind is, by weight, and how it is laid out.
+    ///
+    /// If every weight is zero, the panels are Neutral.
+    pub kinds: Vec<(PanelKind, u32, Spread)>,
+    /// Kinds that are left alone.
+    pub keep: Vec<PanelKind>,
+    /// About how many panels across the patches of clustered kinds are.
+    pub scale: usize,
+}
+
+impl Default for FillConfig {
+    fn default() -> FillConfig {
+        use PanelKind::*;
+
+        FillConfig {
+            kinds: vec![
+                (Bonus, 4, Spread::Scattered),
+                (Drop, 3, Spread::Uniform),
+                (Draw, 3, Spread::Uniform),
+                (Encounter, 3, Spread::Clustered),
+                (Neutral, 1, Spread::Uniform),
+            ],
+            keep: vec![Home],
+            scale: 4,
+        }
+    }
+}
+
+/// Sets the kinds of a field's panels, leaving its exits alone.
+///
+/// Empty panels, and panels of the kinds in `config.keep`, are not changed.
+/// Each other panel is given a kind at random by weight, where clustered
+/// kinds are more likely in patches of smooth noise and next to panels
+/// already of their kind, and scattered kinds are less likely near panels
+/// already of their kind. The same seed always gives the same kinds.
+///
+/// # Examples
+/// ```
+/// use citrus_common::PanelKind;
+/// use citrus_common::gen::{self, templates, FillConfig};
+///
+/// let skeleton = templates::double_ring(8, 8);
+/// let field = gen::fill(&skeleton, &FillConfig::default(), 3);
+///
+/// assert!(field.positions().all(|pos| field[pos].exits == skeleton[pos].exits));
+///
+/// let empty = |field: &citrus_common::Field| {
+///     field.panels().filter(|(_, _, panel)| panel.kind == PanelKind::Empty).count()
+/// };
+/// assert_eq!(empty(&field), empty(&skeleton));
+/// ```
+pub fn fill(field: &Field, config: &FillConfig, seed: u64) -> Field {
+    let mut rng = Rng::new(seed);
+    let mut field = field.clone();
+
+    let noise: Vec<Vec<f64>> = config.kinds.iter()
+        .map(|_| value_noise(field.width(), field.height(), config.scale.max(1), &mut rng))
+        .collect();
+
+    let mut open: Vec<Pos> = field.positions()
+        .filter(|pos| field[*pos].kind != PanelKind::Empty && !config.keep.contains(&field[*pos].kind))
+        .collect();
+    rng.shuffle(&mut open);
+
+    let mut filled = vec![false; field.width() * field.height()];
+
+    for pos in open {
+        let near = |radius: usize, kind: PanelKind| {
+            field.positions()
+                .filter(|other| filled[other.y * field.width() + other.x] && field[*other].kind == kind)
+                .filter(|other| other.x.abs_diff(pos.x) + other.y.abs_diff(pos.y) <= radius)
+                .count()
+        };
+
+        let weights: Vec<(PanelKind, u32)> = config.kinds.iter()
+            .zip(noise.iter())
+            .map(|((kind, weight, spread), noise)| {
+                let bias = match spread {
+                    Spread::Uniform => 1.0,
+                    Spread::Clustered => {
+                        let level = noise[pos.y * field.width() + pos.x];
+                        3.0 * level * level * (1 + near(1, *kind)) as f64
+                    },
+                    Spread::Scattered => 1.0 / (1 + 4 * near(2, *kind)) as f64,
+                };
+
+                (*kind, (*weight as f64 * bias * 1000.0) as u32)
+            })
+            .collect();
+
+        field[pos].kind = rng.weighted(&weights).copied().unwrap_or(PanelKind::Neutral);
+        filled[pos.y * field.width() + pos.x] = true;
+    }
+
+    field
+}
+
+/// Makes smooth noise in `0.0..1.0` over a field, in row-major order, with
+/// features about `scale` panels across.
+fn value_noise(width: usize, height: usize, scale: usize, rng: &mut Rng) -> Vec<f64> {
+    let (cols, rows) = (width / scale + 2, height / scale + 2);
+    let lattice: Vec<f64> = (0..cols * rows).map(|_| rng.unit()).collect();
+    let at = |x: usize, y: usize| lattice[y * cols + x];
+
+    let mut noise = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (cx, cy) = (x / scale, y / scale);
+            let (tx, ty) = ((x % scale) as f64 / scale as f64, (y % scale) as f64 / scale as f64);
+
+            let top = at(cx, cy) * (1.0 - tx) + at(cx + 1, cy) * tx;
+            let bottom = at(cx, cy + 1) * (1.0 - tx) + at(cx + 1, cy + 1) * tx;
+
+            noise.push(top * (1.0 - ty) + bottom * ty);
+        }
+    }
+
+    noise
+}
@@ -0,0 +1,500 @@
+//! Generating fields.
+//!
+//! Generators are seeded, so the same seed and settings always make the same
+//! field. For fixed shapes to start from instead, see [`templates`].
+//!
+//! # Examples
+//! ```
+//! use citrus_common::gen::{self, RandomConfig};
+//! use citrus_common::analysis::{self, Connectivity};
+//!
+//! let field = gen::random(10, 8, 1234, &RandomConfig::default());
+//!
+//! // every panel can be reached from every other panel
+//! assert_eq!(analysis::components(&field, Connectivity::Strong).len(), 1);
+//! assert_eq!(field, gen::random(10, 8, 1234, &RandomConfig::default()));
+//! ```
+
+pub mod templates;
+
+mod fill;
+mod mutate;
+mod pipeline;
+mod track;
+
+pub use self::fill::{fill, FillConfig, Spread};
+pub use self::mutate::{mutate, Mutation, MutationConfig};
+pub use self::pipeline::{Pipeline, PipelineStats, Rule};
+pub use self::track::track;
+
+use crate::{Direction, Field, PanelKind, Pos};
+use crate::analysis::{components, laps, Connectivity};
+use crate::draw::ring;
+use crate::pos::Rect;
+use crate::rng::Rng;
+use crate::transform::Anchor;
+
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::ops::RangeInclusive;
+
+/// How [`random`] generates a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RandomConfig {
+    /// How many Homes to place on the main loop, spread evenly around it.
+    pub homes: usize,
+    /// How many branches to try to add. Each branch leaves the main loop, or
+    /// another branch, and joins back onto it somewhere else.
+    pub branches: usize,
+    /// Whether every exit also has one leading back, so players can go
+    /// either way.
+    pub two_way: bool,
+    /// How likely each kind is for the panels that are not Homes, by weight.
+    ///
+    /// If every weight is zero, the panels are Neutral.
+    pub kinds: Vec<(PanelKind, u32)>,
+}
+
+impl Default for RandomConfig {
+    fn default() -> RandomConfig {
+        use PanelKind::*;
+
+        RandomConfig {
+            homes: 4,
+            branches: 2,
+            two_way: false,
+            kinds: vec![(Bonus, 4), (Drop, 3), (Draw, 3), (Encounter, 3), (Neutral, 1)],
+        }
+    }
+}
+
+/// Generates a random field: a main loop, with branches off of it.
+///
+/// Every panel can be reached from every other panel, and backtrack exits
+/// lead back the way each exit came. Branches are only
+/// added where there is room for them, so small fields may have fewer than
+/// asked for.
+///
+/// # Panics
+/// Will panic if the field is less than two panels wide or tall.
+pub fn random(width: usize, height: usize, seed: u64, config: &RandomConfig) -> Field {
+    assert!(width >= 2 && height >= 2, "fields must be at least 2x2 to loop");
+
+    let mut rng = Rng::new(seed);
+    let mut field = Field::builder(width, height).build();
+
+    // the main loop, covering at least half of each side
+    let loop_width = rng.between(width.div_ceil(2).max(2), width);
+    let loop_height = rng.between(height.div_ceil(2).max(2), height);
+    let rect = Rect::new(
+        rng.between(0, width - loop_width),
+        rng.between(0, height - loop_height),
+        loop_width,
+        loop_height,
+    );
+
+    let main = ring(rect);
+    let mut closed = main.clone();
+    closed.push(main[0]);
+    lay(&mut field, &closed, config.two_way);
+
+    for _ in 0..config.branches {
+        if let Some(branch) = branch(&field, &mut rng) {
+            lay(&mut field, &branch, config.two_way);
+        }
+    }
+
+    populate(&mut field, &main, &mut rng, config);
+
+    field
+}
+
+/// A way a field can be symmetric.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    /// The field is the same mirrored left to right.
+    Horizontal,
+    /// The field is the same mirrored top to bottom.
+    Vertical,
+    /// The field is the same turned halfway around.
+    HalfTurn,
+    /// The field is the same turned a quarter of the way around.
+    QuarterTurn,
+    /// The field is the same mirrored left to right, and top to bottom.
+    FourWay,
+}
+
+impl Symmetry {
+    /// Checks if a field has this symmetry, kinds and exits alike.
+    pub fn holds(self, field: &Field) -> bool {
+        match self {
+            Symmetry::Horizontal => field.flip_horizontal() == *field,
+            Symmetry::Vertical => field.flip_vertical() == *field,
+            Symmetry::HalfTurn => field.rotate_180() == *field,
+            Symmetry::QuarterTurn => field.rotate_cw() == *field,
+            Symmetry::FourWay => Symmetry::Horizontal.holds(field) && Symmetry::Vertical.holds(field),
+        }
+    }
+
+    /// Gets every way of moving the field, other than leaving it be, that
+    /// this symmetry keeps the same.
+    fn images(self, field: &Field) -> Vec<Field> {
+        match self {
+            Symmetry::Horizontal => vec![field.flip_horizontal()],
+            Symmetry::Vertical => vec![field.flip_vertical()],
+            Symmetry::HalfTurn => vec![field.rotate_180()],
+            Symmetry::QuarterTurn => vec![field.rotate_cw(), field.rotate_180(), field.rotate_ccw()],
+            Symmetry::FourWay => vec![field.flip_horizontal(), field.flip_vertical(), field.rotate_180()],
+        }
+    }
+
+    /// Gets how many copies of a part make up a field.
+    fn copies(self) -> usize {
+        match self {
+            Symmetry::Horizontal | Symmetry::Vertical | Symmetry::HalfTurn => 2,
+            Symmetry::QuarterTurn | Symmetry::FourWay => 4,
+        }
+    }
+
+    /// Gets the size of the part of a field that the rest is copied from.
+    fn part_size(self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Symmetry::Horizontal | Symmetry::HalfTurn => (width / 2, height),
+            Symmetry::Vertical => (width, height / 2),
+            Symmetry::QuarterTurn | Symmetry::FourWay => (width / 2, height / 2),
+        }
+    }
+}
+
+/// Generates a random field with a symmetry, so every Home faces the same
+/// layout.
+///
+/// A part of the field, a half or a quarter, is generated like [`random`] and
+/// copied around the field, kinds and exits alike. The copies are then
+/// joined by paths that lead both ways. `config.homes` is split between the
+/// copies, rounding down.
+///
+/// # Panics
+/// Will panic if the part is less than two panels wide or tall, or if a
+/// [`Symmetry::QuarterTurn`] field is not square.
+///
+/// # Examples
+/// ```
+/// use citrus_common::gen::{self, RandomConfig, Symmetry};
+///
+/// let field = gen::symmetric(9, 8, 42, &RandomConfig::default(), Symmetry::FourWay);
+///
+/// assert!(Symmetry::FourWay.holds(&field));
+/// assert_eq!(field, field.flip_horizontal());
+/// ```
+pub fn symmetric(width: usize, height: usize, seed: u64, config: &RandomConfig, symmetry: Symmetry) -> Field {
+    assert!(
+        symmetry != Symmetry::QuarterTurn || width == height,
+        "fields turned a quarter of the way around must be square"
+    );
+
+    let (part_width, part_height) = symmetry.part_size(width, height);
+    let config = RandomConfig {
+        homes: config.homes / symmetry.copies(),
+        ..config.clone()
+    };
+
+    let part = random(part_width, part_height, seed, &config)
+        .resize(width, height, Anchor::TopLeft);
+    let mut field = symmetrize(&part, symmetry);
+
+    // join the copies until every panel can be reached
+    loop {
+        let components = components(&field, Connectivity::Weak);
+
+        match components.first() {
+            Some(first) if components.len() > 1 => {
+                let bridge = bridge(&field, first);
+                join(&mut field, &bridge, true);
+                field = symmetrize(&field, symmetry);
+            },
+            _ => break field,
+        }
+    }
+}
+
+/// Combines a field with its copies under a symmetry.
+///
+/// Copies only ever add exits, and only fill in empty panels.
+fn symmetrize(field: &Field, symmetry: Symmetry) -> Field {
+    let mut result = field.clone();
+
+    for image in symmetry.images(field) {
+        for pos in image.positions() {
+            let (panel, copy) = (&mut result[pos], &image[pos]);
+
+            if panel.kind == PanelKind::Empty {
+                panel.kind = copy.kind;
+            }
+
+            panel.exits |= copy.exits;
+            panel.exits_backtrack |= copy.exits_backtrack;
+        }
+    }
+
+    result
+}
+
+/// Finds the shortest path through empty space from a group of panels to a
+/// panel outside of it.
+fn bridge(field: &Field, group: &[Pos]) -> Vec<Pos> {
+    let group: HashSet<Pos> = group.iter().copied().collect();
+    let mut came_from = HashMap::new();
+    let mut queue: VecDeque<Pos> = field.positions().filter(|pos| group.contains(pos)).collect();
+
+    for pos in queue.iter() {
+        came_from.insert(*pos, *pos);
+    }
+
+    while let Some(pos) = queue.pop_front() {
+        let neighbors = Direction::ALL.iter()
+            .filter_map(|dir| pos.step(*dir))
+            .filter(|to| field.contains(*to));
+
+        for next in neighbors {
+            if let Entry::Vacant(entry) = came_from.entry(next) {
+                entry.insert(pos);
+
+                if field[next].kind == PanelKind::Empty {
+                    queue.push_back(next);
+                } else {
+                    let mut path = vec![next];
+                    let mut pos = pos;
+
+                    while !group.contains(&pos) {
+                        path.push(pos);
+                        pos = came_from[&pos];
+                    }
+
+                    path.push(pos);
+                    path.reverse();
+
+                    return path;
+                }
+            }
+        }
+    }
+
+    Vec::new()
+}
+
+/// What a field made by [`constrained`] must be like.
+///
+/// Bonus and Drop counts include their 2x versions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraints {
+    /// The exact number of Homes.
+    pub homes: Option<usize>,
+    /// How many Bonus panels there may be.
+    pub bonus: RangeInclusive<usize>,
+    /// How many Drop panels there may be.
+    pub drop: RangeInclusive<usize>,
+    /// A symmetry the field must have.
+    pub symmetry: Option<Symmetry>,
+    /// How many moves a lap from each Home may take, as found by
+    /// [`analysis::lap`](crate::analysis::lap).
+    pub lap: Option<RangeInclusive<usize>>,
+}
+
+impl Default for Constraints {
+    fn default() -> Constraints {
+        Constraints {
+            homes: None,
+            bonus: 0..=usize::MAX,
+            drop: 0..=usize::MAX,
+            symmetry: None,
+            lap: None,
+        }
+    }
+}
+
+impl Constraints {
+    /// Checks if a field meets every constraint.
+    pub fn satisfied_by(&self, field: &Field) -> bool {
+        use PanelKind::*;
+
+        let count = |kind: PanelKind| {
+            field.panels().filter(|(_, _, panel)| panel.kind.base_kind() == kind).count()
+        };
+
+        self.homes.is_none_or(|homes| count(Home) == homes)
+            && self.bonus.contains(&count(Bonus))
+            && self.drop.contains(&count(Drop))
+            && self.symmetry.is_none_or(|symmetry| symmetry.holds(field))
+            && self.lap.as_ref().is_none_or(|range| {
+                laps(field).laps.iter().all(|(_, lap)| lap.is_some_and(|lap| range.contains(&lap)))
+            })
+    }
+}
+
+/// No field meeting the constraints was found within the budget given to
+/// [`constrained`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BudgetExhausted {
+    /// How many fields were generated and rejected.
+    pub attempts: usize,
+}
+
+impl Display for BudgetExhausted {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "no field met the constraints after {} attempts", self.attempts)
+    }
+}
+
+impl std::error::Error for BudgetExhausted {}
+
+/// Generates random fields until one meets the constraints, trying at most
+/// `budget` times.
+///
+/// Fields are generated like [`random`], except that the number of Homes is
+/// taken from the constraints, if they give one, and fields that must have a
+/// symmetry are generated like [`symmetric`]. Like the other generators, the
+/// same seed always gives the same result.
+///
+/// # Examples
+/// ```
+/// use citrus_common::gen::{self, Constraints, RandomConfig};
+///
+/// let constraints = Constraints {
+///     homes: Some(2),
+///     bonus: 3..=6,
+///     ..Constraints::default()
+/// };
+///
+/// let field = gen::constrained(8, 8, 99, &RandomConfig::default(), &constraints, 500).unwrap();
+/// assert!(constraints.satisfied_by(&field));
+/// ```
+pub fn constrained(
+    width: usize,
+    height: usize,
+    seed: u64,
+    config: &RandomConfig,
+    constraints: &Constraints,
+    budget: usize,
+) -> Result<Field, BudgetExhausted> {
+    let config = RandomConfig {
+        homes: constraints.homes.unwrap_or(config.homes),
+        ..config.clone()
+    };
+
+    let mut seeds = Rng::new(seed);
+
+    for _ in 0..budget {
+        let field = match constraints.symmetry {
+            Some(symmetry) => symmetric(width, height, seeds.next_u64(), &config, symmetry),
+            None => random(width, height, seeds.next_u64(), &config),
+        };
+
+        if constraints.satisfied_by(&field) {
+            return Ok(field);
+        }
+    }
+
+    Err(BudgetExhausted { attempts: budget })
+}
+
+/// Wires exits and backtrack exits along a path, and both ways if `two_way`
+/// is set, making its panels Neutral.
+fn lay(field: &mut Field, path: &[Pos], two_way: bool) {
+    field.lay_path(path, Some(PanelKind::Neutral), true);
+
+    if two_way {
+        let back: Vec<Pos> = path.iter().rev().copied().collect();
+        field.lay_path(&back, None, true);
+    }
+}
+
+/// Wires a path like [`lay`], but only makes its empty panels Neutral.
+fn join(field: &mut Field, path: &[Pos], two_way: bool) {
+    field.lay_path(path, None, true);
+
+    if two_way {
+        let back: Vec<Pos> = path.iter().rev().copied().collect();
+        field.lay_path(&back, None, true);
+    }
+
+    for pos in path {
+        if field[*pos].kind == PanelKind::Empty {
+            field[*pos].kind = PanelKind::Neutral;
+        }
+    }
+}
+
+/// Finds a path through empty space that leaves a non-empty panel and joins
+/// another.
+fn branch(field: &Field, rng: &mut Rng) -> Option<Vec<Pos>> {
+    let empty = |pos: Pos| field[pos].kind == PanelKind::Empty;
+    let neighbors = |pos: Pos| Direction::ALL.iter()
+        .filter_map(move |dir| pos.step(*dir))
+        .filter(move |to| field.contains(*to));
+
+    // where a branch can start, and the empty panel it starts into
+    let starts: Vec<(Pos, Pos)> = field.positions()
+        .filter(|pos| !empty(*pos))
+        .flat_map(|pos| neighbors(pos).filter(|to| empty(*to)).map(move |to| (pos, to)))
+        .collect();
+
+    let (from, first) = *rng.pick(&starts)?;
+
+    // search the empty space for panels next to another non-empty panel
+    let mut came_from = HashMap::new();
+    let mut queue = VecDeque::new();
+    let mut ends = Vec::new();
+
+    came_from.insert(first, first);
+    queue.push_back(first);
+
+    while let Some(pos) = queue.pop_front() {
+        for next in neighbors(pos) {
+            if empty(next) {
+                if let Entry::Vacant(entry) = came_from.entry(next) {
+                    entry.insert(pos);
+                    queue.push_back(next);
+                }
+            } else if next != from {
+                ends.push((pos, next));
+            }
+        }
+    }
+
+    let (last, to) = *rng.pick(&ends)?;
+
+    let mut path = vec![to, last];
+    let mut pos = last;
+
+    while pos != first {
+        pos = came_from[&pos];
+        path.push(pos);
+    }
+
+    path.push(from);
+    path.reverse();
+
+    Some(path)
+}
+
+/// Sets the kinds of the non-empty panels, spreading the Homes evenly around
+/// the main loop.
+fn populate(field: &mut Field, main: &[Pos], rng: &mut Rng, config: &RandomConfig) {
+    for pos in field.positions() {
+        if field[pos].kind != PanelKind::Empty {
+            field[pos].kind = rng.weighted(&config.kinds)
+                .copied()
+                .unwrap_or(PanelKind::Neutral);
+        }
+    }
+
+    let homes = config.homes.min(main.len());
+    let offset = rng.below(main.len());
+
+    for idx in 0..homes {
+        field[main[(offset + idx * main.len() / homes) % main.len()]].kind = PanelKind::Home;
+    }
+}
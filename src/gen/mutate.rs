@@ -0,0 +1,240 @@
+use crate::{Direction, Field, Panel, PanelKind, Pos};
+use crate::analysis::{bad_exits, components, dead_ends, Connectivity};
+use crate::path::successors;
+use crate::rng::Rng;
+
+use super::{branch, join};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::hash_map::Entry;
+
+/// How many times [`mutate`] tries to make each edit before giving up on it.
+const ATTEMPTS: usize = 20;
+
+/// A small edit made by [`mutate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mutation {
+    /// Swaps the kinds of two panels.
+    SwapKinds,
+    /// Moves a one-way stretch of panels between two others onto a new
+    /// route through empty space, keeping their kinds in order.
+    Reroute,
+    /// Adds a path through empty space from one panel to another.
+    Shortcut,
+}
+
+/// How [`mutate`] edits a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MutationConfig {
+    /// How many edits to make.
+    pub mutations: usize,
+    /// How likely each edit is, by weight.
+    pub weights: Vec<(Mutation, u32)>,
+    /// Whether shortcuts lead both ways.
+    pub two_way: bool,
+}
+
+impl Default for MutationConfig {
+    fn default() -> MutationConfig {
+        MutationConfig {
+            mutations: 1,
+            weights: vec![(Mutation::SwapKinds, 3), (Mutation::Reroute, 1), (Mutation::Shortcut, 1)],
+            two_way: false,
+        }
+    }
+}
+
+/// Makes small random edits to a field.
+///
+/// An edit is only kept if it does not make the field any less valid: it
+/// must not split the field into more strongly connected parts, or add dead
+/// ends or exits that lead nowhere. Edits that cannot be made that way are
+/// skipped, so fewer edits than asked for may be made. The same seed always
+/// makes the same edits.
+///
+/// # Examples
+/// ```
+/// use citrus_common::gen::{self, templates, MutationConfig};
+/// use citrus_common::analysis::{self, Connectivity};
+///
+/// let field = templates::double_ring(8, 8);
+/// let config = MutationConfig { mutations: 5, ..MutationConfig::default() };
+///
+/// let mutated = gen::mutate(&field, &config, 7);
+/// assert_eq!(analysis::components(&mutated, Connectivity::Strong).len(), 1);
+/// ```
+pub fn mutate(field: &Field, config: &MutationConfig, seed: u64) -> Field {
+    let mut rng = Rng::new(seed);
+    let mut field = field.clone();
+
+    for _ in 0..config.mutations {
+        for _ in 0..ATTEMPTS {
+            let mutation = match rng.weighted(&config.weights) {
+                Some(mutation) => *mutation,
+                None => return field,
+            };
+
+            let mut mutated = field.clone();
+            let done = match mutation {
+                Mutation::SwapKinds => swap_kinds(&mut mutated, &mut rng),
+                Mutation::Reroute => reroute(&mut mutated, &mut rng),
+                Mutation::Shortcut => shortcut(&mut mutated, &mut rng, config.two_way),
+            };
+
+            if done && no_worse(&field, &mutated) {
+                field = mutated;
+                break;
+            }
+        }
+    }
+
+    field
+}
+
+/// Checks that an edit did not make a field any less valid.
+fn no_worse(before: &Field, after: &Field) -> bool {
+    components(after, Connectivity::Strong).len() <= components(before, Connectivity::Strong).len()
+        && dead_ends(after).len() <= dead_ends(before).len()
+        && bad_exits(after).len() <= bad_exits(before).len()
+}
+
+/// Swaps the kinds of two random panels, if they differ.
+fn swap_kinds(field: &mut Field, rng: &mut Rng) -> bool {
+    let panels: Vec<Pos> = field.positions()
+        .filter(|pos| field[*pos].kind != PanelKind::Empty)
+        .collect();
+
+    match (rng.pick(&panels).copied(), rng.pick(&panels).copied()) {
+        (Some(a), Some(b)) if field[a].kind != field[b].kind => {
+            let kind = field[a].kind;
+            field[a].kind = field[b].kind;
+            field[b].kind = kind;
+            true
+        },
+        _ => false,
+    }
+}
+
+/// Adds a path through empty space between two random panels.
+fn shortcut(field: &mut Field, rng: &mut Rng, two_way: bool) -> bool {
+    match branch(field, rng) {
+        Some(path) => {
+            join(field, &path, two_way);
+            true
+        },
+        None => false,
+    }
+}
+
+/// Moves a random one-way stretch of panels onto a new route.
+fn reroute(field: &mut Field, rng: &mut Rng) -> bool {
+    let mut before: HashMap<Pos, Vec<Pos>> = HashMap::new();
+
+    for pos in field.positions() {
+        for next in successors(field, pos) {
+            before.entry(next).or_default().push(pos);
+        }
+    }
+
+    // panels on a one-way stretch, with one way in and one way out
+    let stretch = |pos: Pos| {
+        field[pos].kind != PanelKind::Home
+            && successors(field, pos).count() == 1
+            && before.get(&pos).is_some_and(|from| from.len() == 1)
+    };
+
+    let candidates: Vec<Pos> = field.positions().filter(|pos| stretch(*pos)).collect();
+    let start = match rng.pick(&candidates) {
+        Some(start) => *start,
+        None => return false,
+    };
+
+    let mut first = start;
+
+    while stretch(before[&first][0]) {
+        first = before[&first][0];
+
+        // the whole loop is one stretch, with nowhere else to go
+        if first == start {
+            return false;
+        }
+    }
+
+    let mut run = vec![first];
+
+    while let Some(next) = successors(field, *run.last().unwrap()).next().filter(|next| stretch(*next)) {
+        run.push(next);
+    }
+
+    let from = before[&first][0];
+    let to = successors(field, *run.last().unwrap()).next().unwrap();
+
+    if from == to {
+        return false;
+    }
+
+    let kinds: Vec<PanelKind> = run.iter().map(|pos| field[*pos].kind).collect();
+
+    for pos in run.iter() {
+        field[*pos] = Panel::EMPTY;
+    }
+
+    field.normalize_exits(false);
+
+    let path = match detour(field, from, to, &run, rng) {
+        Some(path) => path,
+        None => return false,
+    };
+
+    join(field, &path, false);
+
+    for (pos, kind) in path[1..path.len() - 1].iter().zip(kinds) {
+        field[*pos].kind = kind;
+    }
+
+    true
+}
+
+/// Finds a shortest path through empty space between two panels, avoiding
+/// some positions, going whichever way first at random.
+fn detour(field: &Field, from: Pos, to: Pos, avoid: &[Pos], rng: &mut Rng) -> Option<Vec<Pos>> {
+    let avoid: HashSet<Pos> = avoid.iter().copied().collect();
+    let mut came_from = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    came_from.insert(from, from);
+    queue.push_back(from);
+
+    while let Some(pos) = queue.pop_front() {
+        let turn = rng.below(Direction::ALL.len());
+
+        for dir in Direction::ALL.iter().cycle().skip(turn).take(Direction::ALL.len()) {
+            let next = match pos.step(*dir).filter(|next| field.contains(*next)) {
+                Some(next) => next,
+                None => continue,
+            };
+
+            if next == to {
+                let mut path = vec![to, pos];
+                let mut pos = pos;
+
+                while pos != from {
+                    pos = came_from[&pos];
+                    path.push(pos);
+                }
+
+                path.reverse();
+                return Some(path);
+            }
+
+            if field[next].kind == PanelKind::Empty && !avoid.contains(&next) {
+                if let Entry::Vacant(entry) = came_from.entry(next) {
+                    entry.insert(pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    None
+}
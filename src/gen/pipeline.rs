@@ -0,0 +1,162 @@
+use crate::Field;
+use crate::analysis::{bad_exits, components, dead_ends, Connectivity};
+use crate::rng::Rng;
+
+use super::Constraints;
+
+use std::collections::HashMap;
+
+/// A fix a [`Pipeline`] runs on fields that break a rule.
+type Fix = Box<dyn Fn(&mut Field)>;
+
+/// A check [`Pipeline`] makes of each field it generates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// Every panel can be reached from every other panel.
+    Connected,
+    /// No panel is a dead end, as found by
+    /// [`analysis::dead_ends`](crate::analysis::dead_ends).
+    NoDeadEnds,
+    /// No exit leads off the field or into an empty panel, as found by
+    /// [`analysis::bad_exits`](crate::analysis::bad_exits).
+    NoBadExits,
+    /// The field meets the pipeline's [`Constraints`].
+    Constraints,
+}
+
+impl Rule {
+    /// Checks if a field follows this rule.
+    pub fn holds(self, field: &Field, constraints: &Constraints) -> bool {
+        match self {
+            Rule::Connected => components(field, Connectivity::Strong).len() <= 1,
+            Rule::NoDeadEnds => dead_ends(field).is_empty(),
+            Rule::NoBadExits => bad_exits(field).is_empty(),
+            Rule::Constraints => constraints.satisfied_by(field),
+        }
+    }
+}
+
+/// What happened over a [`Pipeline::run`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PipelineStats {
+    /// How many fields were generated.
+    pub attempts: usize,
+    /// Whether the field returned only followed the rules once fixed.
+    pub fixed: bool,
+    /// How many rejected fields broke each rule, counting only the first rule
+    /// each broke.
+    pub rejections: HashMap<Rule, usize>,
+}
+
+/// Generates fields until one follows a set of rules, fixing them up on the
+/// way.
+///
+/// Each field from the generator is checked against the rules. If it breaks
+/// any, the fixes are run on it in the order they were added, and it is
+/// checked again. If it still breaks a rule, it is thrown away and another
+/// is generated, until the budget runs out.
+///
+/// By default, every [`Rule`] is checked, the constraints let any field
+/// through, there are no fixes, and the budget is 100 fields.
+///
+/// # Examples
+/// ```
+/// use citrus_common::gen::{self, Constraints, Pipeline, RandomConfig};
+///
+/// let config = RandomConfig::default();
+/// let pipeline = Pipeline::new(|seed| gen::random(8, 8, seed, &config))
+///     .constraints(Constraints { bonus: 4..=8, ..Constraints::default() })
+///     .fix(|field| { field.normalize_exits(false); })
+///     .budget(1000);
+///
+/// let (field, stats) = pipeline.run(5);
+///
+/// assert!(field.is_some());
+/// assert!(stats.attempts >= 1);
+/// ```
+pub struct Pipeline<G> {
+    generator: G,
+    rules: Vec<Rule>,
+    constraints: Constraints,
+    fixes: Vec<Fix>,
+    budget: usize,
+}
+
+impl<G> Pipeline<G>
+where
+    G: Fn(u64) -> Field,
+{
+    /// Creates a pipeline around a generator, which makes a field from a
+    /// seed.
+    pub fn new(generator: G) -> Pipeline<G> {
+        Pipeline {
+            generator,
+            rules: vec![Rule::Connected, Rule::NoDeadEnds, Rule::NoBadExits, Rule::Constraints],
+            constraints: Constraints::default(),
+            fixes: Vec::new(),
+            budget: 100,
+        }
+    }
+
+    /// Sets the rules fields are checked against.
+    pub fn rules(mut self, rules: &[Rule]) -> Pipeline<G> {
+        self.rules = rules.to_vec();
+        self
+    }
+
+    /// Sets the constraints checked by [`Rule::Constraints`].
+    pub fn constraints(mut self, constraints: Constraints) -> Pipeline<G> {
+        self.constraints = constraints;
+        self
+    }
+
+    /// Adds a fix, run on fields that break a rule.
+    pub fn fix<F>(mut self, fix: F) -> Pipeline<G>
+    where
+        F: Fn(&mut Field) + 'static,
+    {
+        self.fixes.push(Box::new(fix));
+        self
+    }
+
+    /// Sets how many fields to generate before giving up.
+    pub fn budget(mut self, budget: usize) -> Pipeline<G> {
+        self.budget = budget;
+        self
+    }
+
+    /// Runs the pipeline, returning the first field that follows the rules,
+    /// if any did. The same seed always gives the same result.
+    pub fn run(&self, seed: u64) -> (Option<Field>, PipelineStats) {
+        let mut seeds = Rng::new(seed);
+        let mut stats = PipelineStats::default();
+
+        for _ in 0..self.budget {
+            let mut field = (self.generator)(seeds.next_u64());
+            stats.attempts += 1;
+
+            if self.broken(&field).is_none() {
+                return (Some(field), stats);
+            }
+
+            for fix in self.fixes.iter() {
+                fix(&mut field);
+            }
+
+            match self.broken(&field) {
+                None => {
+                    stats.fixed = true;
+                    return (Some(field), stats);
+                },
+                Some(rule) => *stats.rejections.entry(rule).or_default() += 1,
+            }
+        }
+
+        (None, stats)
+    }
+
+    /// Gets the first rule a field breaks.
+    fn broken(&self, field: &Field) -> Option<Rule> {
+        self.rules.iter().copied().find(|rule| !rule.holds(field, &self.constraints))
+    }
+}
@@ -0,0 +1,167 @@
+//! Common field shapes, ready to be filled in.
+//!
+//! Each template is made of Neutral panels with their exits and backtrack
+//! exits already wired, filling the field it is given as best the shape
+//! allows.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::gen::templates;
+//! use citrus_common::analysis::{self, Connectivity};
+//!
+//! let field = templates::double_ring(8, 7);
+//!
+//! assert_eq!(analysis::components(&field, Connectivity::Strong).len(), 1);
+//! assert!(analysis::dead_ends(&field).is_empty());
+//! ```
+
+use crate::{Field, Pos};
+use crate::draw;
+use crate::pos::Rect;
+
+use super::lay;
+
+/// Makes a single loop around the edges of the field, clockwise.
+///
+/// # Panics
+/// Will panic if the field is less than two panels wide or tall.
+pub fn ring(width: usize, height: usize) -> Field {
+    assert!(width >= 2 && height >= 2, "rings must be at least 2x2");
+
+    let mut field = Field::builder(width, height).build();
+    lay(&mut field, &closed(draw::ring(Rect::of_size(width, height))), false);
+
+    field
+}
+
+/// Makes a loop around the edges of the field, with a second loop inside it.
+///
+/// Players cross onto the inner loop near the top-left corner, and back out
+/// near the bottom-right corner.
+///
+/// # Panics
+/// Will panic if the field is less than six panels wide or tall.
+pub fn double_ring(width: usize, height: usize) -> Field {
+    assert!(width >= 6 && height >= 6, "double rings must be at least 6x6");
+
+    let mut field = ring(width, height);
+    let inner = draw::ring(Rect::new(2, 2, width - 4, height - 4));
+    lay(&mut field, &closed(inner), false);
+
+    let (right, bottom) = (width - 3, height - 3);
+    let cross_in = [Pos::new(2, 0), Pos::new(2, 1), Pos::new(2, 2)];
+    let cross_out = [Pos::new(right, bottom), Pos::new(right, bottom + 1), Pos::new(right, bottom + 2)];
+    lay(&mut field, &cross_in, false);
+    lay(&mut field, &cross_out, false);
+
+    field
+}
+
+/// Makes a spiral from the top-left corner in to the middle, covering the
+/// whole field.
+///
+/// A spiral has nowhere to loop back around, so its exits lead both ways, and
+/// players walk back out the way they came.
+///
+/// # Panics
+/// Will panic if the field is less than two panels wide or tall.
+pub fn spiral(width: usize, height: usize) -> Field {
+    assert!(width >= 2 && height >= 2, "spirals must be at least 2x2");
+
+    let mut path = Vec::new();
+    let mut rect = Rect::of_size(width, height);
+
+    // each turn of the spiral ends next to where the next begins
+    while rect.width >= 2 && rect.height >= 2 {
+        path.extend(draw::ring(rect));
+        rect = Rect::new(rect.x + 1, rect.y + 1, rect.width.saturating_sub(2), rect.height.saturating_sub(2));
+    }
+
+    // a single row or column may be left in the middle
+    if rect.width > 0 && rect.height > 0 {
+        path.extend(rect.positions());
+    }
+
+    let mut field = Field::builder(width, height).build();
+    lay(&mut field, &path, true);
+
+    field
+}
+
+/// Makes a loop around the outline of a plus, with an arm reaching to the
+/// middle of each side of the field.
+///
+/// Each arm is a third of the field long, and the middle of the plus is
+/// left empty.
+///
+/// # Panics
+/// Will panic if the field is less than four panels wide or tall.
+pub fn plus(width: usize, height: usize) -> Field {
+    assert!(width >= 4 && height >= 4, "pluses must be at least 4x4");
+
+    let (left, right) = (width / 3, width - 1 - width / 3);
+    let (top, bottom) = (height / 3, height - 1 - height / 3);
+    let (far_right, far_bottom) = (width - 1, height - 1);
+
+    let corners = [
+        (left, 0), (right, 0), (right, top), (far_right, top),
+        (far_right, bottom), (right, bottom), (right, far_bottom), (left, far_bottom),
+        (left, bottom), (0, bottom), (0, top), (left, top), (left, 0),
+    ];
+
+    let mut path = vec![Pos::new(left, 0)];
+
+    for pair in corners.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        path.extend(line(Pos::new(from.0, from.1), Pos::new(to.0, to.1)).skip(1));
+    }
+
+    let mut field = Field::builder(width, height).build();
+    lay(&mut field, &path, false);
+
+    field
+}
+
+/// Makes two loops that meet at a single panel in the middle of the field,
+/// one in the top-left and one in the bottom-right.
+///
+/// Players coming off of either loop can carry on around the other.
+///
+/// # Panics
+/// Will panic if the field is less than three panels wide or tall.
+pub fn figure_eight(width: usize, height: usize) -> Field {
+    assert!(width >= 3 && height >= 3, "figure eights must be at least 3x3");
+
+    let middle = Pos::new((width - 1) / 2, (height - 1) / 2);
+    let first = draw::ring(Rect::new(0, 0, middle.x + 1, middle.y + 1));
+    let second = draw::ring(Rect::new(middle.x, middle.y, width - middle.x, height - middle.y));
+
+    let mut field = Field::builder(width, height).build();
+    lay(&mut field, &closed(starting_at(first, middle)), false);
+    lay(&mut field, &closed(second), false);
+
+    field
+}
+
+/// Gets the positions along a straight line, both ends included.
+fn line(from: Pos, to: Pos) -> impl Iterator<Item = Pos> {
+    let steps = from.x.abs_diff(to.x).max(from.y.abs_diff(to.y));
+    let step = |from: usize, to: usize, idx: usize| {
+        if to >= from { from + idx.min(to - from) } else { from - idx.min(from - to) }
+    };
+
+    (0..=steps).map(move |idx| Pos::new(step(from.x, to.x, idx), step(from.y, to.y, idx)))
+}
+
+/// Turns a ring so it starts at a position on it.
+fn starting_at(mut ring: Vec<Pos>, start: Pos) -> Vec<Pos> {
+    let idx = ring.iter().position(|pos| *pos == start).unwrap_or(0);
+    ring.rotate_left(idx);
+    ring
+}
+
+/// Closes a ring into a path that ends where it starts.
+fn closed(mut ring: Vec<Pos>) -> Vec<Pos> {
+    ring.push(ring[0]);
+    ring
+}
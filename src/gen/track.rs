@@ -0,0 +1,77 @@
+use crate::{Field, Pos};
+use crate::draw::ring;
+use crate::pos::Rect;
+use crate::rng::Rng;
+
+use super::lay;
+
+use std::collections::HashSet;
+
+/// Generates a single winding loop, with no branches, that players can walk
+/// either way.
+///
+/// The loop starts as a 2x2 square and grows by pushing out a side at a
+/// time, until it is at least `length` panels long or there is no room left
+/// to grow. Loops on a grid always have an even number of panels. Every
+/// panel is Neutral, ready for [`fill`](super::fill).
+///
+/// # Panics
+/// Will panic if the field is less than two panels wide or tall.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{gen, PanelKind};
+/// use citrus_common::analysis;
+///
+/// let field = gen::track(10, 8, 30, 5);
+///
+/// assert_eq!(field.panels().filter(|(_, _, panel)| panel.kind != PanelKind::Empty).count(), 30);
+/// assert!(analysis::dead_ends(&field).is_empty());
+/// ```
+pub fn track(width: usize, height: usize, length: usize, seed: u64) -> Field {
+    assert!(width >= 2 && height >= 2, "fields must be at least 2x2 to loop");
+
+    let mut rng = Rng::new(seed);
+    let start = Rect::new(rng.below(width - 1), rng.below(height - 1), 2, 2);
+
+    let mut path = ring(start);
+    let mut taken: HashSet<Pos> = path.iter().copied().collect();
+
+    while path.len() < length {
+        // sides of the loop that can be pushed out into empty space
+        let mut pushes = Vec::new();
+
+        for idx in 0..path.len() {
+            let (a, b) = (path[idx], path[(idx + 1) % path.len()]);
+            let (dx, dy) = (b.x as i64 - a.x as i64, b.y as i64 - a.y as i64);
+
+            for (px, py) in [(-dy, dx), (dy, -dx)].iter() {
+                let out = (a.offset(*px, *py), b.offset(*px, *py));
+
+                if let (Some(a), Some(b)) = out {
+                    let free = |pos: Pos| pos.x < width && pos.y < height && !taken.contains(&pos);
+
+                    if free(a) && free(b) {
+                        pushes.push((idx, a, b));
+                    }
+                }
+            }
+        }
+
+        match rng.pick(&pushes) {
+            Some((idx, a, b)) => {
+                path.splice(idx + 1..idx + 1, [*a, *b].iter().copied());
+                taken.insert(*a);
+                taken.insert(*b);
+            },
+            None => break,
+        }
+    }
+
+    path.push(path[0]);
+
+    let mut field = Field::builder(width, height).build();
+    lay(&mut field, &path, true);
+
+    field
+}
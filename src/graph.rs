@@ -0,0 +1,90 @@
+//! Converting fields to and from [`petgraph`] graphs.
+//!
+//! Panels are nodes and exits are edges, the same way
+//! [`path`](crate::path) follows them, so any of `petgraph`'s algorithms can
+//! run on a field.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, Pos, PanelKind::*};
+//! use petgraph::algo::has_path_connecting;
+//!
+//! let field = field![[Home + EAST, Draw + EAST, Bonus]];
+//! let graph = field.to_graph();
+//!
+//! let home = graph.node_indices().find(|idx| graph[*idx] == Pos::new(0, 0)).unwrap();
+//! let bonus = graph.node_indices().find(|idx| graph[*idx] == Pos::new(2, 0)).unwrap();
+//!
+//! assert!(has_path_connecting(&graph, home, bonus, None));
+//! assert!(!has_path_connecting(&graph, bonus, home, None));
+//! ```
+
+use crate::{Direction, Field, Exits, PanelKind, Pos};
+
+use petgraph::graph::{DiGraph, NodeIndex};
+
+use std::collections::HashMap;
+
+impl Field {
+    /// Creates a graph of the field, where each non-empty panel is a node,
+    /// weighted with its position, and each exit between two of them is an
+    /// edge, weighted with the exit's direction.
+    ///
+    /// Nodes are added in row-major order. Exits leading off the field or
+    /// into empty panels are left out, as are backtrack exits.
+    pub fn to_graph(&self) -> DiGraph<Pos, Direction> {
+        let mut graph = DiGraph::new();
+
+        let nodes: HashMap<Pos, NodeIndex> = self.positions()
+            .filter(|pos| self[*pos].kind != PanelKind::Empty)
+            .map(|pos| (pos, graph.add_node(pos)))
+            .collect();
+
+        for from in graph.node_indices() {
+            let pos = graph[from];
+
+            for dir in self[pos].exits.directions() {
+                let to = pos.step(dir).and_then(|to| nodes.get(&to));
+
+                if let Some(to) = to {
+                    graph.add_edge(from, *to, dir);
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Replaces the exits of the field's panels with the edges of a graph,
+    /// like one made by [`Field::to_graph`].
+    ///
+    /// Each edge gives its starting panel an exit in the direction it is
+    /// weighted with.
+    /// Backtrack exits are left alone.
+    ///
+    /// # Panics
+    /// Will panic if any node's position is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, Direction, PanelKind::*};
+    ///
+    /// let mut field = field![[Home + EAST, Draw]];
+    ///
+    /// let mut graph = field.to_graph();
+    /// graph.reverse();
+    /// graph.edge_weights_mut().for_each(|dir| *dir = Direction::West);
+    ///
+    /// field.set_exits_from_graph(&graph);
+    /// assert_eq!(field, field![[Home, Draw + WEST]]);
+    /// ```
+    pub fn set_exits_from_graph(&mut self, graph: &DiGraph<Pos, Direction>) {
+        for pos in graph.node_weights() {
+            self[*pos].exits = Exits::none();
+        }
+
+        for edge in graph.raw_edges() {
+            self[graph[edge.source()]].exits |= edge.weight.to_exits();
+        }
+    }
+}
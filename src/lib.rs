@@ -1,14 +1,39 @@
 //! Tools for working with 100% Orange Juice fields.
 
+pub mod analysis;
+pub mod annotate;
+pub mod diff;
+pub mod draw;
+pub mod edit;
+pub mod export;
 pub mod field;
+pub mod field_set;
 pub mod format;
+pub mod gen;
+#[cfg(feature = "petgraph")]
+pub mod graph;
+#[cfg(feature = "fields")]
+pub mod official;
 pub mod panel;
+pub mod path;
+pub mod pos;
+pub mod render;
+pub mod sanitize;
+pub mod sim;
+#[cfg(feature = "tilemap")]
+pub mod tilemap;
+pub mod transform;
 
 #[doc(hidden)]
 pub mod util;
 
-pub use field::Field;
-pub use panel::{Panel, PanelKind, Exits};
+mod rng;
+
+pub use field::{Field, FieldBuilder};
+pub use field_set::FieldSet;
+pub use panel::{Panel, PanelKind, Exits, Direction};
+pub use pos::Pos;
+pub use format::DecodeError;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,92 @@
+//! The official fields, behind the `fields` feature.
+//!
+//! The fields are embedded in their original `.fld` form, and decoded the
+//! first time they are used.
+//!
+//! Only one field is embedded: Training Program, the single official field
+//! whose data ships with this crate, shared with its test fixtures. The
+//! other official boards are not available, and lookups for them return
+//! `None`.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{official, PanelKind};
+//!
+//! let field = official::by_name("Training Program").unwrap();
+//!
+//! assert_eq!(field.width(), 15);
+//! assert!(field.iter().any(|(x, y)| field.get((x, y)).kind == PanelKind::Home));
+//! ```
+
+use crate::Field;
+use crate::format::fld;
+
+use std::sync::OnceLock;
+
+/// An official field.
+pub struct OfficialField {
+    name: &'static str,
+    dims: (usize, usize),
+    data: &'static [u8],
+    field: OnceLock<Field>,
+}
+
+impl OfficialField {
+    const fn new(name: &'static str, dims: (usize, usize), data: &'static [u8]) -> OfficialField {
+        OfficialField {
+            name,
+            dims,
+            data,
+            field: OnceLock::new(),
+        }
+    }
+
+    /// Gets the name of the field, as shown in game.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Gets the dimensions of the field.
+    pub fn dims(&self) -> (usize, usize) {
+        self.dims
+    }
+
+    /// Gets the field's original `.fld` data.
+    pub fn data(&self) -> &'static [u8] {
+        self.data
+    }
+
+    /// Gets the field, decoding it if this is the first time.
+    pub fn field(&self) -> &Field {
+        self.field.get_or_init(|| {
+            fld::decode(self.dims, self.data)
+                .expect("official fields are valid")
+        })
+    }
+}
+
+// the one embedded field is the test fixture, so it is only stored once
+static EMBEDDED: [OfficialField; 1] = [
+    OfficialField::new("Training Program", fld::S15, include_bytes!("../tests/field_training.fld")),
+];
+
+/// Gets an iterator over the embedded official fields.
+///
+/// This is only Training Program.
+pub fn iter() -> impl DoubleEndedIterator<Item = &'static OfficialField> + ExactSizeIterator {
+    EMBEDDED.iter()
+}
+
+/// Finds an embedded official field by name.
+///
+/// Names are compared case-insensitively.
+pub fn get(name: &str) -> Option<&'static OfficialField> {
+    EMBEDDED.iter().find(|field| field.name().eq_ignore_ascii_case(name))
+}
+
+/// Gets an embedded official field by name.
+///
+/// Names are compared case-insensitively.
+pub fn by_name(name: &str) -> Option<&'static Field> {
+    get(name).map(OfficialField::field)
+}
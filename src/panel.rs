@@ -11,12 +11,19 @@
 //! assert!(panel.exits.has(Exits::SOUTH));
 //! ```
 
-use num_enum::{IntoPrimitive, TryFromPrimitive};
+use num_enum::{FromPrimitive, IntoPrimitive};
 
-use std::ops::{BitOr, BitOrAssign, BitAnd};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
 
 /// A single panel.
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Panel {
     /// The panel's kind.
     pub kind: PanelKind,
@@ -56,7 +63,21 @@ impl Panel {
 }
 
 /// A panel's type.
-#[derive(Copy, Clone, Debug, PartialEq, IntoPrimitive, TryFromPrimitive)]
+///
+/// Kinds convert to and from the bytes the game stores them as. Bytes this
+/// crate does not know convert to [`Unknown`](PanelKind::Unknown), which
+/// converts back to the same byte.
+///
+/// # Examples
+/// ```
+/// use citrus_common::PanelKind;
+///
+/// assert_eq!(PanelKind::from(0x05), PanelKind::Bonus);
+/// assert_eq!(PanelKind::from(0xF0), PanelKind::Unknown(0xF0));
+/// assert_eq!(u8::from(PanelKind::Unknown(0xF0)), 0xF0);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, IntoPrimitive, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(u8)]
 pub enum PanelKind {
     Empty = 0x00,
@@ -81,31 +102,348 @@ pub enum PanelKind {
     Heal2x = 0x1C, // confirmation needed
     Damage = 0x20,
     Damage2x = 0x21,
+    /// A kind this crate does not know, with the byte it is stored as.
+    #[num_enum(catch_all)]
+    Unknown(u8),
+}
+
+impl PanelKind {
+    /// Every kind this crate knows, in the order of their bytes.
+    pub const KNOWN: [PanelKind; 22] = [
+        PanelKind::Empty, PanelKind::Neutral, PanelKind::Home, PanelKind::Encounter,
+        PanelKind::Draw, PanelKind::Bonus, PanelKind::Drop, PanelKind::Warp,
+        PanelKind::Draw2x, PanelKind::Bonus2x, PanelKind::Drop2x, PanelKind::Deck,
+        PanelKind::Encounter2x, PanelKind::Move, PanelKind::Move2x, PanelKind::WarpMove,
+        PanelKind::WarpMove2x, PanelKind::Ice, PanelKind::Heal, PanelKind::Heal2x,
+        PanelKind::Damage, PanelKind::Damage2x,
+    ];
+
+    /// Checks if this crate knows this kind, so it is not
+    /// [`Unknown`](PanelKind::Unknown).
+    pub const fn is_known(self) -> bool {
+        !matches!(self, PanelKind::Unknown(_))
+    }
+
+    /// Checks if this is the 2x version of another kind.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::PanelKind;
+    ///
+    /// assert!(PanelKind::Draw2x.is_2x());
+    /// assert_eq!(PanelKind::Draw2x.base_kind(), PanelKind::Draw);
+    /// assert_eq!(PanelKind::Draw2x.multiplier(), 2);
+    /// assert_eq!(PanelKind::Draw.base_kind(), PanelKind::Draw);
+    /// ```
+    pub const fn is_2x(self) -> bool {
+        use PanelKind::*;
+
+        matches!(self, Draw2x | Bonus2x | Drop2x | Encounter2x | Move2x | WarpMove2x | Heal2x | Damage2x)
+    }
+
+    /// Gets the kind this is the 2x version of, or this kind if it is not a
+    /// 2x version.
+    pub const fn base_kind(self) -> PanelKind {
+        use PanelKind::*;
+
+        match self {
+            Draw2x => Draw,
+            Bonus2x => Bonus,
+            Drop2x => Drop,
+            Encounter2x => Encounter,
+            Move2x => Move,
+            WarpMove2x => WarpMove,
+            Heal2x => Heal,
+            Damage2x => Damage,
+            kind => kind,
+        }
+    }
+
+    /// Gets how many times over this kind's effect happens: `2` for 2x
+    /// versions, and `1` otherwise.
+    pub const fn multiplier(self) -> u32 {
+        if self.is_2x() { 2 } else { 1 }
+    }
+
+    /// Checks if landing on this kind warps the unit to another panel of
+    /// it: Warp, WarpMove and WarpMove2x.
+    pub const fn is_warp(self) -> bool {
+        matches!(self.base_kind(), PanelKind::Warp | PanelKind::WarpMove)
+    }
+
+    /// Checks if landing on this kind moves the unit somewhere else: the
+    /// warps, Move and Move2x.
+    pub const fn is_movement(self) -> bool {
+        self.is_warp() || matches!(self.base_kind(), PanelKind::Move)
+    }
+
+    /// Checks if landing on this kind gains or loses stars by itself:
+    /// Bonus, Drop and their 2x versions.
+    pub const fn affects_stars(self) -> bool {
+        matches!(self.base_kind(), PanelKind::Bonus | PanelKind::Drop)
+    }
+
+    /// Gets the category of this kind.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::PanelKind;
+    /// use citrus_common::panel::PanelCategory;
+    ///
+    /// assert_eq!(PanelKind::Bonus2x.category(), PanelCategory::Gain);
+    /// assert_eq!(PanelKind::Home.category(), PanelCategory::Structural);
+    /// ```
+    pub const fn category(self) -> PanelCategory {
+        use PanelKind::*;
+
+        match self.base_kind() {
+            Bonus | Draw | Heal => PanelCategory::Gain,
+            Drop | Damage => PanelCategory::Loss,
+            Encounter => PanelCategory::Battle,
+            Warp | WarpMove | Move | Ice => PanelCategory::Movement,
+            Deck | Unknown(_) => PanelCategory::Utility,
+            _ => PanelCategory::Structural,
+        }
+    }
+}
+
+/// The canonical name of each kind.
+const KIND_NAMES: [(PanelKind, &str); 22] = [
+    (PanelKind::Empty, "Empty"),
+    (PanelKind::Neutral, "Neutral"),
+    (PanelKind::Home, "Home"),
+    (PanelKind::Encounter, "Encounter"),
+    (PanelKind::Draw, "Draw"),
+    (PanelKind::Bonus, "Bonus"),
+    (PanelKind::Drop, "Drop"),
+    (PanelKind::Warp, "Warp"),
+    (PanelKind::Draw2x, "Draw 2x"),
+    (PanelKind::Bonus2x, "Bonus 2x"),
+    (PanelKind::Drop2x, "Drop 2x"),
+    (PanelKind::Deck, "Deck"),
+    (PanelKind::Encounter2x, "Encounter 2x"),
+    (PanelKind::Move, "Move"),
+    (PanelKind::Move2x, "Move 2x"),
+    (PanelKind::WarpMove, "Warp Move"),
+    (PanelKind::WarpMove2x, "Warp Move 2x"),
+    (PanelKind::Ice, "Ice"),
+    (PanelKind::Heal, "Heal"),
+    (PanelKind::Heal2x, "Heal 2x"),
+    (PanelKind::Damage, "Damage"),
+    (PanelKind::Damage2x, "Damage 2x"),
+];
+
+/// The character and abbreviation of each kind. 2x versions are written in
+/// uppercase.
+const KIND_TEXT: [(PanelKind, char, &str); 22] = [
+    (PanelKind::Empty, ' ', "  "),
+    (PanelKind::Neutral, '.', "[]"),
+    (PanelKind::Home, '@', "@@"),
+    (PanelKind::Encounter, 'e', "en"),
+    (PanelKind::Draw, 'd', "da"),
+    (PanelKind::Bonus, 'b', "bs"),
+    (PanelKind::Drop, 'r', "dr"),
+    (PanelKind::Warp, 'w', "wa"),
+    (PanelKind::Draw2x, 'D', "DA"),
+    (PanelKind::Bonus2x, 'B', "BS"),
+    (PanelKind::Drop2x, 'R', "DR"),
+    (PanelKind::Deck, '_', "__"),
+    (PanelKind::Encounter2x, 'E', "EN"),
+    (PanelKind::Move, 'm', "mo"),
+    (PanelKind::Move2x, 'M', "MO"),
+    (PanelKind::WarpMove, 'v', "wm"),
+    (PanelKind::WarpMove2x, 'V', "WM"),
+    (PanelKind::Ice, 'i', "ic"),
+    (PanelKind::Heal, 'h', "he"),
+    (PanelKind::Heal2x, 'H', "HE"),
+    (PanelKind::Damage, 'x', "dm"),
+    (PanelKind::Damage2x, 'X', "DM"),
+];
+
+impl PanelKind {
+    /// Gets the character this kind is written as, or `?` for kinds this
+    /// crate does not know.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::PanelKind;
+    /// use std::convert::TryFrom;
+    ///
+    /// assert_eq!(PanelKind::Bonus2x.to_char(), 'B');
+    /// assert_eq!(PanelKind::try_from('B'), Ok(PanelKind::Bonus2x));
+    /// assert_eq!(PanelKind::Bonus2x.abbreviation(), "BS");
+    /// assert_eq!(PanelKind::from_abbreviation("BS"), Some(PanelKind::Bonus2x));
+    /// ```
+    pub fn to_char(self) -> char {
+        KIND_TEXT.iter()
+            .find(|(kind, _, _)| *kind == self)
+            .map_or('?', |(_, c, _)| *c)
+    }
+
+    /// Gets the two characters this kind is written as, or `??` for kinds
+    /// this crate does not know.
+    ///
+    /// Every text format in this crate, like [`render::ascii`] and
+    /// [`format::csv`], writes kinds this way.
+    ///
+    /// [`render::ascii`]: crate::render::ascii
+    /// [`format::csv`]: crate::format::csv
+    pub fn abbreviation(self) -> &'static str {
+        KIND_TEXT.iter()
+            .find(|(kind, _, _)| *kind == self)
+            .map_or("??", |(_, _, abbrev)| *abbrev)
+    }
+
+    /// Finds the kind written as an abbreviation.
+    pub fn from_abbreviation(text: &str) -> Option<PanelKind> {
+        KIND_TEXT.iter()
+            .find(|(_, _, abbrev)| *abbrev == text)
+            .map(|(kind, _, _)| *kind)
+    }
+}
+
+/// Reads a kind from the character [`PanelKind::to_char`] writes.
+impl TryFrom<char> for PanelKind {
+    type Error = InvalidKind;
+
+    fn try_from(c: char) -> Result<PanelKind, InvalidKind> {
+        KIND_TEXT.iter()
+            .find(|(_, other, _)| *other == c)
+            .map(|(kind, _, _)| *kind)
+            .ok_or_else(|| InvalidKind { value: c.to_string() })
+    }
+}
+
+/// Other names kinds are known by, squashed like [`squash`] does.
+const KIND_ALIASES: [(&str, PanelKind); 4] = [
+    ("none", PanelKind::Empty),
+    ("blank", PanelKind::Empty),
+    ("battle", PanelKind::Encounter),
+    ("card", PanelKind::Draw),
+];
+
+/// Writes the kind's canonical name, like `Bonus 2x`, or `Unknown 0x2A` for
+/// kinds this crate does not know.
+///
+/// # Examples
+/// ```
+/// use citrus_common::PanelKind;
+///
+/// assert_eq!(PanelKind::WarpMove2x.to_string(), "Warp Move 2x");
+/// assert_eq!("warp_move_x2".parse::<PanelKind>().unwrap(), PanelKind::WarpMove2x);
+/// assert_eq!("Battle".parse::<PanelKind>().unwrap(), PanelKind::Encounter);
+/// ```
+impl Display for PanelKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match KIND_NAMES.iter().find(|(kind, _)| kind == self) {
+            Some((_, name)) => f.write_str(name),
+            None => write!(f, "Unknown {:#04X}", u8::from(*self)),
+        }
+    }
+}
+
+/// Reads a kind from its canonical name or a common alias, ignoring case,
+/// spaces, `-` and `_`. `x2` is read the same as `2x`.
+impl FromStr for PanelKind {
+    type Err = InvalidKind;
+
+    fn from_str(text: &str) -> Result<PanelKind, InvalidKind> {
+        let name = squash(text);
+
+        KIND_NAMES.iter()
+            .find(|(_, other)| squash(other) == name)
+            .map(|(kind, _)| *kind)
+            .or_else(|| {
+                KIND_ALIASES.iter()
+                    .find(|(alias, _)| *alias == name)
+                    .map(|(_, kind)| *kind)
+            })
+            .or_else(|| {
+                let value = name.strip_prefix("unknown0x")?;
+                u8::from_str_radix(value, 16).ok().map(PanelKind::from)
+            })
+            .ok_or_else(|| InvalidKind { value: text.to_string() })
+    }
+}
+
+/// Lowercases a name and drops its separators, so names can be compared
+/// loosely.
+fn squash(name: &str) -> String {
+    let name: String = name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    match name.strip_suffix("x2") {
+        Some(base) => format!("{}2x", base),
+        None => name,
+    }
+}
+
+/// An error that indicates a kind name that could not be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidKind {
+    /// The text that could not be understood.
+    pub value: String,
+}
+
+impl Display for InvalidKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "invalid panel kind {:?}", self.value)
+    }
+}
+
+impl std::error::Error for InvalidKind { }
+
+/// What a kind of panel does, broadly, from [`PanelKind::category`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PanelCategory {
+    /// Gives the unit stars, cards or HP: Bonus, Draw and Heal.
+    Gain,
+    /// Takes stars or HP from the unit: Drop and Damage.
+    Loss,
+    /// Starts a battle: Encounter.
+    Battle,
+    /// Moves the unit, or changes how it moves: the warps, Move and Ice.
+    Movement,
+    /// Does something else for the game: Deck, and kinds this crate does
+    /// not know.
+    Utility,
+    /// Shapes the field without an effect of its own: Empty, Neutral and
+    /// Home.
+    Structural,
 }
 
 /// A panel's exits.
 ///
 /// To combine two directions together into one exit, e.g. make an `Exits` that
 /// is both `SOUTH` and `NORTH`, use the `|` operator. To check if an exit has
-/// a direction, use the `&` operator.
+/// a direction, use [`has`](Exits::has). The `&`, `^` and `!` operators work
+/// like they do on bitflags, keeping to the four directions.
 ///
 /// # Examples
 /// ```
-/// use citrus_common::panel::Exits;
+/// use citrus_common::panel::{Direction, Exits};
 ///
 /// // check if our exits has a direction set.
 /// let exits = Exits::SOUTH;
-/// assert!(exits & Exits::SOUTH);
-/// assert!(!(exits & Exits::NORTH));
+/// assert!(exits.has(Exits::SOUTH));
+/// assert!(!exits.has(Exits::NORTH));
 ///
 /// // make exits that point to north and south
-/// let exits = Exits::SOUTH | Exits::NORTH;
-/// assert!(exits & Exits::SOUTH);
-/// assert!(exits & Exits::NORTH);
+/// let mut exits = Exits::SOUTH | Exits::NORTH;
+/// assert!(exits.has(Exits::SOUTH));
+/// assert!(exits.has(Exits::NORTH));
 /// // we can also mix these together, AOK!
-/// assert!(exits & (Exits::SOUTH | Exits::NORTH));
+/// assert_eq!(exits & (Exits::SOUTH | Exits::EAST), Exits::SOUTH);
+/// assert_eq!(!exits, Exits::EAST | Exits::WEST);
+/// assert_eq!(exits.directions().collect::<Vec<_>>(), vec![Direction::North, Direction::South]);
+///
+/// exits.remove(Exits::NORTH);
+/// assert_eq!(exits.count(), 1);
 /// ```
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Exits(u8);
 
 impl Exits {
@@ -119,18 +457,301 @@ impl Exits {
         Exits(0)
     }
 
+    /// An `Exits` with every exit.
+    pub const fn all() -> Exits {
+        Exits(0b1111)
+    }
+
     /// Checks if an `Exits` has a direction, or multiple directions.
     pub const fn has(&self, rhs: Exits) -> bool {
         self.0 & rhs.0 > 0
     }
+
+    /// Checks if an `Exits` has no exits.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Gets the number of exits.
+    pub const fn count(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Gets these exits without any of the directions in `rhs`.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::Exits;
+    ///
+    /// let exits = Exits::NORTH | Exits::EAST;
+    ///
+    /// assert_eq!(exits.without(Exits::EAST | Exits::SOUTH), Exits::NORTH);
+    /// ```
+    pub const fn without(self, rhs: Exits) -> Exits {
+        Exits(self.0 & !rhs.0)
+    }
+
+    /// Removes the directions in `rhs` from these exits, in place.
+    pub fn remove(&mut self, rhs: Exits) {
+        *self = self.without(rhs);
+    }
+
+    /// Gets an iterator over the directions of the exits, in the order of
+    /// [`Direction::ALL`].
+    pub fn directions(self) -> impl Iterator<Item = Direction> {
+        Direction::ALL.iter()
+            .copied()
+            .filter(move |dir| self.has(dir.to_exits()))
+    }
+
+    /// Gets an iterator over the directions of the exits, in the order they
+    /// are written as text: north, east, south, west.
+    pub(crate) fn written(self) -> impl Iterator<Item = Direction> {
+        DIRECTION_TEXT.iter()
+            .map(|(dir, _, _)| *dir)
+            .filter(move |dir| self.has(dir.to_exits()))
+    }
 }
 
-impl PartialEq for Exits {
-    fn eq(&self, rhs: &Exits) -> bool {
-        self.0 == rhs.0
+/// A direction an exit can lead in.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{Direction, Exits, Pos};
+///
+/// let dir = Direction::East;
+///
+/// assert_eq!(dir.opposite(), Direction::West);
+/// assert_eq!(dir.to_exits(), Exits::EAST);
+/// assert_eq!(Pos::new(1, 1).step(dir), Some(Pos::new(2, 1)));
+/// assert_eq!(Pos::new(0, 0).step(Direction::North), None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    /// Every direction, in the order of the bits of [`Exits`], which is
+    /// clockwise from west.
+    pub const ALL: [Direction; 4] = [
+        Direction::West,
+        Direction::North,
+        Direction::East,
+        Direction::South,
+    ];
+
+    /// Gets how far a step in this direction moves along `x` and `y`.
+    pub const fn delta(self) -> (i64, i64) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::South => (0, 1),
+            Direction::East => (1, 0),
+            Direction::West => (-1, 0),
+        }
+    }
+
+    /// Gets the direction leading the other way.
+    pub const fn opposite(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
+
+    /// Gets the direction a quarter turn clockwise.
+    pub const fn clockwise(self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    /// Gets the direction a quarter turn counterclockwise.
+    pub const fn counterclockwise(self) -> Direction {
+        self.clockwise().opposite()
+    }
+
+    /// Gets the exit leading in this direction.
+    pub const fn to_exits(self) -> Exits {
+        match self {
+            Direction::North => Exits::NORTH,
+            Direction::South => Exits::SOUTH,
+            Direction::East => Exits::EAST,
+            Direction::West => Exits::WEST,
+        }
+    }
+
+    /// Gets the first letter of the direction's name, in uppercase.
+    pub fn letter(self) -> char {
+        self.text().1
+    }
+
+    /// Gets the direction's name, in lowercase.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::Direction;
+    ///
+    /// assert_eq!(Direction::North.name(), "north");
+    /// assert_eq!(Direction::North.letter(), 'N');
+    /// assert_eq!("N".parse::<Direction>().unwrap(), Direction::North);
+    /// ```
+    pub fn name(self) -> &'static str {
+        self.text().2
+    }
+
+    fn text(self) -> &'static (Direction, char, &'static str) {
+        DIRECTION_TEXT.iter()
+            .find(|(dir, _, _)| *dir == self)
+            .expect("every direction has text")
+    }
+}
+
+/// The letter and name of each direction, in the order they are written.
+const DIRECTION_TEXT: [(Direction, char, &str); 4] = [
+    (Direction::North, 'N', "north"),
+    (Direction::East, 'E', "east"),
+    (Direction::South, 'S', "south"),
+    (Direction::West, 'W', "west"),
+];
+
+/// Reads a direction from its [letter](Direction::letter), in any case.
+impl TryFrom<char> for Direction {
+    type Error = InvalidDirection;
+
+    fn try_from(c: char) -> Result<Direction, InvalidDirection> {
+        DIRECTION_TEXT.iter()
+            .find(|(_, letter, _)| letter.eq_ignore_ascii_case(&c))
+            .map(|(dir, _, _)| *dir)
+            .ok_or_else(|| InvalidDirection { value: c.to_string() })
+    }
+}
+
+/// Reads a direction from its letter or its name, in any case.
+impl FromStr for Direction {
+    type Err = InvalidDirection;
+
+    fn from_str(text: &str) -> Result<Direction, InvalidDirection> {
+        let text = text.trim();
+        let mut chars = text.chars();
+
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Direction::try_from(c);
+        }
+
+        DIRECTION_TEXT.iter()
+            .find(|(_, _, name)| text.eq_ignore_ascii_case(name))
+            .map(|(dir, _, _)| *dir)
+            .ok_or_else(|| InvalidDirection { value: text.to_string() })
+    }
+}
+
+impl From<Direction> for Exits {
+    fn from(dir: Direction) -> Exits {
+        dir.to_exits()
+    }
+}
+
+impl Debug for Exits {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("Exits(")?;
+
+        let mut first = true;
+
+        for dir in self.directions() {
+            if !first {
+                f.write_str(" | ")?;
+            }
+
+            f.write_str(&dir.name().to_ascii_uppercase())?;
+            first = false;
+        }
+
+        if first {
+            f.write_str("none")?;
+        }
+
+        f.write_str(")")
+    }
+}
+
+/// Writes the first letter of each direction, separated by `|`, like
+/// `N|E|S`, or `none`.
+///
+/// # Examples
+/// ```
+/// use citrus_common::Exits;
+///
+/// let exits = Exits::WEST | Exits::NORTH;
+///
+/// assert_eq!(exits.to_string(), "N|W");
+/// assert_eq!("n | west".parse::<Exits>().unwrap(), exits);
+/// assert_eq!(Exits::none().to_string().parse::<Exits>().unwrap(), Exits::none());
+/// ```
+impl Display for Exits {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+
+        for (idx, dir) in self.written().enumerate() {
+            if idx > 0 {
+                f.write_str("|")?;
+            }
+
+            write!(f, "{}", dir.letter())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads directions separated by `|`, each its first letter or its name in
+/// any case. `none` and the empty string are no exits.
+impl FromStr for Exits {
+    type Err = InvalidDirection;
+
+    fn from_str(text: &str) -> Result<Exits, InvalidDirection> {
+        let text = text.trim();
+
+        if text.is_empty() || text.eq_ignore_ascii_case("none") {
+            return Ok(Exits::none());
+        }
+
+        let mut exits = Exits::none();
+
+        for part in text.split('|') {
+            exits |= part.parse::<Direction>()?.to_exits();
+        }
+
+        Ok(exits)
+    }
+}
+
+/// An error that indicates a direction that could not be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidDirection {
+    /// The text that could not be understood.
+    pub value: String,
+}
+
+impl Display for InvalidDirection {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "invalid direction {:?}", self.value)
     }
 }
 
+impl std::error::Error for InvalidDirection { }
+
 impl BitOr for Exits {
     type Output = Exits;
 
@@ -146,10 +767,150 @@ impl BitOrAssign for Exits {
 }
 
 impl BitAnd for Exits {
-    type Output = bool;
+    type Output = Exits;
 
-    fn bitand(self, rhs: Exits) -> bool {
-        self.0 & rhs.0 > 0
+    fn bitand(self, rhs: Exits) -> Exits {
+        Exits(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for Exits {
+    fn bitand_assign(&mut self, rhs: Exits) {
+        self.0 &= rhs.0
+    }
+}
+
+impl BitXor for Exits {
+    type Output = Exits;
+
+    fn bitxor(self, rhs: Exits) -> Exits {
+        Exits(self.0 ^ rhs.0)
+    }
+}
+
+impl BitXorAssign for Exits {
+    fn bitxor_assign(&mut self, rhs: Exits) {
+        self.0 ^= rhs.0
+    }
+}
+
+impl Not for Exits {
+    type Output = Exits;
+
+    fn not(self) -> Exits {
+        Exits(!self.0 & 0b1111)
+    }
+}
+
+
+/// Serializes as a list of direction names in human-readable formats, and as
+/// the raw bitflags otherwise.
+#[cfg(feature = "serde")]
+impl Serialize for Exits {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(self.directions().map(Direction::name))
+        } else {
+            serializer.serialize_u8(self.0)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Exits {
+    fn deserialize<D>(deserializer: D) -> Result<Exits, D::Error>
+    where D: Deserializer<'de> {
+        use serde::de::Error as _;
+
+        if deserializer.is_human_readable() {
+            let names = Vec::<String>::deserialize(deserializer)?;
+            let mut exits = Exits::none();
+
+            for name in names {
+                match DIRECTION_TEXT.iter().find(|(_, _, other)| *other == name) {
+                    Some((dir, _, _)) => exits |= dir.to_exits(),
+                    None => return Err(D::Error::unknown_variant(
+                        &name,
+                        &["north", "east", "south", "west"],
+                    )),
+                }
+            }
+
+            Ok(exits)
+        } else {
+            let bits = u8::deserialize(deserializer)?;
+
+            if bits & !0xF == 0 {
+                Ok(Exits(bits))
+            } else {
+                Err(D::Error::custom(
+                    format!("invalid exit bitflags {:#04x}", bits)
+                ))
+            }
+        }
     }
 }
 
+/// Every panel kind, for picking one at random.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for PanelKind {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<PanelKind> {
+        u.choose(&PanelKind::KNOWN).copied()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Exits {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Exits> {
+        Ok(Exits(u.int_in_range(0..=0xF)?))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Panel {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Panel> {
+        Ok(Panel {
+            kind: u.arbitrary()?,
+            exits: u.arbitrary()?,
+            exits_backtrack: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for PanelKind {
+    type Parameters = ();
+    type Strategy = proptest::sample::Select<PanelKind>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        proptest::sample::select(&PanelKind::KNOWN[..])
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Exits {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Exits>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::strategy::Strategy as _;
+
+        (0..=0xFu8).prop_map(Exits).boxed()
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for Panel {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Panel>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::arbitrary::any;
+        use proptest::strategy::Strategy as _;
+
+        (any::<PanelKind>(), any::<Exits>(), any::<Exits>())
+            .prop_map(|(kind, exits, exits_backtrack)| Panel { kind, exits, exits_backtrack })
+            .boxed()
+    }
+}
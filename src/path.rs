@@ -0,0 +1,192 @@
+//! Finding paths between panels.
+//!
+//! Paths follow the exits of each panel, like a player moving on the field,
+//! so two panels next to each other are only connected if an exit leads
+//! from one to the other. Exits that lead off the field or into empty panels
+//! are ignored.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, Pos, PanelKind::*};
+//! use citrus_common::path;
+//!
+//! let field = field![
+//!     [Home + EAST, Draw + SOUTH],
+//!     [Empty, Bonus],
+//! ];
+//!
+//! let path = path::shortest_path(&field, Pos::new(0, 0), Pos::new(1, 1));
+//! assert_eq!(path, Some(vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)]));
+//!
+//! // exits only lead one way
+//! assert_eq!(path::shortest_path(&field, Pos::new(1, 1), Pos::new(0, 0)), None);
+//! ```
+
+use crate::{Field, PanelKind, Pos};
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::collections::hash_map::Entry;
+
+/// Finds a shortest path from one panel to another, following exits.
+///
+/// The path includes both ends, so a path from a panel to itself is just
+/// that panel. Returns `None` if either end is out of bounds, or if `to`
+/// cannot be reached from `from`.
+pub fn shortest_path(field: &Field, from: Pos, to: Pos) -> Option<Vec<Pos>> {
+    if !field.contains(from) || !field.contains(to) {
+        return None;
+    }
+
+    // the panel each reached panel was first reached from
+    let mut came_from = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    came_from.insert(from, from);
+    queue.push_back(from);
+
+    while let Some(pos) = queue.pop_front() {
+        if pos == to {
+            return Some(walk_back(&came_from, from, to));
+        }
+
+        for next in successors(field, pos) {
+            if let Entry::Vacant(entry) = came_from.entry(next) {
+                entry.insert(pos);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Finds how many moves it takes to reach each panel from a panel, following
+/// exits.
+///
+/// Panels that cannot be reached are left out. Returns nothing if `from` is
+/// out of bounds.
+pub fn distances(field: &Field, from: Pos) -> HashMap<Pos, usize> {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    if field.contains(from) {
+        distances.insert(from, 0);
+        queue.push_back(from);
+    }
+
+    while let Some(pos) = queue.pop_front() {
+        let distance = distances[&pos] + 1;
+
+        for next in successors(field, pos) {
+            if let Entry::Vacant(entry) = distances.entry(next) {
+                entry.insert(distance);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    distances
+}
+
+/// Finds a cheapest path from one panel to another, following exits, where
+/// `cost` gives the cost of moving onto a panel of each kind.
+///
+/// The panel the path starts on is free. Returns the path, including both
+/// ends, and its total cost, or `None` if either end is out of bounds or `to`
+/// cannot be reached from `from`.
+///
+/// This is an A* search, guided by the distance to `to` and the cheapest
+/// kind of panel on the field, so it never does worse than a plain search
+/// of every panel.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, Pos, PanelKind::*};
+/// use citrus_common::path;
+///
+/// let field = field![
+///     [Home + EAST + SOUTH, Encounter + EAST, Draw],
+///     [Bonus + EAST, Bonus + EAST, Bonus + NORTH],
+/// ];
+///
+/// // avoid encounters
+/// let cost = |kind| if kind == Encounter { 10 } else { 1 };
+/// let (path, total) = path::cheapest_path(&field, Pos::new(0, 0), Pos::new(2, 0), cost).unwrap();
+///
+/// assert_eq!(path.len(), 5);
+/// assert_eq!(total, 4);
+/// ```
+pub fn cheapest_path<F>(field: &Field, from: Pos, to: Pos, cost: F) -> Option<(Vec<Pos>, u64)>
+where F: Fn(PanelKind) -> u32 {
+    if !field.contains(from) || !field.contains(to) {
+        return None;
+    }
+
+    // no step can cost less than this, so it keeps the estimates in check
+    let cheapest = field.panels()
+        .filter(|(_, _, panel)| panel.kind != PanelKind::Empty)
+        .map(|(_, _, panel)| cost(panel.kind) as u64)
+        .min()
+        .unwrap_or(0);
+    let estimate = |pos: Pos| pos.distance(to) as u64 * cheapest;
+
+    let mut came_from = HashMap::new();
+    let mut costs = HashMap::new();
+    let mut queue = BinaryHeap::new();
+
+    came_from.insert(from, from);
+    costs.insert(from, 0u64);
+    queue.push(Reverse((estimate(from), 0u64, (from.y, from.x))));
+
+    while let Some(Reverse((_, spent, (y, x)))) = queue.pop() {
+        let pos = Pos::new(x, y);
+
+        if pos == to {
+            return Some((walk_back(&came_from, from, to), spent));
+        }
+
+        // skip panels that were since reached more cheaply
+        if costs.get(&pos).is_some_and(|best| *best < spent) {
+            continue;
+        }
+
+        for next in successors(field, pos) {
+            let total = spent + cost(field[next].kind) as u64;
+
+            if costs.get(&next).is_none_or(|best| total < *best) {
+                costs.insert(next, total);
+                came_from.insert(next, pos);
+                queue.push(Reverse((total + estimate(next), total, (next.y, next.x))));
+            }
+        }
+    }
+
+    None
+}
+
+/// Gets an iterator over the panels the exits of a panel lead to.
+///
+/// # Panics
+/// Will panic if the position is out of bounds.
+pub fn successors(field: &Field, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+    let exits = field[pos].exits;
+
+    exits.directions()
+        .filter_map(move |dir| pos.step(dir))
+        .filter(move |to| field.contains(*to) && field[*to].kind != PanelKind::Empty)
+}
+
+/// Rebuilds a path from the panel each panel was reached from.
+fn walk_back(came_from: &HashMap<Pos, Pos>, from: Pos, to: Pos) -> Vec<Pos> {
+    let mut path = vec![to];
+    let mut pos = to;
+
+    while pos != from {
+        pos = came_from[&pos];
+        path.push(pos);
+    }
+
+    path.reverse();
+    path
+}
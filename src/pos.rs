@@ -0,0 +1,194 @@
+//! Positions and rectangles on a field.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{Field, Panel, PanelKind::*, Pos};
+//!
+//! let field = Field::new_slice(&[
+//!     &[Panel::new(Draw), Panel::new(Encounter)],
+//!     &[Panel::new(Bonus), Panel::new(Drop)],
+//! ]);
+//!
+//! let pos = Pos::new(0, 1);
+//! assert_eq!(field[pos].kind, Bonus);
+//!
+//! let east = pos.offset(1, 0).unwrap();
+//! assert_eq!(field[east].kind, Drop);
+//! assert_eq!(east.to_string(), "(1, 1)");
+//! ```
+
+use crate::panel::Direction;
+
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// A position on a field, where `x` grows to the east and `y` grows to the
+/// south.
+///
+/// Positions convert to and from `(x, y)` tuples, so either can be used
+/// where a `Pos` is expected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Pos {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Pos {
+    /// The top-left corner of a field.
+    pub const ORIGIN: Pos = Pos::new(0, 0);
+
+    /// Creates a new position.
+    pub const fn new(x: usize, y: usize) -> Pos {
+        Pos { x, y }
+    }
+
+    /// Offsets a position by a certain vector, returning `None` if either
+    /// coordinate would be negative.
+    ///
+    /// This does not know about any field, so the new position may still be
+    /// out of bounds.
+    pub fn offset(self, x_offset: i64, y_offset: i64) -> Option<Pos> {
+        let x = (self.x as i64).checked_add(x_offset)?;
+        let y = (self.y as i64).checked_add(y_offset)?;
+
+        if x >= 0 && y >= 0 {
+            Some(Pos::new(x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Gets the position a step away in a direction, or `None` if it would
+    /// be west of or north of the origin.
+    pub fn step(self, dir: Direction) -> Option<Pos> {
+        let (dx, dy) = dir.delta();
+        self.offset(dx, dy)
+    }
+
+    /// Gets the number of orthogonal steps between two positions.
+    pub fn distance(self, other: Pos) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
+
+impl From<(usize, usize)> for Pos {
+    fn from((x, y): (usize, usize)) -> Pos {
+        Pos { x, y }
+    }
+}
+
+impl From<Pos> for (usize, usize) {
+    fn from(pos: Pos) -> (usize, usize) {
+        (pos.x, pos.y)
+    }
+}
+
+impl Add for Pos {
+    type Output = Pos;
+
+    fn add(self, rhs: Pos) -> Pos {
+        Pos::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Pos {
+    fn add_assign(&mut self, rhs: Pos) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Pos {
+    type Output = Pos;
+
+    /// # Panics
+    /// Will panic if either coordinate would be negative, like with `usize`.
+    fn sub(self, rhs: Pos) -> Pos {
+        Pos::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Pos {
+    fn sub_assign(&mut self, rhs: Pos) {
+        *self = *self - rhs;
+    }
+}
+
+impl Display for Pos {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// A rectangle of positions, with its top-left corner at `(x, y)`.
+///
+/// # Examples
+/// ```
+/// use citrus_common::pos::{Pos, Rect};
+///
+/// let rect = Rect::new(1, 1, 2, 3);
+///
+/// assert!(rect.contains(Pos::new(2, 3)));
+/// assert!(!rect.contains(Pos::new(3, 1)));
+/// assert_eq!(rect.positions().count(), 6);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    /// Creates a new rectangle.
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    /// Creates a rectangle covering a whole field of the given size.
+    pub const fn of_size(width: usize, height: usize) -> Rect {
+        Rect::new(0, 0, width, height)
+    }
+
+    /// Gets the top-left corner of the rectangle.
+    pub const fn origin(&self) -> Pos {
+        Pos::new(self.x, self.y)
+    }
+
+    /// Checks if the rectangle has no positions.
+    pub const fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Checks if a position is in the rectangle.
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.x >= self.x && pos.y >= self.y
+            && pos.x - self.x < self.width && pos.y - self.y < self.height
+    }
+
+    /// Checks if the rectangle fits in a field of the given size.
+    pub fn fits(&self, width: usize, height: usize) -> bool {
+        self.x.checked_add(self.width).filter(|end| *end <= width).is_some()
+            && self.y.checked_add(self.height).filter(|end| *end <= height).is_some()
+    }
+
+    /// Gets an iterator over all of the positions in the rectangle,
+    /// row-major.
+    pub fn positions(&self) -> impl DoubleEndedIterator<Item = Pos> + ExactSizeIterator {
+        let Rect { x, y, width, .. } = *self;
+        let len = if self.is_empty() { 0 } else { width * self.height };
+
+        (0..len).map(move |idx| Pos::new(x + idx % width, y + idx / width))
+    }
+}
+
+impl Display for Rect {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}x{} at {}", self.width, self.height, self.origin())
+    }
+}
@@ -0,0 +1,335 @@
+//! Drawing fields as plain text.
+//!
+//! Each panel is drawn as its kind's
+//! [abbreviation](crate::PanelKind::abbreviation), like `[]` for Neutral
+//! panels and `@@` for Homes. Between two panels, `<` and `>` mark
+//! exits leading west and east, and `/\` and `\/` mark exits leading north
+//! and south.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, PanelKind::*};
+//! use citrus_common::render::ascii::{self, RenderOptions};
+//!
+//! let field = field![[Home + EAST, Draw + SOUTH], [Empty, Bonus]];
+//!
+//! let options = RenderOptions { borders: true, ..RenderOptions::default() };
+//! let text = ascii::render(&field, &options);
+//!
+//! assert_eq!(text, "\n+-----+\n|@@>da|\n|   \\/|\n|   bs|\n+-----+");
+//! assert_eq!(ascii::parse(&text).unwrap(), field);
+//! ```
+
+use crate::{Field, Exits, PanelKind};
+use crate::pos::Pos;
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// How [`render`] draws a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Whether and how to draw backtrack exits.
+    pub backtrack: Backtrack,
+    /// How many characters wide each panel is, at least two. Abbreviations
+    /// are padded with spaces on the right.
+    pub cell_width: usize,
+    /// Whether to list the abbreviations of the kinds on the field after it.
+    pub legend: bool,
+    /// Whether to draw a box around the field. Spaces at the ends of lines
+    /// are trimmed before the box is drawn.
+    pub borders: bool,
+    /// Whether to label each column with its `x` above the field, and each
+    /// row with its `y` to the left of it.
+    pub coordinates: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            backtrack: Backtrack::Hide,
+            cell_width: 2,
+            legend: false,
+            borders: false,
+            coordinates: false,
+        }
+    }
+}
+
+/// How [`render`] draws backtrack exits.
+///
+/// Backtrack exits are drawn as `(` and `)` leading west and east, and `^^`
+/// and `vv` leading north and south.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Backtrack {
+    /// Backtrack exits are not drawn.
+    Hide,
+    /// Backtrack exits are drawn where there is no exit.
+    Show,
+    /// Only backtrack exits are drawn, and exits are not.
+    Only,
+}
+
+/// Draws a field as text.
+///
+/// Each row of panels is followed by a line of the exits leading north and
+/// south between it and the next row, and the text starts with a newline, so
+/// the field lines up when printed after other text.
+pub fn render(field: &Field, options: &RenderOptions) -> String {
+    let width = options.cell_width.max(2);
+    let pad = |text: &str| format!("{:width$}", text, width = width);
+
+    let mut lines = Vec::new();
+
+    for y in 0..field.height() {
+        let mut panels = String::new();
+        let mut below = String::new();
+
+        for x in 0..field.width() {
+            let pos = Pos::new(x, y);
+
+            panels += &pad(field[pos].kind.abbreviation());
+
+            if x + 1 < field.width() {
+                let glyph = connector(field, pos, Pos::new(x + 1, y), options, ["<", ">", "(", ")", " "]);
+                panels += glyph;
+            }
+
+            if y + 1 < field.height() {
+                let glyph = connector(field, pos, Pos::new(x, y + 1), options, ["/\\", "\\/", "^^", "vv", "  "]);
+                below += &pad(glyph);
+            }
+
+            below += " ";
+        }
+
+        lines.push(panels);
+        lines.push(below);
+    }
+
+    if options.borders {
+        // the last row has nothing below it
+        lines.pop();
+
+        for line in lines.iter_mut() {
+            line.truncate(line.trim_end().len());
+        }
+
+        let inner = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        let edge = format!("+{}+", "-".repeat(inner));
+
+        lines = std::iter::once(edge.clone())
+            .chain(lines.iter().map(|line| format!("|{:inner$}|", line, inner = inner)))
+            .chain(std::iter::once(edge))
+            .collect();
+    }
+
+    if options.coordinates {
+        let margin = field.height().saturating_sub(1).to_string().len();
+        // the first row of panels, after the top of the box
+        let first = if options.borders { 1 } else { 0 };
+
+        for (idx, line) in lines.iter_mut().enumerate() {
+            let label = match idx.checked_sub(first) {
+                Some(row) if row % 2 == 0 && row / 2 < field.height() => (row / 2).to_string(),
+                _ => String::new(),
+            };
+
+            *line = format!("{:>margin$} {}", label, line, margin = margin);
+        }
+
+        let mut header = " ".repeat(margin + 1 + first);
+        for x in 0..field.width() {
+            header += &format!("{:width$} ", x, width = width);
+        }
+
+        lines.insert(0, header.trim_end().to_string());
+    }
+
+    if options.legend {
+        let mut kinds: Vec<PanelKind> = Vec::new();
+
+        for (_, _, panel) in field.panels() {
+            if panel.kind != PanelKind::Empty && !kinds.contains(&panel.kind) {
+                kinds.push(panel.kind);
+            }
+        }
+
+        kinds.sort_by_key(|kind| u8::from(*kind));
+        lines.push(String::new());
+        lines.extend(kinds.iter().map(|kind| format!("{} {:?}", kind.abbreviation(), kind)));
+    }
+
+    let mut out = String::new();
+
+    for line in lines {
+        out.push('\n');
+        out += &line;
+    }
+
+    out
+}
+
+/// Picks the glyph drawn between two panels, from the glyphs for an exit
+/// leading back from `to`, an exit leading on from `from`, the same for
+/// backtrack exits, and no exit.
+fn connector(field: &Field, from: Pos, to: Pos, options: &RenderOptions, glyphs: [&'static str; 5]) -> &'static str {
+    let (back, on) = if to.x > from.x {
+        (Exits::WEST, Exits::EAST)
+    } else {
+        (Exits::NORTH, Exits::SOUTH)
+    };
+
+    let exits = options.backtrack != Backtrack::Only;
+    let backtrack = options.backtrack != Backtrack::Hide;
+
+    if exits && field[to].exits.has(back) {
+        glyphs[0]
+    } else if exits && field[from].exits.has(on) {
+        glyphs[1]
+    } else if backtrack && field[to].exits_backtrack.has(back) {
+        glyphs[2]
+    } else if backtrack && field[from].exits_backtrack.has(on) {
+        glyphs[3]
+    } else {
+        glyphs[4]
+    }
+}
+
+/// An error that indicates text [`parse`] could not understand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidGlyph {
+    /// The zero-indexed line of the text.
+    pub line: usize,
+    /// The zero-indexed column of the text, in characters.
+    pub column: usize,
+    /// The text that could not be understood.
+    pub value: String,
+}
+
+impl Display for InvalidGlyph {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f, "invalid glyph {:?} at line {}, column {}",
+            self.value, self.line, self.column,
+        )
+    }
+}
+
+impl std::error::Error for InvalidGlyph { }
+
+/// Reads a field back from text drawn by [`render`].
+///
+/// The text must be drawn two characters wide, without a legend or
+/// coordinates, but it may have borders, and may start and end with blank
+/// lines. Spaces missing from the ends of lines are filled in, so text can be
+/// written by hand, but without borders, rows of only Empty panels at the
+/// top or bottom are taken for blank lines. Backtrack exits are read from
+/// their glyphs, but are not built otherwise.
+///
+/// `render` draws an exit leading one way between two panels over an exit
+/// leading the other way, so only one of the two can be read back. Kinds this
+/// crate does not know are drawn as `??`, so those cannot be read back
+/// either.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::render::ascii;
+///
+/// let field = ascii::parse("
+/// @@>da
+///    \\/
+///    bs").unwrap();
+///
+/// assert_eq!(field, field![[Home + EAST, Draw + SOUTH], [Empty, Bonus]]);
+/// ```
+pub fn parse(text: &str) -> Result<Field, InvalidGlyph> {
+    let blank = |line: &(usize, Vec<char>)| line.1.iter().all(|c| c.is_whitespace());
+
+    let mut lines: Vec<(usize, Vec<char>)> = text.lines()
+        .map(|line| line.chars().collect())
+        .enumerate()
+        .collect();
+
+    while lines.last().is_some_and(blank) {
+        lines.pop();
+    }
+
+    let start = lines.iter().position(|line| !blank(line)).unwrap_or(lines.len());
+    lines.drain(..start);
+
+    // the box drawn around the field, if any
+    let mut margin = 0;
+
+    if lines.first().is_some_and(|(_, line)| line.first() == Some(&'+')) {
+        lines.remove(0);
+        lines.pop();
+        margin = 1;
+
+        for (_, line) in lines.iter_mut() {
+            line.drain(..1.min(line.len()));
+
+            if line.last() == Some(&'|') {
+                line.pop();
+            }
+        }
+    }
+
+    // a line of exits at the end leads to a row of only Empty panels
+    let height = if lines.is_empty() { 0 } else { lines.len() / 2 + 1 };
+    let width = lines.iter().map(|(_, line)| (line.len() + 1) / 3).max().unwrap_or(0);
+
+    // gets the glyph at a place in the text, with the error if it is invalid
+    let glyph = |row: usize, column: usize, len: usize| {
+        let (line, chars) = lines.get(row).map_or((0, &[][..]), |(line, chars)| (*line, &chars[..]));
+        let value: String = (column..column + len)
+            .map(|idx| chars.get(idx).copied().unwrap_or(' '))
+            .collect();
+        let error = InvalidGlyph { line, column: column + margin, value: value.clone() };
+
+        (value, error)
+    };
+
+    let mut field = Field::builder(width, height).build();
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = Pos::new(x, y);
+
+            let (value, error) = glyph(y * 2, x * 3, 2);
+            field[pos].kind = PanelKind::from_abbreviation(&value).ok_or(error)?;
+
+            if x + 1 < width {
+                let (value, error) = glyph(y * 2, x * 3 + 2, 1);
+                connect(&mut field, pos, Pos::new(x + 1, y), &value, ["<", ">", "(", ")", " "]).ok_or(error)?;
+            }
+
+            if y + 1 < height {
+                let (value, error) = glyph(y * 2 + 1, x * 3, 2);
+                connect(&mut field, pos, Pos::new(x, y + 1), &value, ["/\\", "\\/", "^^", "vv", "  "]).ok_or(error)?;
+            }
+        }
+    }
+
+    Ok(field)
+}
+
+/// Sets the exit between two panels that a glyph from [`connector`] stands
+/// for, or returns `None` if it is not one of the glyphs.
+fn connect(field: &mut Field, from: Pos, to: Pos, glyph: &str, glyphs: [&'static str; 5]) -> Option<()> {
+    let (back, on) = if to.x > from.x {
+        (Exits::WEST, Exits::EAST)
+    } else {
+        (Exits::NORTH, Exits::SOUTH)
+    };
+
+    match glyphs.iter().position(|other| *other == glyph)? {
+        0 => field[to].exits |= back,
+        1 => field[from].exits |= on,
+        2 => field[to].exits_backtrack |= back,
+        3 => field[from].exits_backtrack |= on,
+        _ => (),
+    }
+
+    Some(())
+}
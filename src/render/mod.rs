@@ -0,0 +1,17 @@
+//! Drawing fields for people to look at.
+//!
+//! Each renderer has its own options; their defaults draw something
+//! reasonable. [`Field`](crate::Field)'s `Display` impl draws with
+//! [`ascii`] and its default options. To draw a field as an image, see
+//! [`svg`], or with the `image` feature, `png`.
+
+pub mod ascii;
+pub mod unicode;
+
+mod svg;
+#[cfg(feature = "image")]
+mod png;
+
+pub use self::svg::{color, svg, SvgOptions};
+#[cfg(feature = "image")]
+pub use self::png::{png, raster, Atlas, PngOptions};
@@ -0,0 +1,186 @@
+use crate::{Direction, Field, PanelKind};
+
+use image::{ImageError, ImageFormat, Rgba, RgbaImage};
+use image::imageops::{self, FilterType};
+
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// The sprites [`png`] draws panels with, one square tile per kind.
+///
+/// Kinds without a sprite are drawn as placeholder tiles: squares filled
+/// with the kind's [`color`](super::color).
+#[derive(Clone, Debug)]
+pub struct Atlas {
+    tile_size: u32,
+    sprites: HashMap<PanelKind, RgbaImage>,
+}
+
+impl Atlas {
+    /// Creates an atlas with no sprites, so every kind is drawn as a
+    /// placeholder tile `tile_size` pixels across.
+    pub fn new(tile_size: u32) -> Atlas {
+        Atlas {
+            tile_size: tile_size.max(1),
+            sprites: HashMap::new(),
+        }
+    }
+
+    /// Cuts sprites out of a sheet of tiles, read left to right and then top
+    /// to bottom, one for each kind in `kinds`.
+    ///
+    /// Kinds past the end of the sheet are left as placeholders.
+    pub fn from_sheet(sheet: &RgbaImage, tile_size: u32, kinds: &[PanelKind]) -> Atlas {
+        let mut atlas = Atlas::new(tile_size);
+        let columns = sheet.width() / atlas.tile_size;
+        let rows = sheet.height() / atlas.tile_size;
+
+        for (idx, kind) in kinds.iter().enumerate().take((columns * rows) as usize) {
+            let (x, y) = (idx as u32 % columns, idx as u32 / columns);
+            let size = atlas.tile_size;
+
+            atlas.sprites.insert(*kind, imageops::crop_imm(sheet, x * size, y * size, size, size).to_image());
+        }
+
+        atlas
+    }
+
+    /// Sets the sprite a kind is drawn with. Sprites that are not the size of
+    /// a tile are stretched to fit.
+    pub fn insert(&mut self, kind: PanelKind, sprite: RgbaImage) {
+        let size = self.tile_size;
+
+        let sprite = if sprite.dimensions() == (size, size) {
+            sprite
+        } else {
+            imageops::resize(&sprite, size, size, FilterType::Nearest)
+        };
+
+        self.sprites.insert(kind, sprite);
+    }
+
+    /// How many pixels across each tile is.
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    /// Gets the tile a kind is drawn with, its sprite or a placeholder.
+    pub fn sprite(&self, kind: PanelKind) -> RgbaImage {
+        match self.sprites.get(&kind) {
+            Some(sprite) => sprite.clone(),
+            None => placeholder(kind, self.tile_size),
+        }
+    }
+}
+
+impl Default for Atlas {
+    fn default() -> Atlas {
+        Atlas::new(16)
+    }
+}
+
+/// How [`png`] draws a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PngOptions {
+    /// How many pixels each pixel of a tile is scaled up to.
+    pub scale: u32,
+    /// Whether to mark exits, as dark triangles at the edges of tiles.
+    pub exits: bool,
+    /// Whether to mark backtrack exits where there is no exit, as light
+    /// triangles.
+    pub backtrack: bool,
+}
+
+impl Default for PngOptions {
+    fn default() -> PngOptions {
+        PngOptions {
+            scale: 2,
+            exits: true,
+            backtrack: false,
+        }
+    }
+}
+
+/// Draws a field with an atlas, as an image.
+///
+/// Each panel is drawn as its kind's tile, and Empty panels are left
+/// transparent unless the atlas has a sprite for them.
+pub fn raster(field: &Field, atlas: &Atlas, options: &PngOptions) -> RgbaImage {
+    let size = atlas.tile_size();
+    let mut image = RgbaImage::new(field.width() as u32 * size, field.height() as u32 * size);
+
+    for pos in field.positions() {
+        let panel = &field[pos];
+        let mut tile = atlas.sprite(panel.kind);
+
+        for dir in Direction::ALL.iter().copied() {
+            if options.exits && panel.exits.has(dir.to_exits()) {
+                mark(&mut tile, dir, Rgba([0x20, 0x20, 0x20, 0xFF]));
+            } else if options.backtrack && panel.exits_backtrack.has(dir.to_exits()) {
+                mark(&mut tile, dir, Rgba([0xA0, 0xA0, 0xA0, 0xFF]));
+            }
+        }
+
+        imageops::overlay(&mut image, &tile, pos.x as i64 * size as i64, pos.y as i64 * size as i64);
+    }
+
+    match options.scale.max(1) {
+        1 => image,
+        scale => imageops::resize(&image, image.width() * scale, image.height() * scale, FilterType::Nearest),
+    }
+}
+
+/// Draws a field with an atlas, as PNG-encoded bytes.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::render::{self, Atlas, PngOptions};
+///
+/// let bytes = render::png(&field![[Home + EAST, Draw]], &Atlas::new(8), &PngOptions::default()).unwrap();
+/// let image = image::load_from_memory(&bytes).unwrap();
+///
+/// assert_eq!((image.width(), image.height()), (32, 16));
+/// ```
+pub fn png(field: &Field, atlas: &Atlas, options: &PngOptions) -> Result<Vec<u8>, ImageError> {
+    let mut bytes = Vec::new();
+    raster(field, atlas, options).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+
+    Ok(bytes)
+}
+
+/// Makes the placeholder tile for a kind.
+fn placeholder(kind: PanelKind, size: u32) -> RgbaImage {
+    if kind == PanelKind::Empty {
+        return RgbaImage::new(size, size);
+    }
+
+    let fill = super::color(kind).trim_start_matches('#');
+    let channel = |idx: usize| u8::from_str_radix(&fill[idx..idx + 2], 16).unwrap_or(0);
+    let fill = Rgba([channel(0), channel(2), channel(4), 0xFF]);
+
+    RgbaImage::from_fn(size, size, |x, y| {
+        if x == 0 || y == 0 || x + 1 == size || y + 1 == size {
+            Rgba([0x40, 0x40, 0x40, 0xFF])
+        } else {
+            fill
+        }
+    })
+}
+
+/// Marks an exit on a tile, as a triangle pointing out of the edge it leads
+/// through.
+fn mark(tile: &mut RgbaImage, dir: Direction, color: Rgba<u8>) {
+    let (dx, dy) = dir.delta();
+    let size = tile.width().min(tile.height()) as i64;
+    let mid = size / 2;
+
+    for depth in 0..(size / 4).max(1) {
+        let along = if dx + dy > 0 { size - 1 - depth } else { depth };
+
+        for side in -depth..=depth {
+            let (x, y) = if dx != 0 { (along, mid + side) } else { (mid + side, along) };
+            tile.put_pixel(x as u32, y as u32, color);
+        }
+    }
+}
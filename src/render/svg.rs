@@ -0,0 +1,164 @@
+use crate::{Direction, Field, PanelKind, Pos};
+
+use std::fmt::Write as _;
+
+/// How [`svg`] draws a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// How many pixels wide and tall each panel's cell is.
+    pub cell_size: u32,
+    /// Whether to write the abbreviation of each panel's kind on it.
+    pub labels: bool,
+    /// Whether to draw backtrack exits, as dashed arrows.
+    pub backtrack: bool,
+    /// Whether to draw the lines between cells.
+    pub grid: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions {
+            cell_size: 48,
+            labels: true,
+            backtrack: true,
+            grid: true,
+        }
+    }
+}
+
+/// Gets the color a kind is filled with.
+pub fn color(kind: PanelKind) -> &'static str {
+    use PanelKind::*;
+
+    match kind.base_kind() {
+        Empty => "none",
+        Neutral => "#c8c8c8",
+        Home => "#ffffff",
+        Encounter => "#e05050",
+        Draw => "#50b050",
+        Bonus => "#f0d040",
+        Drop => "#5080e0",
+        Warp | WarpMove => "#a060d0",
+        Move => "#50c0c0",
+        Deck => "#806040",
+        Ice => "#b0e0f0",
+        Heal => "#f090c0",
+        Damage => "#a03030",
+        _ => "#808080",
+    }
+}
+
+/// Draws a field as an SVG image.
+///
+/// Each non-empty panel is a rounded square filled with its kind's
+/// [`color`], and each exit is an arrow crossing into the next panel, drawn
+/// to the right of the way it leads so exits leading both ways do not
+/// overlap. Backtrack exits are dashed, and drawn to the left.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::render::{self, SvgOptions};
+///
+/// let svg = render::svg(&field![[Home + EAST, Draw]], &SvgOptions::default());
+///
+/// assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"96\" height=\"48\""));
+/// assert_eq!(svg.matches("class=\"exit\"").count(), 1);
+/// ```
+pub fn svg(field: &Field, options: &SvgOptions) -> String {
+    let cell = options.cell_size.max(1) as f64;
+    let (width, height) = (field.width() as f64 * cell, field.height() as f64 * cell);
+
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = width, h = height,
+    );
+
+    out += "  <defs>\n";
+    for (id, fill) in [("arrow", "#202020"), ("arrow-backtrack", "#808080")].iter() {
+        let _ = writeln!(
+            out,
+            "    <marker id=\"{}\" viewBox=\"0 0 10 10\" refX=\"8\" refY=\"5\" markerWidth=\"5\" markerHeight=\"5\" orient=\"auto\">\
+             <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{}\"/></marker>",
+            id, fill,
+        );
+    }
+    out += "  </defs>\n";
+
+    if options.grid {
+        for x in 0..=field.width() {
+            let x = x as f64 * cell;
+            let _ = writeln!(out, "  <line class=\"grid\" x1=\"{x}\" y1=\"0\" x2=\"{x}\" y2=\"{}\" stroke=\"#e0e0e0\"/>", height, x = x);
+        }
+
+        for y in 0..=field.height() {
+            let y = y as f64 * cell;
+            let _ = writeln!(out, "  <line class=\"grid\" x1=\"0\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#e0e0e0\"/>", width, y = y);
+        }
+    }
+
+    let panels: Vec<Pos> = field.positions()
+        .filter(|pos| field[*pos].kind != PanelKind::Empty)
+        .collect();
+
+    for pos in panels.iter() {
+        let kind = field[*pos].kind;
+        let (x, y) = (pos.x as f64 * cell, pos.y as f64 * cell);
+
+        let _ = writeln!(
+            out,
+            "  <rect class=\"panel\" data-kind=\"{:?}\" x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" rx=\"{}\" fill=\"{}\" stroke=\"#404040\"/>",
+            kind, px(x + cell * 0.1), px(y + cell * 0.1), px(cell * 0.1), color(kind), s = px(cell * 0.8),
+        );
+
+        if options.labels {
+            let _ = writeln!(
+                out,
+                "  <text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"monospace\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                px(x + cell / 2.0), px(y + cell / 2.0), px(cell * 0.3), kind.abbreviation().trim(),
+            );
+        }
+    }
+
+    for pos in panels.iter() {
+        let panel = &field[*pos];
+        let (cx, cy) = (pos.x as f64 * cell + cell / 2.0, pos.y as f64 * cell + cell / 2.0);
+
+        for dir in Direction::ALL.iter().copied() {
+            let (dx, dy) = dir.delta();
+            let (dx, dy) = (dx as f64, dy as f64);
+
+            // arrows sit to the right of the way they lead, backtrack to the left
+            let arrows = [(panel.exits, 1.0, "exit", "#202020", "arrow", ""),
+                (panel.exits_backtrack, -1.0, "backtrack", "#808080", "arrow-backtrack", " stroke-dasharray=\"4 3\"")];
+
+            for (exits, side, class, stroke, marker, dash) in arrows.iter() {
+                if !exits.has(dir.to_exits()) || (*class == "backtrack" && !options.backtrack) {
+                    continue;
+                }
+
+                let (ox, oy) = (-dy * side * cell * 0.12, dx * side * cell * 0.12);
+
+                let _ = writeln!(
+                    out,
+                    "  <line class=\"{}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{} marker-end=\"url(#{})\"/>",
+                    class,
+                    px(cx + dx * cell * 0.3 + ox), px(cy + dy * cell * 0.3 + oy),
+                    px(cx + dx * cell * 0.7 + ox), px(cy + dy * cell * 0.7 + oy),
+                    stroke, px(cell / 24.0), dash, marker,
+                );
+            }
+        }
+    }
+
+    out += "</svg>\n";
+    out
+}
+
+/// Rounds a length to hundredths of a pixel, so it prints tidily.
+fn px(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
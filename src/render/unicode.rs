@@ -0,0 +1,175 @@
+//! Drawing fields with Unicode box-drawing characters.
+//!
+//! Each non-empty panel is drawn as a box holding its kind's
+//! [abbreviation](crate::PanelKind::abbreviation), and each exit as an arrow
+//! in the wall between two boxes: `▶`, `◀`, `▲` and `▼` for exits leading one
+//! way, and `↔` and `↕` for exits leading both ways.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, PanelKind::*};
+//! use citrus_common::render::unicode::{self, UnicodeOptions};
+//!
+//! let field = field![[Home + EAST, Draw + SOUTH], [Empty, Bonus]];
+//! let text = unicode::render(&field, &UnicodeOptions::default());
+//!
+//! assert_eq!(text, "\
+//! ┌────┬────┐
+//! │ @@ ▶ da │
+//! └────┼─▼──┤
+//!      │ bs │
+//!      └────┘");
+//! ```
+
+use crate::{Field, Exits, PanelKind, Pos};
+
+/// How [`render`] draws a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnicodeOptions {
+    /// How many characters wide the inside of each box is, at least two.
+    pub cell_width: usize,
+    /// Whether to draw backtrack exits where there is no exit, as `▷`, `◁`,
+    /// `△` and `▽`, or `◇` where they lead both ways.
+    pub backtrack: bool,
+}
+
+impl Default for UnicodeOptions {
+    fn default() -> UnicodeOptions {
+        UnicodeOptions {
+            cell_width: 4,
+            backtrack: false,
+        }
+    }
+}
+
+/// Draws a field with box-drawing characters.
+///
+/// Walls are only drawn around non-empty panels, and spaces at the ends of
+/// lines are trimmed.
+pub fn render(field: &Field, options: &UnicodeOptions) -> String {
+    let width = options.cell_width.max(2);
+    let filled = |x: usize, y: usize| {
+        x < field.width() && y < field.height() && field[Pos::new(x, y)].kind != PanelKind::Empty
+    };
+
+    // whether there is a wall along the top or the left of a cell
+    let top = |x: usize, y: usize| filled(x, y) || (y > 0 && filled(x, y - 1));
+    let left = |x: usize, y: usize| filled(x, y) || (x > 0 && filled(x - 1, y));
+
+    let corner = |x: usize, y: usize| {
+        junction(
+            y > 0 && left(x, y - 1),
+            left(x, y),
+            x > 0 && top(x - 1, y),
+            top(x, y),
+        )
+    };
+
+    let mut lines = Vec::new();
+
+    for y in 0..=field.height() {
+        let mut walls = String::new();
+
+        for x in 0..field.width() {
+            walls.push(corner(x, y));
+
+            let arrow = if y > 0 && y < field.height() {
+                arrow(field, Pos::new(x, y - 1), Pos::new(x, y), options, ['↕', '▼', '▲', '◇', '▽', '△'])
+            } else {
+                None
+            };
+
+            let line = if top(x, y) { '─' } else { ' ' };
+            walls.extend((0..width).map(|idx| match arrow {
+                Some(arrow) if idx == (width - 1) / 2 => arrow,
+                _ => line,
+            }));
+        }
+
+        walls.push(corner(field.width(), y));
+        lines.push(walls);
+
+        if y == field.height() {
+            break;
+        }
+
+        let mut cells = String::new();
+
+        for x in 0..=field.width() {
+            let arrow = if x > 0 && x < field.width() {
+                arrow(field, Pos::new(x - 1, y), Pos::new(x, y), options, ['↔', '▶', '◀', '◇', '▷', '◁'])
+            } else {
+                None
+            };
+
+            cells.push(match arrow {
+                Some(arrow) => arrow,
+                None if left(x, y) => '│',
+                None => ' ',
+            });
+
+            if x < field.width() {
+                let label = if filled(x, y) { field[Pos::new(x, y)].kind.abbreviation() } else { "" };
+                cells += &format!("{:^width$}", label, width = width);
+            }
+        }
+
+        lines.push(cells);
+    }
+
+    lines.iter()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Picks the arrow drawn between two panels, where `to` is east or south of
+/// `from`, from the arrows for exits leading both ways, on from `from`, and
+/// back from `to`, then the same for backtrack exits.
+fn arrow(field: &Field, from: Pos, to: Pos, options: &UnicodeOptions, arrows: [char; 6]) -> Option<char> {
+    let (on, back) = if to.x > from.x {
+        (Exits::EAST, Exits::WEST)
+    } else {
+        (Exits::SOUTH, Exits::NORTH)
+    };
+
+    let pick = |on: bool, back: bool, offset: usize| match (on, back) {
+        (true, true) => Some(arrows[offset]),
+        (true, false) => Some(arrows[offset + 1]),
+        (false, true) => Some(arrows[offset + 2]),
+        (false, false) => None,
+    };
+
+    let (from, to) = (&field[from], &field[to]);
+
+    pick(from.exits.has(on), to.exits.has(back), 0).or_else(|| {
+        if options.backtrack {
+            pick(from.exits_backtrack.has(on), to.exits_backtrack.has(back), 3)
+        } else {
+            None
+        }
+    })
+}
+
+/// Gets the box-drawing character joining walls leading up, down, left and
+/// right from a corner.
+fn junction(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, false, false, false) => ' ',
+        (false, false, true, true) => '─',
+        (true, true, false, false) => '│',
+        (false, true, false, true) => '┌',
+        (false, true, true, false) => '┐',
+        (true, false, false, true) => '└',
+        (true, false, true, false) => '┘',
+        (true, true, false, true) => '├',
+        (true, true, true, false) => '┤',
+        (false, true, true, true) => '┬',
+        (true, false, true, true) => '┴',
+        (true, true, true, true) => '┼',
+        (true, false, false, false) => '╵',
+        (false, true, false, false) => '╷',
+        (false, false, true, false) => '╴',
+        (false, false, false, true) => '╶',
+    }
+}
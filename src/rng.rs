@@ -0,0 +1,87 @@
+//! A small seedable random number generator.
+//!
+//! Simulations and generators only need reproducible numbers, not good ones,
+//! so this saves depending on a whole crate for them.
+
+/// A SplitMix64 generator.
+#[derive(Clone, Debug)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Gets a number in `0.0..1.0`.
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Gets a number in `0..bound`.
+    ///
+    /// # Panics
+    /// Will panic if `bound` is zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "bound must be positive");
+
+        // the bias is far too small to matter for boards
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Picks an item from a slice, or `None` if it is empty.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len())])
+        }
+    }
+
+    /// Gets a number in `low..=high`.
+    ///
+    /// # Panics
+    /// Will panic if `low` is greater than `high`.
+    pub fn between(&mut self, low: usize, high: usize) -> usize {
+        assert!(low <= high, "low must not be greater than high");
+        low + self.below(high - low + 1)
+    }
+
+    /// Shuffles a slice.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            items.swap(idx, self.below(idx + 1));
+        }
+    }
+
+    /// Picks an item by weight, or `None` if every weight is zero.
+    pub fn weighted<'a, T>(&mut self, items: &'a [(T, u32)]) -> Option<&'a T> {
+        let total: u64 = items.iter().map(|(_, weight)| *weight as u64).sum();
+
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = self.next_u64() % total;
+
+        for (item, weight) in items {
+            if roll < *weight as u64 {
+                return Some(item);
+            }
+
+            roll -= *weight as u64;
+        }
+
+        None
+    }
+}
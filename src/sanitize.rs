@@ -0,0 +1,105 @@
+//! Repairing fields from old or hand-made files.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, PanelKind::*};
+//!
+//! // an exit off the field, and an exit out of an empty panel
+//! let mut field = field![[Home + EAST + NORTH, Draw + WEST, Empty + WEST]];
+//! let report = field.sanitize();
+//!
+//! assert_eq!(report.removed.len(), 2);
+//! assert!(field.sanitize().is_empty());
+//! ```
+
+use crate::{Field, PanelKind, Pos};
+use crate::draw::ExitChange;
+
+/// Kinds whose ids are not confirmed to be used by the game, and the kinds
+/// they are replaced with.
+const UNCONFIRMED: [(PanelKind, PanelKind); 2] = [
+    (PanelKind::WarpMove2x, PanelKind::WarpMove),
+    (PanelKind::Heal2x, PanelKind::Heal),
+];
+
+/// The kind [`PanelKind::Unknown`] panels are replaced with.
+const UNKNOWN_REPLACEMENT: PanelKind = PanelKind::Neutral;
+
+/// A panel whose kind was replaced by [`Field::sanitize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KindChange {
+    /// The position of the panel.
+    pub pos: Pos,
+    /// The kind the panel had.
+    pub old: PanelKind,
+    /// The kind the panel has now.
+    pub new: PanelKind,
+}
+
+/// What [`Field::sanitize`] repaired.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SanitizeReport {
+    /// Panels of kinds the game may not support, replaced with safe ones.
+    pub replaced: Vec<KindChange>,
+    /// Exits that led off the field, into empty panels, or out of empty
+    /// panels. Backtrack exits are not listed here, since they are rebuilt.
+    pub removed: Vec<ExitChange>,
+    /// Panels whose backtrack exits changed when they were rebuilt.
+    pub backtrack: Vec<Pos>,
+}
+
+impl SanitizeReport {
+    /// Checks if nothing needed repairing.
+    pub fn is_empty(&self) -> bool {
+        self.replaced.is_empty() && self.removed.is_empty() && self.backtrack.is_empty()
+    }
+}
+
+impl Field {
+    /// Repairs a field so the game can use it, returning what was repaired.
+    ///
+    /// This:
+    /// 1. Replaces panels whose kinds are not confirmed to be in the game,
+    ///    like `WarpMove2x` and `Heal2x`, with their 1x versions, and panels
+    ///    of [unknown](PanelKind::Unknown) kinds with `Neutral` panels.
+    /// 2. Removes exits that lead off the field or into empty panels, and
+    ///    exits on empty panels, like [`Field::normalize_exits`].
+    /// 3. Rebuilds every backtrack exit from the exits, like
+    ///    [`Field::build_backtrack`]. Backtrack exits set by hand that do not
+    ///    lead back along an exit are lost.
+    ///
+    /// Sanitizing a field again repairs nothing.
+    pub fn sanitize(&mut self) -> SanitizeReport {
+        let mut report = SanitizeReport::default();
+
+        for pos in self.positions() {
+            let old = self[pos].kind;
+            let new = match old {
+                PanelKind::Unknown(_) => Some(UNKNOWN_REPLACEMENT),
+                old => UNCONFIRMED.iter()
+                    .find(|(kind, _)| *kind == old)
+                    .map(|(_, new)| *new),
+            };
+
+            if let Some(new) = new {
+                self[pos].kind = new;
+                report.replaced.push(KindChange { pos, old, new });
+            }
+        }
+
+        let before = self.clone();
+
+        report.removed = self.normalize_exits(false).removed
+            .into_iter()
+            .filter(|change| !change.backtrack)
+            .collect();
+
+        self.build_backtrack();
+
+        report.backtrack = self.positions()
+            .filter(|pos| self[*pos].exits_backtrack != before[*pos].exits_backtrack)
+            .collect();
+
+        report
+    }
+}
@@ -0,0 +1,323 @@
+//! Simulating players moving around a field.
+//!
+//! Players move along exits, the same way [`path`](crate::path) follows them,
+//! one panel for each point rolled on a six-sided die. Where a panel has more
+//! than one exit, each is just as likely to be taken. Panels with no exits
+//! that lead anywhere end the move early.
+//!
+//! Panel effects, such as those of Warp and Move panels, are not simulated.
+//!
+//! [`Mover`] follows the exact rules instead, where players cannot turn
+//! around on the way.
+
+use crate::{Direction, Field, PanelKind, Pos};
+use crate::draw::direction_between;
+use crate::path::successors;
+use crate::rng::Rng;
+
+use std::collections::HashSet;
+
+/// The most times [`landing_probabilities`] refines its estimate.
+const MAX_ROUNDS: usize = 10_000;
+
+/// How close two estimates must be for [`landing_probabilities`] to stop.
+const TOLERANCE: f64 = 1e-12;
+
+/// Finds how likely a player is to end a move on each panel, in the long run.
+///
+/// Players start on the Homes, or anywhere if there are none. The
+/// probabilities are in row-major order, like [`Field::positions`], and add
+/// up to `1.0` unless the field has no non-empty panels.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::sim;
+///
+/// // a loop of two panels is landed on evenly
+/// let field = field![[Home + EAST, Draw + WEST]];
+/// let landing = sim::landing_probabilities(&field);
+///
+/// assert!((landing[0] - 0.5).abs() < 1e-9);
+/// assert!((landing[1] - 0.5).abs() < 1e-9);
+/// ```
+pub fn landing_probabilities(field: &Field) -> Vec<f64> {
+    let positions: Vec<_> = field.positions().collect();
+
+    // where one step from each panel can lead
+    let steps: Vec<Vec<usize>> = positions.iter()
+        .map(|pos| successors(field, *pos).map(|to| to.y * field.width() + to.x).collect())
+        .collect();
+
+    let kinds = |f: fn(PanelKind) -> bool| -> Vec<usize> {
+        (0..positions.len()).filter(|idx| f(field[positions[*idx]].kind)).collect()
+    };
+
+    let mut starts = kinds(|kind| kind == PanelKind::Home);
+
+    if starts.is_empty() {
+        starts = kinds(|kind| kind != PanelKind::Empty);
+    }
+
+    let mut landing = vec![0.0; positions.len()];
+
+    for idx in starts.iter() {
+        landing[*idx] = 1.0 / starts.len() as f64;
+    }
+
+    for _ in 0..MAX_ROUNDS {
+        let mut next = vec![0.0; positions.len()];
+        let mut walking = landing.clone();
+
+        for _ in 1..=6 {
+            walking = step(&steps, &walking);
+
+            for (next, walking) in next.iter_mut().zip(&walking) {
+                *next += walking / 6.0;
+            }
+        }
+
+        let change: f64 = next.iter().zip(&landing).map(|(a, b)| (a - b).abs()).sum();
+        landing = next;
+
+        if change < TOLERANCE {
+            break;
+        }
+    }
+
+    landing
+}
+
+/// Moves each panel's probability one step along its exits.
+fn step(steps: &[Vec<usize>], probabilities: &[f64]) -> Vec<f64> {
+    let mut next = vec![0.0; probabilities.len()];
+
+    for (idx, probability) in probabilities.iter().enumerate() {
+        if steps[idx].is_empty() {
+            next[idx] += probability;
+        } else {
+            for to in &steps[idx] {
+                next[*to] += probability / steps[idx].len() as f64;
+            }
+        }
+    }
+
+    next
+}
+
+/// How [`simulate`] runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimConfig {
+    /// How many players to simulate, one after another. Players start on each
+    /// Home in turn.
+    pub walks: usize,
+    /// How many turns each player moves for.
+    pub turns: usize,
+    /// The seed for the dice and the exits taken; the same seed gives the
+    /// same results.
+    pub seed: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> SimConfig {
+        SimConfig {
+            walks: 100,
+            turns: 100,
+            seed: 0,
+        }
+    }
+}
+
+/// The results of [`simulate`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimResult {
+    /// How many times each panel was moved onto, in row-major order, like
+    /// [`Field::positions`].
+    pub visits: Vec<usize>,
+    /// How many times each panel was the end of a move, in the same order.
+    pub landings: Vec<usize>,
+    /// How many turns each lap took, where a lap ends when a player moves
+    /// onto the Home it started from.
+    pub lap_times: Vec<usize>,
+}
+
+impl SimResult {
+    /// Gets the average number of turns a lap took, or `None` if no lap was
+    /// finished.
+    pub fn average_lap(&self) -> Option<f64> {
+        if self.lap_times.is_empty() {
+            None
+        } else {
+            Some(self.lap_times.iter().sum::<usize>() as f64 / self.lap_times.len() as f64)
+        }
+    }
+}
+
+/// Runs random walks from the Homes of a field, counting where players go.
+///
+/// Fields without Homes have no players, so nothing is counted.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, PanelKind::*};
+/// use citrus_common::sim::{self, SimConfig};
+///
+/// let field = field![
+///     [Home + EAST, Draw + SOUTH],
+///     [Bonus + NORTH, Drop + WEST],
+/// ];
+///
+/// let result = sim::simulate(&field, SimConfig { walks: 10, turns: 20, seed: 7 });
+///
+/// assert_eq!(result.landings.iter().sum::<usize>(), 10 * 20);
+/// assert!(result.average_lap().unwrap() < 4.0);
+/// ```
+pub fn simulate(field: &Field, config: SimConfig) -> SimResult {
+    let index = |pos: Pos| pos.y * field.width() + pos.x;
+
+    let mut rng = Rng::new(config.seed);
+    let mut result = SimResult {
+        visits: vec![0; field.width() * field.height()],
+        landings: vec![0; field.width() * field.height()],
+        lap_times: Vec::new(),
+    };
+
+    let homes: Vec<Pos> = field.positions()
+        .filter(|pos| field[*pos].kind == PanelKind::Home)
+        .collect();
+
+    for home in homes.iter().cycle().take(if homes.is_empty() { 0 } else { config.walks }) {
+        let mut pos = *home;
+        let mut lap_start = 0;
+
+        for turn in 1..=config.turns {
+            let roll = rng.below(6) + 1;
+            let mut home_reached = false;
+
+            for _ in 0..roll {
+                let next: Vec<Pos> = successors(field, pos).collect();
+
+                match rng.pick(&next) {
+                    Some(next) => pos = *next,
+                    None => break,
+                }
+
+                result.visits[index(pos)] += 1;
+                home_reached |= pos == *home;
+            }
+
+            result.landings[index(pos)] += 1;
+
+            if home_reached {
+                result.lap_times.push(turn - lap_start);
+                lap_start = turn;
+            }
+        }
+    }
+
+    result
+}
+
+/// Where a move ends, from [`Mover::destinations`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Destination {
+    /// The panel the move ends on.
+    pub pos: Pos,
+    /// The direction of the last step of the move, or `None` if the player
+    /// could not move at all.
+    pub heading: Option<Direction>,
+}
+
+/// Finds every panel a move could end on.
+///
+/// Players cannot turn around partway through a move: the exit leading back
+/// the way they came is only taken when there is no other way on. A panel
+/// with no exits that lead anywhere ends the move early.
+///
+/// # Examples
+/// ```
+/// use citrus_common::{field, Direction, Pos, PanelKind::*};
+/// use citrus_common::sim::Mover;
+///
+/// // a fork, where the exits also lead back
+/// let field = field![
+///     [Home + EAST, Draw + WEST + EAST + SOUTH, Bonus + WEST],
+///     [Empty, Drop + NORTH, Empty],
+/// ];
+///
+/// let mover = Mover::new(&field);
+/// let ends = mover.destinations(Pos::new(0, 0), Some(Direction::East), 2);
+///
+/// let mut ends: Vec<Pos> = ends.into_iter().map(|end| end.pos).collect();
+/// ends.sort_by_key(|pos| (pos.y, pos.x));
+///
+/// // the walk cannot turn back west on its way
+/// assert_eq!(ends, vec![Pos::new(2, 0), Pos::new(1, 1)]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Mover<'a> {
+    field: &'a Field,
+}
+
+impl<'a> Mover<'a> {
+    /// Creates a mover for a field.
+    pub fn new(field: &'a Field) -> Mover<'a> {
+        Mover { field }
+    }
+
+    /// Finds every way a move of `roll` panels from `from` could end, given
+    /// the direction the player last moved in, if any.
+    ///
+    /// Each destination is given once, in no particular order. Returns
+    /// nothing if `from` is out of bounds.
+    pub fn destinations(&self, from: Pos, heading: Option<Direction>, roll: usize) -> Vec<Destination> {
+        if !self.field.contains(from) {
+            return Vec::new();
+        }
+
+        let mut moving = HashSet::new();
+        moving.insert(Destination { pos: from, heading });
+
+        let mut ended = HashSet::new();
+
+        for _ in 0..roll {
+            let mut next = HashSet::new();
+
+            for at in moving {
+                let steps = self.steps(at);
+
+                if steps.is_empty() {
+                    ended.insert(at);
+                }
+
+                next.extend(steps);
+            }
+
+            moving = next;
+        }
+
+        ended.extend(moving);
+        ended.into_iter().collect()
+    }
+
+    /// Gets the steps a player can take from a panel, given the direction
+    /// they last moved in.
+    fn steps(&self, at: Destination) -> Vec<Destination> {
+        let back = at.heading.map(Direction::opposite);
+
+        let steps = |turn_around: bool| -> Vec<Destination> {
+            successors(self.field, at.pos)
+                .map(|pos| Destination { pos, heading: direction_between(at.pos, pos) })
+                .filter(|step| turn_around || step.heading != back)
+                .collect()
+        };
+
+        let forward = steps(false);
+
+        if forward.is_empty() {
+            steps(true)
+        } else {
+            forward
+        }
+    }
+}
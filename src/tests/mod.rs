@@ -1,4 +1,4 @@
-use crate::{Field, Panel, PanelKind};
+use crate::{DecodeError, Field, Panel, PanelKind};
 
 #[test]
 fn test_field() {
@@ -11,26 +11,2707 @@ fn test_field() {
     ]);
 }
 
+#[test]
+fn test_field_macro() {
+    use crate::Exits;
+    use PanelKind::*;
+
+    let field = Field::new_slice(&[
+        &[Panel::new(Home), Panel::new(Draw), Panel::new(Home)],
+        &[Panel::new(Bonus), Panel::new(Empty), Panel::new(Drop)],
+        &[Panel::new(Home), Panel::new(Encounter), Panel::new(Home)],
+    ]);
+
+    assert_eq!(field, crate::field![
+        [Home, Draw, Home],
+        [Bonus, Empty, Drop],
+        [Home, Encounter, Home],
+    ]);
+    assert_eq!(crate::field![], Field::new());
+
+    // exits follow the kind
+    let field = crate::field![[Home + EAST + SOUTH, Draw + WEST]];
+    assert!(field.get((0, 0)).exits == Exits::EAST | Exits::SOUTH);
+    assert!(field.get((1, 0)).exits == Exits::WEST);
+}
+
+#[test]
+fn test_field_iter_mut() {
+    use PanelKind::*;
+
+    let mut field = Field::new_vec(vec![Panel::EMPTY; 6], 3, 2);
+
+    // lay a track along the border
+    field.row_iter_mut(0).for_each(|panel| panel.kind = Neutral);
+    field.column_iter_mut(2).rev().for_each(|panel| panel.kind = Draw);
+
+    assert_eq!(field.column_iter_mut(1).len(), 2);
+    assert_eq!(
+        field.panels().map(|(_, _, panel)| panel.kind).collect::<Vec<_>>(),
+        [Neutral, Neutral, Draw, Empty, Empty, Draw],
+    );
+}
+
+#[test]
+fn test_field_try_get() {
+    use PanelKind::*;
+
+    let mut field = crate::field![
+        [Home, Draw],
+        [Bonus, Drop],
+        [Empty, Encounter],
+    ];
+
+    assert_eq!(field.try_get((1, 2)).unwrap().kind, Encounter);
+    assert!(field.try_get((2, 0)).is_none());
+    assert!(field.try_get((0, 3)).is_none());
+    assert!(field.try_get((usize::MAX, usize::MAX)).is_none());
+    assert!(Field::new().try_get((0, 0)).is_none());
+
+    field.try_get_mut((0, 2)).unwrap().kind = Warp;
+    assert!(field.try_get_mut((0, 3)).is_none());
+    assert_eq!(field.get((0, 2)).kind, Warp);
+}
+
+#[test]
+fn test_field_panels() {
+    use PanelKind::*;
+
+    let field = crate::field![
+        [Home, Draw, Bonus],
+        [Drop, Empty, Encounter],
+    ];
+
+    // panels line up with their positions
+    assert_eq!(field.panels().len(), 6);
+    assert!(field.panels().zip(field.iter()).all(|((x, y, panel), pos)| {
+        (x, y) == pos && *panel == *field.get((x, y))
+    }));
+
+    let (x, y, panel) = field.panels().next_back().unwrap();
+    assert_eq!((x, y, panel.kind), (2, 1, Encounter));
+    assert_eq!(Field::new().panels().len(), 0);
+}
+
+#[test]
+fn test_field_panels_mut() {
+    use crate::Exits;
+    use PanelKind::*;
+
+    let mut field = crate::field![
+        [Home + EAST, Draw + SOUTH],
+        [Empty, Bonus + WEST],
+    ];
+    field.build_backtrack();
+
+    // clear every exit in one pass
+    for (_, _, panel) in field.panels_mut() {
+        panel.exits = Exits::none();
+        panel.exits_backtrack = Exits::none();
+    }
+    assert_eq!(field, crate::field![[Home, Draw], [Empty, Bonus]]);
+
+    // positions match the panels they come with
+    for (x, y, panel) in field.panels_mut().rev() {
+        if (x, y) == (1, 0) {
+            panel.kind = Drop;
+        }
+    }
+    assert_eq!(field, crate::field![[Home, Drop], [Empty, Bonus]]);
+    assert_eq!(field.panels_mut().len(), 4);
+}
+
+#[test]
+fn test_panel_neighbors() {
+    use PanelKind::*;
+
+    let mut field = Field::new_vec(vec![Panel::EMPTY; 9], 3, 3);
+
+    let mut center = field.get_mut((1, 1));
+    center.north().unwrap().kind = Draw;
+    center.west().unwrap().kind = Bonus;
+    assert_eq!(center.neighbors().count(), 4);
+
+    let corner = field.get((0, 0));
+    assert!(corner.north().is_none() && corner.west().is_none());
+    assert_eq!(corner.east().unwrap().kind, Draw);
+    assert_eq!(corner.south().unwrap().kind, Bonus);
+    assert_eq!(corner.neighbors().count(), 2);
+}
+
+#[test]
+fn test_pos() {
+    use crate::{Direction, Pos};
+    use crate::pos::Rect;
+    use PanelKind::*;
+
+    let pos = Pos::new(2, 1);
+
+    // arithmetic and offsets
+    assert_eq!(pos + Pos::new(1, 3), Pos::new(3, 4));
+    assert_eq!(pos - Pos::new(2, 0), Pos::new(0, 1));
+    assert_eq!(pos.offset(-2, 1), Some(Pos::new(0, 2)));
+    assert_eq!(pos.offset(0, -2), None);
+    assert_eq!(pos.step(Direction::North), Some(Pos::new(2, 0)));
+    assert_eq!(Pos::ORIGIN.step(Direction::West), None);
+    assert_eq!(pos.distance(Pos::ORIGIN), 3);
+
+    // conversions keep x and y apart
+    assert_eq!(Pos::from((2, 1)), pos);
+    assert_eq!(<(usize, usize)>::from(pos), (2, 1));
+    assert_eq!(pos.to_string(), "(2, 1)");
+
+    let mut field = crate::field![
+        [Home, Draw, Bonus],
+        [Drop, Empty, Encounter],
+    ];
+
+    field[pos].kind = Warp;
+    assert_eq!(field.get((2, 1)).kind, Warp);
+    assert_eq!(field.get((2, 1)).pos(), pos);
+
+    // accessors take a pos or a tuple
+    field.get_mut(Pos::new(0, 1)).kind = Bonus;
+    assert_eq!(field.get(Pos::new(0, 1)).kind, Bonus);
+    assert_eq!(field.try_get(pos).map(|p| p.kind), Some(Warp));
+    assert!(field.try_get(Pos::new(3, 0)).is_none());
+    assert!(field.try_get_mut((0, 2)).is_none());
+    assert!(field.contains(pos) && !field.contains((1, 2)));
+    assert_eq!(field.positions().nth(5), Some(pos));
+
+    let rect = Rect::new(1, 0, 2, 2);
+    assert_eq!(rect.to_string(), "2x2 at (1, 0)");
+    assert!(rect.fits(3, 2) && !rect.fits(2, 2));
+    assert_eq!(rect.positions().last(), Some(pos));
+    assert_eq!(Rect::new(1, 1, 0, 5).positions().len(), 0);
+}
+
+#[test]
+fn test_field_builder() {
+    use crate::Exits;
+    use std::panic::catch_unwind;
+    use PanelKind::*;
+
+    let field = Field::builder(3, 2)
+        .fill(Neutral)
+        .fill_rect(1, 1, 2, 1, Draw)
+        .set(0, 0, Home)
+        .with_exits(0, 0, Exits::EAST)
+        .with_exits(1, 0, Exits::SOUTH)
+        .build();
+
+    assert_eq!(field, crate::field![
+        [Home + EAST, Neutral + SOUTH, Neutral],
+        [Neutral, Draw, Draw],
+    ]);
+    assert_eq!(Field::builder(2, 1).build(), crate::field![[Empty, Empty]]);
+
+    // mistakes panic where they are made
+    assert!(catch_unwind(|| Field::builder(2, 2).set(2, 0, Home)).is_err());
+    assert!(catch_unwind(|| Field::builder(2, 2).fill_rect(1, 1, 2, 1, Draw)).is_err());
+    assert!(catch_unwind(|| Field::builder(2, 2).with_exits(0, 2, Exits::all())).is_err());
+}
+
+#[test]
+fn test_field_traits() {
+    use crate::Exits;
+    use std::collections::HashSet;
+
+    let mut home = Panel::new(PanelKind::Home);
+    home.exits = Exits::EAST | Exits::SOUTH;
+
+    let field = Field::new_slice(&[
+        &[home.clone(), Panel::new(PanelKind::Draw)],
+    ]);
+
+    let mut edited = field.clone();
+    assert_eq!(field, edited);
+
+    edited.get_mut((1, 0)).kind = PanelKind::Bonus;
+    assert_ne!(field, edited);
+
+    let set = vec![field.clone(), edited, field.clone()].into_iter().collect::<HashSet<_>>();
+    assert_eq!(set.len(), 2);
+
+    assert_eq!(
+        format!("{:?}", field),
+        r#"Field { width: 2, height: 1, rows: ["020c 0400"] }"#,
+    );
+    assert_eq!(format!("{:?}", home.exits), "Exits(EAST | SOUTH)");
+}
+
+#[test]
+fn test_build_backtrack() {
+    use crate::{field, Exits};
+
+    // backtracking walks exits the other way, so the panel an exit leads to
+    // gets an exit back
+    let mut field = field![
+        [Home + SOUTH, Draw],
+        [Bonus, Drop + NORTH],
+    ];
+    field.build_backtrack();
+
+    assert!(field.get((0, 0)).exits_backtrack == Exits::none());
+    assert!(field.get((0, 1)).exits_backtrack == Exits::NORTH);
+    assert!(field.get((1, 0)).exits_backtrack == Exits::SOUTH);
+    assert!(field.get((1, 1)).exits_backtrack == Exits::none());
+}
+
+#[test]
+fn test_resize() {
+    use crate::{field, Exits};
+    use crate::transform::Anchor;
+    use PanelKind::*;
+
+    let field = field![
+        [Home + EAST, Draw + EAST, Bonus + WEST],
+    ];
+
+    // growing keeps everything
+    let grown = field.resize(5, 3, Anchor::Center);
+    assert_eq!(grown.get((1, 1)).kind, Home);
+    assert!(grown.get((3, 1)).exits == Exits::WEST);
+    assert_eq!(grown.resize(3, 1, Anchor::Center), field);
+
+    // shrinking trims exits into the cropped panels
+    let shrunk = field.resize(2, 1, Anchor::Left);
+    assert!(shrunk.get((0, 0)).exits == Exits::EAST);
+    assert!(shrunk.get((1, 0)).exits == Exits::none());
+}
+
+#[test]
+fn test_crop() {
+    use crate::field;
+    use std::panic::catch_unwind;
+
+    let field = field![
+        [Home + EAST, Draw + EAST + SOUTH, Bonus + WEST],
+        [Empty, Drop + NORTH + EAST, Encounter + WEST + NORTH],
+    ];
+
+    // exits crossing the new border are trimmed, and the rest are kept
+    let sub = field.subfield(1, 0, 2, 2);
+    assert_eq!(sub, field![
+        [Draw + EAST + SOUTH, Bonus + WEST],
+        [Drop + NORTH + EAST, Encounter + WEST + NORTH],
+    ]);
+    assert_eq!(field.subfield(0, 1, 3, 1), field![[Empty, Drop + EAST, Encounter + WEST]]);
+
+    assert_eq!(field.subfield(0, 0, 3, 2), field);
+    assert_eq!(field.subfield(2, 1, 0, 0), Field::new());
+    assert_eq!(field.clone().crop(1, 0, 2, 2), sub);
+
+    assert!(catch_unwind(|| field.subfield(2, 0, 2, 1)).is_err());
+    assert!(catch_unwind(|| field.subfield(usize::MAX, 0, 2, 1)).is_err());
+}
+
+#[test]
+fn test_pad() {
+    use crate::field;
+
+    let field = field![
+        [Home + EAST, Draw + SOUTH],
+        [Empty, Bonus + NORTH],
+    ];
+
+    let padded = field.pad(1, 0, 2, 1);
+    assert_eq!(padded, field![
+        [Empty, Empty, Empty],
+        [Empty, Empty, Empty],
+        [Empty, Home + EAST, Draw + SOUTH],
+        [Empty, Empty, Bonus + NORTH],
+        [Empty, Empty, Empty],
+    ]);
+
+    // the original content can be cut back out
+    assert_eq!(padded.subfield(1, 2, 2, 2), field);
+    assert_eq!(field.pad(0, 0, 0, 0), field);
+    assert_eq!(Field::new().pad(1, 0, 0, 1), field![[Empty]]);
+}
+
+#[test]
+fn test_trim() {
+    use crate::{field, Pos};
+
+    let field = field![
+        [Empty, Empty, Empty, Empty],
+        [Empty, Home + EAST, Empty, Empty],
+        [Empty, Empty, Draw + NORTH, Empty],
+    ];
+
+    let (trimmed, offset) = field.trim();
+    assert_eq!(trimmed, field![
+        [Home + EAST, Empty],
+        [Empty, Draw + NORTH],
+    ]);
+    assert_eq!(offset, Pos::new(1, 1));
+
+    // trimming undoes padding, and does nothing to a tight field
+    assert_eq!(trimmed.pad(3, 0, 1, 2).trim(), (trimmed.clone(), Pos::new(3, 1)));
+    assert_eq!(trimmed.trim(), (trimmed.clone(), Pos::ORIGIN));
+
+    // fields without panels trim away entirely
+    assert_eq!(field![[Empty, Empty]].trim(), (Field::new(), Pos::ORIGIN));
+    assert_eq!(Field::new().trim(), (Field::new(), Pos::ORIGIN));
+}
+
+#[test]
+fn test_rotate() {
+    use crate::field;
+
+    let field = field![
+        [Home + EAST + SOUTH, Draw + WEST],
+        [Bonus + NORTH, Empty],
+        [Drop, Encounter],
+    ];
+
+    let rotated = field.rotate_cw();
+    assert_eq!(rotated, field![
+        [Drop, Bonus + EAST, Home + SOUTH + WEST],
+        [Encounter, Empty, Draw + NORTH],
+    ]);
+
+    assert_eq!(rotated.rotate_ccw(), field);
+    assert_eq!(field.rotate_180(), rotated.rotate_cw());
+    assert_eq!(field.rotate_180().rotate_180(), field);
+
+    // mirroring both ways is a half turn
+    assert_eq!(field.flip_horizontal().flip_vertical(), field.rotate_180());
+    assert_eq!(field.flip_vertical().flip_vertical(), field);
+
+    // every orientation has the same canonical form
+    let canonical = field.canonicalize();
+    for turned in &[rotated.clone(), field.flip_horizontal(), rotated.flip_vertical()] {
+        assert_eq!(turned.canonicalize(), canonical);
+        assert_eq!(turned.canonical_hash(), field.canonical_hash());
+    }
+    assert_ne!(field.canonical_hash(), field.subfield(0, 0, 2, 2).canonical_hash());
+}
+
+#[test]
+fn test_shift() {
+    use crate::field;
+
+    let field = field![
+        [Home + EAST, Draw + WEST + SOUTH],
+        [Empty, Bonus + NORTH],
+    ];
+
+    // the seam between the two columns is cut
+    assert_eq!(field.shift(1, 0, true), field![
+        [Draw + SOUTH, Home],
+        [Bonus + NORTH, Empty],
+    ]);
+
+    // and so is the seam between the two rows
+    assert_eq!(field.shift(0, 3, true), field![
+        [Empty, Bonus],
+        [Home + EAST, Draw + WEST],
+    ]);
+
+    assert_eq!(field.shift(-1, -1, false), field![
+        [Bonus, Empty],
+        [Empty, Empty],
+    ]);
+}
+
+#[test]
+fn test_paste() {
+    use crate::field;
+    use crate::edit::Blend;
+    use crate::pos::Rect;
+
+    let mut field = field![
+        [Home + EAST, Draw + WEST, Bonus],
+        [Drop, Drop, Drop],
+    ];
+
+    // the exit out of the region is trimmed
+    let stamp = field.copy_region(Rect::new(1, 0, 2, 1));
+    assert_eq!(stamp.field(), &field![[Draw, Bonus]]);
+
+    let blank = field![[Empty, Encounter]].into();
+
+    field.paste(&blank, 0, 1, Blend::SkipEmpty);
+    field.paste(&stamp, 2, 1, Blend::Overwrite);
+
+    assert_eq!(field, field![
+        [Home + EAST, Draw + WEST, Bonus],
+        [Drop, Encounter, Draw],
+    ]);
+}
+
+#[test]
+fn test_move_region() {
+    use crate::{field, Pos};
+    use crate::pos::Rect;
+
+    let mut field = field![
+        [Home + EAST, Draw + WEST + SOUTH, Empty],
+        [Empty, Bonus + NORTH, Empty],
+    ];
+
+    // moving the loop keeps it connected, but cuts it from what it leaves
+    field.move_region(Rect::new(0, 0, 2, 1), Pos::new(1, 0));
+
+    assert_eq!(field, field![
+        [Empty, Home + EAST, Draw + WEST],
+        [Empty, Bonus, Empty],
+    ]);
+}
+
+#[test]
+fn test_edit_history() {
+    use crate::field;
+    use crate::edit::EditHistory;
+    use crate::transform::Anchor;
+
+    let original = field![[Home, Empty]];
+    let mut history = EditHistory::new(original.clone());
+
+    history.edit(|field| field.get_mut((1, 0)).kind = PanelKind::Draw);
+    history.edit(|field| *field = field.resize(3, 2, Anchor::TopLeft));
+    history.edit(|_| ());
+
+    let edited = history.field().clone();
+    assert_eq!((edited.width(), edited.height()), (3, 2));
+
+    assert!(history.undo() && history.undo());
+    assert!(!history.undo());
+    assert_eq!(history.field(), &original);
+
+    assert!(history.redo() && history.redo());
+    assert!(!history.can_redo());
+    assert_eq!(history.field(), &edited);
+
+    // a new edit forgets what was undone
+    history.undo();
+    history.edit(|field| field.get_mut((0, 0)).kind = PanelKind::Bonus);
+    assert!(!history.can_redo());
+
+    // a transaction is a single step, or none at all
+    let before = history.field().clone();
+    let result = history.transaction(|field| {
+        field.get_mut((0, 0)).kind = PanelKind::Drop;
+        field.get_mut((1, 0)).kind = PanelKind::Drop;
+        Err::<(), _>("failed")
+    });
+
+    assert!(result.is_err());
+    assert_eq!(history.field(), &before);
+
+    history.transaction(|field| {
+        field.get_mut((0, 0)).kind = PanelKind::Drop;
+        field.get_mut((1, 0)).kind = PanelKind::Drop;
+        Ok::<_, ()>(())
+    }).unwrap();
+
+    assert!(history.undo());
+    assert_eq!(history.field(), &before);
+}
+
+#[test]
+fn test_selection() {
+    use crate::{field, Pos};
+    use crate::edit::Selection;
+    use crate::pos::Rect;
+
+    let a = Selection::from(Rect::new(0, 0, 2, 2));
+    let b = vec![Pos::new(1, 1), Pos::new(3, 0)].into_iter().collect::<Selection>();
+
+    assert_eq!(a.union(&b).len(), 5);
+    assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), [Pos::new(1, 1)]);
+    assert_eq!(a.difference(&b).len(), 3);
+    assert_eq!(a.union(&b).bounds(), Some(Rect::new(0, 0, 4, 2)));
+    assert_eq!(Selection::new().bounds(), None);
+
+    // positions off the field are skipped
+    let field = field![[Home, Draw, Bonus]];
+    let kinds = b.union(&a)
+        .panels(&field)
+        .map(|(_, panel)| panel.kind)
+        .collect::<Vec<_>>();
+
+    assert_eq!(kinds, [PanelKind::Home, PanelKind::Draw]);
+}
+
+#[test]
+fn test_draw() {
+    use crate::{field, Pos};
+    use crate::pos::Rect;
+    use PanelKind::*;
+
+    let mut field = Field::builder(4, 3).build();
+
+    // lines run both ways, and only take orthogonal steps
+    let line = field.draw_line(Pos::new(3, 2), Pos::new(0, 0), Draw);
+    assert_eq!(line.len(), 6);
+    assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+
+    field.draw_rect(Rect::new(0, 0, 4, 3), Bonus, false);
+    assert_eq!(field.get((0, 0)).kind, Bonus);
+    assert_ne!(field.get((1, 1)).kind, Bonus);
+
+    field.draw_rect(Rect::new(1, 1, 2, 1), Empty, true);
+    assert_eq!(field.get((1, 1)).kind, Empty);
+
+    let mut field = Field::builder(3, 1).build();
+    field.draw_ring(Rect::new(0, 0, 3, 1), Neutral);
+    assert_eq!(field, field![[Neutral, Neutral, Neutral]]);
+
+    // a path that ends where it starts is a loop
+    let mut field = Field::builder(2, 2).build();
+    let path = [Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1), Pos::new(0, 1), Pos::new(0, 0)];
+    field.lay_path(&path, Some(Neutral), true);
+
+    let mut ring = Field::builder(2, 2).build();
+    ring.draw_ring(Rect::new(0, 0, 2, 2), Neutral);
+
+    assert!(field.panels().zip(ring.panels()).all(|((_, _, a), (_, _, b))| a.exits == b.exits));
+    assert!(field.get((1, 0)).exits_backtrack == crate::Exits::WEST);
+
+    let mut field = field![
+        [Home, Draw],
+        [Empty, Bonus],
+    ];
+    field.connect_adjacent(crate::draw::ConnectMode::Bidirectional);
+
+    assert_eq!(field, field![
+        [Home + EAST, Draw + WEST + SOUTH],
+        [Empty, Bonus + NORTH],
+    ]);
+}
+
+#[test]
+fn test_normalize_exits() {
+    use crate::{field, Direction, Exits, Pos};
+    use crate::draw::ExitChange;
+
+    let mut field = field![
+        [Home + EAST + NORTH, Draw + SOUTH],
+        [Empty + EAST, Bonus + WEST],
+    ];
+    field.get_mut((1, 1)).exits_backtrack = Exits::SOUTH;
+
+    let change = |x, y, exit, backtrack| ExitChange { pos: Pos::new(x, y), exit, backtrack };
+
+    // off the field, out of an empty panel, into an empty panel, and backtrack
+    let mut normalized = field.clone();
+    let report = normalized.normalize_exits(false);
+    assert_eq!(report.removed, [
+        change(0, 0, Direction::North, false),
+        change(0, 1, Direction::East, false),
+        change(1, 1, Direction::West, false),
+        change(1, 1, Direction::South, true),
+    ]);
+    assert!(report.mirrored.is_empty());
+    assert_eq!(normalized, field![
+        [Home + EAST, Draw + SOUTH],
+        [Empty, Bonus],
+    ]);
+
+    // mirroring makes the remaining one-way exits two-way
+    let report = field.normalize_exits(true);
+    assert_eq!(report.removed.len(), 4);
+    assert_eq!(report.mirrored, [
+        change(1, 0, Direction::West, false),
+        change(1, 1, Direction::North, false),
+    ]);
+    assert_eq!(field, field![
+        [Home + EAST, Draw + WEST + SOUTH],
+        [Empty, Bonus + NORTH],
+    ]);
+
+    assert!(field.normalize_exits(true).is_empty());
+}
+
+#[test]
+fn test_observed_field() {
+    use crate::{field, Pos};
+    use crate::edit::{FieldEvent, ObservedField};
+    use crate::transform::Anchor;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut field = ObservedField::new(field![[Home, Empty]]);
+
+    let log = Rc::clone(&events);
+    field.observe(move |event| log.borrow_mut().push(event.clone()));
+
+    field.set(Pos::new(1, 0), Panel::new(PanelKind::Draw));
+    field.set(Pos::new(1, 0), Panel::new(PanelKind::Draw));
+    field.edit(|field| *field = field.resize(3, 1, Anchor::TopLeft));
+
+    assert_eq!(*events.borrow(), [
+        FieldEvent::PanelChanged {
+            pos: Pos::new(1, 0),
+            old: Panel::EMPTY,
+            new: Panel::new(PanelKind::Draw),
+        },
+        FieldEvent::Resized { old: (2, 1), new: (3, 1) },
+    ]);
+}
+
+#[test]
+fn test_merge() {
+    use crate::edit::{merge, SizeConflict};
+
+    let base = crate::field![[Home, Empty], [Empty, Empty]];
+    let ours = crate::field![[Home + EAST, Draw], [Empty, Empty]];
+    let theirs = crate::field![[Home, Draw], [Bonus, Empty]];
+
+    // non-overlapping and identical changes merge cleanly
+    let merged = merge(&base, &ours, &theirs).unwrap();
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(merged.field, crate::field![[Home + EAST, Draw], [Bonus, Empty]]);
+
+    // differing changes keep ours and are reported
+    let theirs = crate::field![[Home + SOUTH, Empty], [Empty, Empty]];
+    let merged = merge(&base, &ours, &theirs).unwrap();
+    assert_eq!(merged.conflicts.len(), 1);
+    assert_eq!(merged.conflicts[0].pos, (0, 0).into());
+    assert_eq!(merged.field, ours);
+
+    // resizing only one side takes that side
+    let wide = Field::builder(3, 2).build();
+    assert_eq!(merge(&base, &base, &wide).unwrap().field, wide);
+    assert_eq!(merge(&base, &wide, &base).unwrap().field, wide);
+    assert_eq!(
+        merge(&base, &ours, &wide),
+        Err(SizeConflict { ours: (2, 2), theirs: (3, 2) }),
+    );
+
+    // a resize conflicts with even a single panel edited on the other side
+    let mut edited = base.clone();
+    edited.get_mut((1, 1)).kind = PanelKind::Bonus;
+    assert_eq!(
+        merge(&base, &wide, &edited),
+        Err(SizeConflict { ours: (3, 2), theirs: (2, 2) }),
+    );
+}
+
+#[test]
+fn test_diff() {
+    use crate::diff::{similarity, Change, PatchError};
+    use crate::Pos;
+
+    let old = crate::field![[Home + EAST, Draw], [Empty, Bonus]];
+    assert!(old.diff(&old).is_empty());
+
+    let new = crate::field![[Home + EAST + SOUTH, Draw], [Drop, Bonus]];
+    let changes = old.diff(&new);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[1], Change::Panel {
+        pos: Pos::new(0, 1),
+        old: Panel::new(PanelKind::Empty),
+        new: Panel::new(PanelKind::Drop),
+    });
+
+    // shrinking drops the exit east along with the panel it led to
+    let narrow = crate::field![[Home], [Empty]];
+    let changes = old.diff(&narrow);
+    assert_eq!(changes, vec![Change::Resized { old: (2, 2), new: (1, 2) }]);
+
+    // diffs apply both ways
+    for &(from, to) in &[(&old, &new), (&new, &narrow), (&narrow, &old)] {
+        let mut patched = from.clone();
+        patched.apply(&from.diff(to)).unwrap();
+        assert_eq!(patched, *to);
+    }
+
+    // a failed patch changes nothing
+    let mut patched = old.clone();
+    let mut changes = old.diff(&new);
+    changes.push(Change::Panel { pos: Pos::new(5, 5), old: Panel::EMPTY, new: Panel::EMPTY });
+    assert_eq!(patched.apply(&changes), Err(PatchError::OutOfBounds { index: 2, pos: Pos::new(5, 5) }));
+    assert_eq!(patched, old);
+
+    let err = narrow.clone().apply(&old.diff(&new)).unwrap_err();
+    assert_eq!(err.index(), 0);
+    assert!(matches!(err, PatchError::Conflict { .. }));
+    let err = new.clone().apply(&narrow.diff(&old)).unwrap_err();
+    assert!(matches!(err, PatchError::SizeMismatch { .. }));
+
+    // similarity ignores padding and orientation
+    let moved = old.pad(1, 2, 0, 1).rotate_ccw();
+    assert_eq!(similarity(&old, &moved), 1.0);
+    assert!(similarity(&old, &new) > similarity(&old, &narrow));
+    assert_eq!(similarity(&old, &crate::field![[Drop, Drop]]), 0.0);
+}
+
+#[test]
+fn test_shortest_path() {
+    use crate::path::shortest_path;
+    use crate::Pos;
+
+    // a loop around a wall, with a way back to the start
+    let field = crate::field![
+        [Home + EAST, Neutral + EAST, Draw + SOUTH],
+        [Bonus + NORTH + EAST, Empty, Drop + SOUTH],
+        [Encounter + NORTH, Neutral + WEST, Neutral + WEST],
+    ];
+
+    let path = |from: (usize, usize), to: (usize, usize)| {
+        shortest_path(&field, from.into(), to.into())
+            .map(|path| path.len())
+    };
+
+    assert_eq!(path((0, 0), (0, 0)), Some(1));
+    assert_eq!(path((0, 0), (2, 2)), Some(5));
+    assert_eq!(path((0, 0), (0, 2)), Some(7));
+    // the exit east of (0, 1) leads into an empty panel
+    assert_eq!(path((0, 1), (0, 0)), Some(2));
+    assert_eq!(path((0, 0), (1, 1)), None);
+    assert_eq!(shortest_path(&field, Pos::new(0, 0), Pos::new(3, 0)), None);
+}
+
+#[test]
+fn test_cheapest_path() {
+    use crate::path::cheapest_path;
+    use crate::Pos;
+    use PanelKind::*;
+
+    // two ways around, one through an encounter
+    let field = crate::field![
+        [Home + EAST + SOUTH, Encounter + EAST, Neutral + SOUTH],
+        [Draw + SOUTH, Empty, Bonus],
+        [Drop + EAST, Neutral + EAST, Neutral + NORTH],
+    ];
+
+    let (from, to) = (Pos::new(0, 0), Pos::new(2, 1));
+
+    let flat = cheapest_path(&field, from, to, |_| 1).unwrap();
+    assert_eq!(flat.1, 3);
+    assert_eq!(flat.0[1], Pos::new(1, 0));
+
+    let safe = cheapest_path(&field, from, to, |kind| if kind == Encounter { 5 } else { 1 }).unwrap();
+    assert_eq!(safe.1, 5);
+    assert_eq!(safe.0.len(), 6);
+
+    // free panels still find a path
+    assert_eq!(cheapest_path(&field, from, to, |_| 0).map(|(_, cost)| cost), Some(0));
+    assert_eq!(cheapest_path(&field, to, from, |_| 1), None);
+}
+
+#[test]
+fn test_field_stats() {
+    use crate::Direction;
+    use crate::analysis::FieldStats;
+    use PanelKind::*;
+
+    let field = crate::field![
+        [Home + EAST + NORTH, Draw + SOUTH + EAST, Draw],
+        [Empty, Bonus + WEST, Empty],
+    ];
+
+    let stats = field.stats();
+    assert_eq!((stats.width, stats.height, stats.panels), (3, 2, 4));
+    assert_eq!(stats.kinds.len(), 3);
+    assert_eq!((stats.kinds[&Home], stats.kinds[&Draw], stats.kinds[&Bonus]), (1, 2, 1));
+    assert!(!stats.kinds.contains_key(&Empty));
+
+    // every exit is counted, but only those leading to a panel branch
+    assert_eq!(stats.exits[&Direction::East], 2);
+    assert_eq!(stats.exits.values().sum::<usize>(), 5);
+    assert_eq!(stats.branching, 0.75);
+
+    let empty = Field::builder(2, 1).build().stats();
+    assert_eq!(empty, FieldStats { width: 2, height: 1, ..FieldStats::default() });
+}
+
+#[test]
+fn test_components() {
+    use crate::analysis::{components, Connectivity};
+
+    // a loop, a panel the loop leads into but not back out of, and an island
+    let field = crate::field![
+        [Home + EAST, Draw + SOUTH, Empty, Bonus + SOUTH],
+        [Neutral + NORTH, Drop + WEST + EAST, Encounter, Bonus + NORTH],
+    ];
+
+    let weak = components(&field, Connectivity::Weak);
+    assert_eq!(weak.len(), 2);
+    assert_eq!(weak[0].len(), 5);
+    assert_eq!(weak[1], vec![(3, 0).into(), (3, 1).into()]);
+
+    let strong = components(&field, Connectivity::Strong);
+    assert_eq!(strong.len(), 3);
+    assert_eq!(strong[0].len(), 4);
+    assert_eq!(strong[1], vec![(3, 0).into(), (3, 1).into()]);
+    assert_eq!(strong[2], vec![(2, 1).into()]);
+
+    assert!(components(&Field::builder(2, 2).build(), Connectivity::Weak).is_empty());
+}
+
+#[test]
+fn test_laps() {
+    use crate::analysis::laps;
+
+    // a small loop, and a Home leading into a larger loop it cannot get back to
+    let field = crate::field![
+        [Home + EAST, Draw + SOUTH, Home + EAST, Bonus + EAST, Drop + SOUTH],
+        [Neutral + NORTH, Neutral + WEST, Empty, Neutral + SOUTH, Draw + WEST],
+        [Empty, Empty, Empty, Encounter + EAST, Bonus + NORTH],
+    ];
+
+    let report = laps(&field);
+    assert_eq!(report.loops.len(), 2);
+    assert_eq!(report.loops[1].len(), 4);
+    assert_eq!(report.laps, vec![((0, 0).into(), Some(4)), ((2, 0).into(), None)]);
+    assert!(!report.homes_connected());
+
+    // loop the second Home through the first loop
+    let mut joined = field.clone();
+    joined.get_mut((1, 1)).exits |= crate::Exits::EAST;
+    joined.get_mut((2, 1)).kind = PanelKind::Neutral;
+    joined.get_mut((2, 1)).exits |= crate::Exits::NORTH;
+    joined.get_mut((2, 0)).exits |= crate::Exits::WEST;
+    assert!(laps(&joined).homes_connected());
+
+    assert!(!laps(&Field::builder(2, 2).build()).homes_connected());
+}
+
+#[test]
+fn test_balance() {
+    use crate::analysis::balance;
+
+    // the Homes mirror each other, except for the Drop and Encounter
+    let field = crate::field![
+        [Home + EAST, Bonus + EAST, Draw2x + SOUTH],
+        [Drop + NORTH, Empty, Encounter + SOUTH],
+        [Draw + NORTH, Bonus + WEST, Home + WEST],
+    ];
+
+    let report = balance(&field);
+    assert_eq!(report.homes.len(), 2);
+    assert_eq!(report.homes[0].draw, Some(2));
+    assert_eq!(report.homes[1].drop, Some(3));
+    assert!(report.fairness > 0.5 && report.fairness < 1.0);
+
+    // cut the loop, so only one Home can reach the Drop
+    let mut cut = field.clone();
+    cut.get_mut((1, 0)).exits = crate::Exits::none();
+    let report = balance(&cut);
+    assert_eq!(report.homes[0].drop, None);
+    assert_eq!(report.homes[1].drop, Some(3));
+    assert!(report.fairness < 0.75);
+
+    assert_eq!(balance(&Field::builder(1, 1).build()).fairness, 1.0);
+}
+
+#[test]
+fn test_exit_problems() {
+    use crate::analysis::{bad_exits, dead_ends, traps, BadExitKind};
+    use crate::Pos;
+
+    let mut field = crate::field![
+        [Home + EAST + NORTH, Draw + SOUTH + EAST, Bonus],
+        [Neutral + NORTH, Drop + WEST, Empty],
+    ];
+    field.get_mut((2, 0)).exits_backtrack |= crate::Exits::SOUTH;
+
+    assert_eq!(dead_ends(&field), vec![Pos::new(2, 0)]);
+
+    let bad = bad_exits(&field);
+    assert_eq!(bad.len(), 2);
+    assert_eq!((bad[0].pos, bad[0].kind), (Pos::new(0, 0), BadExitKind::OffField));
+    assert_eq!((bad[1].pos, bad[1].backtrack), (Pos::new(2, 0), true));
+    assert_eq!(bad[1].kind, BadExitKind::IntoEmpty);
+
+    assert_eq!(traps(&field), vec![vec![Pos::new(2, 0)]]);
+
+    // a loop on its own traps nobody
+    field.get_mut((1, 0)).exits = crate::Exits::SOUTH;
+    assert!(traps(&field).is_empty());
+}
+
+#[test]
+fn test_landing_probabilities() {
+    use crate::sim::landing_probabilities;
+
+    // a loop with a dead end branching off it, which players get stuck in
+    let field = crate::field![
+        [Home + EAST, Draw + SOUTH + EAST, Bonus],
+        [Neutral + NORTH, Drop + WEST, Empty],
+    ];
+
+    let landing = landing_probabilities(&field);
+    assert_eq!(landing.len(), 6);
+    assert!((landing.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    assert!(landing[2] > 0.99);
+    assert_eq!(landing[5], 0.0);
+
+    // an even loop is landed on evenly
+    let field = crate::field![[Home + EAST, Draw + SOUTH], [Bonus + NORTH, Drop + WEST]];
+    assert!(landing_probabilities(&field).iter().all(|p| (p - 0.25).abs() < 1e-9));
+
+    assert!(landing_probabilities(&Field::builder(2, 1).build()).iter().all(|p| *p == 0.0));
+}
+
+#[test]
+fn test_simulate() {
+    use crate::sim::{simulate, SimConfig};
+
+    // a loop of six, so every lap takes at least one turn
+    let field = crate::field![
+        [Home + EAST, Draw + EAST, Bonus + SOUTH],
+        [Drop + NORTH, Neutral + WEST, Encounter + WEST],
+    ];
+
+    let config = SimConfig { walks: 20, turns: 50, seed: 42 };
+    let result = simulate(&field, config);
+
+    assert_eq!(result, simulate(&field, config));
+    assert_ne!(result, simulate(&field, SimConfig { seed: 43, ..config }));
+    assert_eq!(result.landings.iter().sum::<usize>(), 20 * 50);
+    assert!(result.visits.iter().all(|visits| *visits > 0));
+    assert!(result.lap_times.iter().all(|turns| (1..=6).contains(turns)));
+
+    let empty = simulate(&Field::builder(2, 2).build(), config);
+    assert!(empty.landings.iter().all(|landings| *landings == 0));
+    assert_eq!(empty.average_lap(), None);
+}
+
+#[test]
+fn test_mover() {
+    use crate::sim::{Destination, Mover};
+    use crate::{Direction, Pos};
+
+    // a two-way corridor with a loop at the east end
+    let field = crate::field![
+        [Home + EAST, Neutral + WEST + EAST, Draw + WEST + EAST, Bonus + SOUTH],
+        [Empty, Empty, Drop + NORTH, Encounter + WEST],
+    ];
+
+    let mover = Mover::new(&field);
+    let ends = |from: (usize, usize), heading, roll| {
+        let mut ends: Vec<(usize, usize)> = mover.destinations(from.into(), heading, roll)
+            .into_iter()
+            .map(|end| end.pos.into())
+            .collect();
+        ends.sort();
+        ends
+    };
+
+    // without a heading, either way can be taken
+    assert_eq!(ends((1, 0), None, 1), vec![(0, 0), (2, 0)]);
+    assert_eq!(ends((1, 0), Some(Direction::East), 1), vec![(2, 0)]);
+    // around the loop and back west, but never turning around
+    assert_eq!(ends((2, 0), Some(Direction::East), 5), vec![(1, 0), (3, 0)]);
+    // the dead end at Home turns players around
+    assert_eq!(ends((1, 0), Some(Direction::West), 2), vec![(1, 0)]);
+
+    assert_eq!(
+        mover.destinations(Pos::new(0, 0), None, 0),
+        vec![Destination { pos: Pos::new(0, 0), heading: None }],
+    );
+    assert!(mover.destinations(Pos::new(9, 9), None, 3).is_empty());
+}
+
+#[test]
+fn test_random_field() {
+    use crate::analysis::{components, dead_ends, Connectivity};
+    use crate::gen::{random, RandomConfig};
+
+    let config = RandomConfig::default();
+
+    for seed in 0..50 {
+        let field = random(9, 7, seed, &config);
+
+        assert_eq!(components(&field, Connectivity::Strong).len(), 1);
+        assert!(dead_ends(&field).is_empty());
+        assert_eq!(field.panels().filter(|(_, _, panel)| panel.kind == PanelKind::Home).count(), 4);
+    }
+
+    let two_way = RandomConfig { two_way: true, branches: 5, ..config.clone() };
+    let mut field = random(6, 6, 3, &two_way);
+    assert!(field.normalize_exits(true).is_empty());
+
+    assert_ne!(random(9, 7, 1, &config), random(9, 7, 2, &config));
+    assert!(random(2, 2, 0, &config).panels().all(|(_, _, panel)| panel.kind != PanelKind::Empty));
+}
+
+#[test]
+fn test_constrained_field() {
+    use crate::analysis::laps;
+    use crate::gen::{constrained, Constraints, RandomConfig, Symmetry};
+
+    let constraints = Constraints {
+        homes: Some(3),
+        drop: 0..=2,
+        lap: Some(10..=30),
+        ..Constraints::default()
+    };
+
+    let field = constrained(8, 8, 5, &RandomConfig::default(), &constraints, 1000).unwrap();
+
+    assert!(constraints.satisfied_by(&field));
+    assert_eq!(field.panels().filter(|(_, _, panel)| panel.kind == PanelKind::Home).count(), 3);
+    assert!(laps(&field).laps.iter().all(|(_, lap)| (10..=30).contains(&lap.unwrap())));
+
+    // no one-way loop on a grid takes fewer than four moves
+    let impossible = Constraints { lap: Some(0..=1), ..Constraints::default() };
+    let err = constrained(4, 4, 0, &RandomConfig::default(), &impossible, 20).unwrap_err();
+    assert_eq!(err.attempts, 20);
+
+    let ring = crate::field![
+        [Home + EAST, Draw + SOUTH],
+        [Draw + NORTH, Home + WEST],
+    ];
+    assert!(Symmetry::HalfTurn.holds(&ring));
+    assert!(!Symmetry::Horizontal.holds(&ring));
+}
+
+#[test]
+fn test_symmetric_field() {
+    use crate::analysis::{components, dead_ends, Connectivity};
+    use crate::gen::{constrained, symmetric, Constraints, RandomConfig, Symmetry};
+
+    let config = RandomConfig::default();
+    let all = [
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::HalfTurn,
+        Symmetry::QuarterTurn,
+        Symmetry::FourWay,
+    ];
+
+    for symmetry in all.iter() {
+        for (seed, size) in [(0, 8), (1, 9), (2, 10)].iter() {
+            let field = symmetric(*size, *size, *seed, &config, *symmetry);
+
+            assert!(symmetry.holds(&field), "{:?} {}", symmetry, seed);
+            assert_eq!(components(&field, Connectivity::Strong).len(), 1);
+            assert!(dead_ends(&field).is_empty());
+            assert_eq!(field.panels().filter(|(_, _, panel)| panel.kind == PanelKind::Home).count(), 4);
+        }
+    }
+
+    let constraints = Constraints { symmetry: Some(Symmetry::Vertical), ..Constraints::default() };
+    let field = constrained(7, 10, 3, &config, &constraints, 1).unwrap();
+    assert!(Symmetry::Vertical.holds(&field));
+}
+
+#[test]
+fn test_mutate() {
+    use crate::analysis::{bad_exits, components, dead_ends, Connectivity};
+    use crate::gen::{mutate, random, templates, Mutation, MutationConfig, RandomConfig};
+
+    let kinds = |field: &Field| {
+        let mut kinds: Vec<PanelKind> = field.panels().map(|(_, _, panel)| panel.kind).collect();
+        kinds.sort_by_key(|kind| u8::from(*kind));
+        kinds
+    };
+
+    let field = random(9, 9, 4, &RandomConfig::default());
+
+    for seed in 0..20 {
+        let config = MutationConfig { mutations: 4, ..MutationConfig::default() };
+        let mutated = mutate(&field, &config, seed);
+
+        assert_eq!(components(&mutated, Connectivity::Strong).len(), 1);
+        assert!(dead_ends(&mutated).is_empty());
+        assert!(bad_exits(&mutated).is_empty());
+        assert_eq!(mutated, mutate(&field, &config, seed));
+    }
+
+    // swapping kinds leaves the exits and the kinds used alone
+    let swaps = MutationConfig { mutations: 3, weights: vec![(Mutation::SwapKinds, 1)], two_way: false };
+    let swapped = mutate(&field, &swaps, 1);
+    assert_ne!(swapped, field);
+    assert_eq!(kinds(&swapped), kinds(&field));
+    assert!(swapped.positions().all(|pos| swapped[pos].exits == field[pos].exits));
+
+    // stretches between where the rings cross can go around the outside
+    let reroutes = MutationConfig { mutations: 1, weights: vec![(Mutation::Reroute, 1)], two_way: false };
+    let rings = templates::double_ring(7, 7).resize(9, 9, crate::transform::Anchor::Center);
+    let rerouted = mutate(&rings, &reroutes, 3);
+    assert_ne!(rerouted, rings);
+    assert_eq!(components(&rerouted, Connectivity::Strong).len(), 1);
+    assert!(dead_ends(&rerouted).is_empty());
+
+    let nothing = MutationConfig { weights: Vec::new(), ..MutationConfig::default() };
+    assert_eq!(mutate(&field, &nothing, 0), field);
+}
+
+#[test]
+fn test_pipeline() {
+    use crate::gen::{random, Constraints, Pipeline, RandomConfig, Rule};
+
+    let config = RandomConfig::default();
+
+    // random fields always follow the structural rules
+    let (field, stats) = Pipeline::new(|seed| random(7, 7, seed, &config)).run(0);
+    assert!(field.is_some());
+    assert_eq!(stats.attempts, 1);
+    assert!(!stats.fixed);
+
+    // an exit from the first panel up into empty space is broken until fixed
+    let broken = |seed| {
+        let mut field = random(7, 7, seed, &config);
+        let first = field.positions().find(|pos| field[*pos].kind != PanelKind::Empty).unwrap();
+        field[first].exits |= crate::Exits::NORTH;
+        field
+    };
+
+    let (field, stats) = Pipeline::new(broken).rules(&[Rule::NoBadExits]).budget(3).run(0);
+    assert!(field.is_none());
+    assert_eq!(stats.attempts, 3);
+    assert_eq!(stats.rejections[&Rule::NoBadExits], 3);
+
+    let pipeline = Pipeline::new(broken).fix(|field| { field.normalize_exits(false); });
+    let (field, stats) = pipeline.run(0);
+    assert!(Rule::NoBadExits.holds(&field.unwrap(), &Constraints::default()));
+    assert!(stats.fixed);
+
+    let impossible = Constraints { homes: Some(9), ..Constraints::default() };
+    let (field, stats) = Pipeline::new(|seed| random(5, 5, seed, &config)).constraints(impossible).budget(10).run(1);
+    assert!(field.is_none());
+    assert_eq!(stats.rejections[&Rule::Constraints], 10);
+}
+
+#[test]
+fn test_sanitize() {
+    use crate::Pos;
+    use crate::sanitize::KindChange;
+
+    // backtrack exits are rebuilt the same way paths lay them
+    let ring = crate::gen::templates::double_ring(7, 7);
+    let mut rebuilt = ring.clone();
+    rebuilt.build_backtrack();
+    assert_eq!(rebuilt, ring);
+
+    let mut field = crate::field![
+        [Home + EAST + NORTH, Heal2x + SOUTH, Empty + WEST],
+        [Empty, Draw + WEST, Empty],
+    ];
+    field[Pos::new(0, 0)].exits_backtrack |= crate::Exits::SOUTH;
+
+    let report = field.sanitize();
+
+    assert_eq!(report.replaced.len(), 1);
+    assert_eq!(report.replaced[0].new, PanelKind::Heal);
+    assert_eq!(report.removed.len(), 3);
+    assert_eq!(report.backtrack, vec![Pos::new(0, 0), Pos::new(1, 0), Pos::new(1, 1)]);
+    assert_eq!(field[Pos::new(0, 0)].exits, crate::Exits::EAST);
+    assert_eq!(field[Pos::new(0, 0)].exits_backtrack, crate::Exits::none());
+    assert_eq!(field[Pos::new(1, 1)].exits_backtrack, crate::Exits::NORTH);
+
+    assert!(field.sanitize().is_empty());
+
+    // unknown kinds are replaced too
+    let mut field = crate::field![[Home + EAST, Draw + WEST]];
+    field[Pos::new(1, 0)].kind = PanelKind::Unknown(0xF0);
+
+    let report = field.sanitize();
+    assert_eq!(report.replaced, [KindChange {
+        pos: Pos::new(1, 0),
+        old: PanelKind::Unknown(0xF0),
+        new: PanelKind::Neutral,
+    }]);
+    assert_eq!(field[Pos::new(1, 0)].kind, PanelKind::Neutral);
+    assert!(field.sanitize().is_empty());
+}
+
+#[test]
+fn test_fill() {
+    use crate::gen::{fill, templates, FillConfig, Spread};
+
+    let mut skeleton = templates::double_ring(10, 10);
+    skeleton[crate::Pos::new(0, 0)].kind = PanelKind::Home;
+
+    let config = FillConfig::default();
+    let field = fill(&skeleton, &config, 11);
+
+    assert_eq!(field, fill(&skeleton, &config, 11));
+    assert_eq!(field[crate::Pos::new(0, 0)].kind, PanelKind::Home);
+    assert!(field.positions().all(|pos| {
+        field[pos].exits == skeleton[pos].exits
+            && (field[pos].kind == PanelKind::Empty) == (skeleton[pos].kind == PanelKind::Empty)
+    }));
+
+    // scattered panels are rarely next to each other, clustered ones often are
+    let adjacent = |kind: PanelKind, spread: Spread| -> usize {
+        let config = FillConfig { kinds: vec![(kind, 1, spread), (PanelKind::Neutral, 2, Spread::Uniform)], ..config.clone() };
+
+        (0..10).map(|seed| {
+            let field = fill(&skeleton, &config, seed);
+            field.positions()
+                .filter(|pos| field[*pos].kind == kind)
+                .filter(|pos| pos.offset(1, 0).is_some_and(|to| field.contains(to) && field[to].kind == kind))
+                .count()
+        }).sum()
+    };
+
+    assert!(adjacent(PanelKind::Encounter, Spread::Clustered) > adjacent(PanelKind::Bonus, Spread::Scattered));
+
+    let nothing = FillConfig { kinds: Vec::new(), ..config.clone() };
+    assert!(fill(&skeleton, &nothing, 0).panels().all(|(_, _, panel)| {
+        matches!(panel.kind, PanelKind::Empty | PanelKind::Neutral | PanelKind::Home)
+    }));
+}
+
+#[test]
+fn test_track() {
+    use crate::analysis::{components, Connectivity};
+    use crate::gen::track;
+    use crate::path::successors;
+
+    for seed in 0..20 {
+        let mut field = track(9, 7, 24, seed);
+        let panels: Vec<crate::Pos> = field.positions().filter(|pos| field[*pos].kind != PanelKind::Empty).collect();
+
+        assert_eq!(panels.len(), 24);
+        assert_eq!(components(&field, Connectivity::Strong).len(), 1);
+
+        // one loop, with no branches, each way
+        assert!(panels.iter().all(|pos| successors(&field, *pos).count() == 2));
+        assert!(field.normalize_exits(true).is_empty());
+    }
+
+    // asking for more than fits stops when the field is full
+    let full = track(4, 4, 100, 1);
+    assert!(full.panels().filter(|(_, _, panel)| panel.kind != PanelKind::Empty).count() <= 16);
+    assert_eq!(track(2, 2, 0, 0), track(2, 2, 4, 0));
+}
+
+#[test]
+fn test_render_ascii() {
+    use crate::render::ascii::{render, Backtrack, RenderOptions};
+
+    let mut field = crate::field![[Home + EAST, Draw + SOUTH], [Empty, Bonus]];
+    field.build_backtrack();
+
+    let default = RenderOptions::default();
+    assert_eq!(render(&field, &default), field.to_string());
+    assert_eq!(field.to_string(), "\n@@>da\n   \\/ \n   bs\n  ");
+
+    let backtrack = RenderOptions { backtrack: Backtrack::Show, ..default.clone() };
+    let mut only_backtrack = field.clone();
+    only_backtrack.positions().for_each(|pos| only_backtrack[pos].exits = crate::Exits::none());
+    assert_eq!(render(&only_backtrack, &backtrack), "\n@@(da\n   ^^ \n   bs\n  ");
+    assert_eq!(render(&field, &backtrack), render(&field, &default));
+
+    let only = RenderOptions { backtrack: Backtrack::Only, ..default.clone() };
+    assert_eq!(render(&field, &only), render(&only_backtrack, &backtrack));
+
+    let wide = RenderOptions { cell_width: 3, ..default.clone() };
+    assert_eq!(render(&field, &wide), "\n@@ >da \n    \\/  \n    bs \n  ");
+
+    let legend = RenderOptions { legend: true, ..default.clone() };
+    assert!(render(&field, &legend).ends_with("\n\n@@ Home\nda Draw\nbs Bonus"));
+
+    let coordinates = RenderOptions { coordinates: true, borders: true, ..default };
+    assert_eq!(render(&field, &coordinates), "\n    0  1\n  +-----+\n0 |@@>da|\n  |   \\/|\n1 |   bs|\n  +-----+");
+    assert!(format!("{:#}", field).starts_with("\n   0  1\n0 @@>da\n"));
+}
+
+#[test]
+fn test_parse_ascii() {
+    use crate::gen::{fill, templates, FillConfig};
+    use crate::render::ascii::{parse, render, Backtrack, InvalidGlyph, RenderOptions};
+
+    let field = fill(&templates::ring(5, 4), &FillConfig::default(), 8);
+    let mut parsed = parse(&field.to_string()).unwrap();
+    parsed.build_backtrack();
+    assert_eq!(parsed, field);
+
+    let mut field = crate::field![[Home + EAST, Draw + SOUTH], [Empty, Bonus]];
+    field.build_backtrack();
+
+    let options = RenderOptions { backtrack: Backtrack::Only, borders: true, ..RenderOptions::default() };
+    let mut only_backtrack = field.clone();
+    only_backtrack.positions().for_each(|pos| only_backtrack[pos].exits = crate::Exits::none());
+    assert_eq!(parse(&render(&field, &options)).unwrap(), only_backtrack);
+
+    // missing spaces at the ends of lines are filled in
+    assert_eq!(parse("[]>[]\n\\/\n[]").unwrap().height(), 2);
+
+    assert_eq!(
+        parse("\n@@>da\n   xx"),
+        Err(InvalidGlyph { line: 2, column: 3, value: "xx".into() }),
+    );
+    assert_eq!(parse("+--+\n|??|\n+--+").unwrap_err().column, 1);
+}
+
+#[test]
+fn test_render_unicode() {
+    use crate::render::unicode::{render, UnicodeOptions};
+
+    let mut field = crate::field![[Home + EAST, Draw + WEST + SOUTH], [Empty, Bonus]];
+    field.build_backtrack();
+
+    let narrow = UnicodeOptions { cell_width: 2, ..UnicodeOptions::default() };
+    assert_eq!(render(&field, &narrow), "┌──┬──┐\n│@@↔da│\n└──┼▼─┤\n   │bs│\n   └──┘");
+
+    field.positions().for_each(|pos| field[pos].exits = crate::Exits::none());
+    assert_eq!(render(&field, &narrow), "┌──┬──┐\n│@@│da│\n└──┼──┤\n   │bs│\n   └──┘");
+
+    let backtrack = UnicodeOptions { backtrack: true, ..narrow };
+    assert_eq!(render(&field, &backtrack), "┌──┬──┐\n│@@◇da│\n└──┼△─┤\n   │bs│\n   └──┘");
+
+    assert!(render(&crate::Field::builder(2, 1).build(), &backtrack).trim().is_empty());
+}
+
+#[test]
+fn test_render_svg() {
+    use crate::render::{svg, SvgOptions};
+
+    let mut field = crate::field![[Home + EAST, Draw + WEST + SOUTH], [Empty, Bonus]];
+    field.build_backtrack();
+
+    let image = svg(&field, &SvgOptions::default());
+    assert!(image.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"96\" height=\"96\""));
+    assert!(image.ends_with("</svg>\n"));
+    assert_eq!(image.matches("class=\"panel\"").count(), 3);
+    assert_eq!(image.matches("class=\"exit\"").count(), 3);
+    assert_eq!(image.matches("class=\"backtrack\"").count(), 3);
+    assert!(image.contains(">da</text>"));
+
+    let plain = SvgOptions { labels: false, backtrack: false, grid: false, cell_size: 10 };
+    let image = svg(&field, &plain);
+    assert!(!image.contains("<text") && !image.contains("class=\"grid\""));
+    assert_eq!(image.matches("class=\"backtrack\"").count(), 0);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_render_png() {
+    use crate::render::{png, raster, Atlas, PngOptions};
+    use image::{Rgba, RgbaImage};
+
+    let field = crate::field![[Home + EAST, Draw + WEST], [Empty, Bonus]];
+    let plain = PngOptions { scale: 1, exits: false, backtrack: false };
+
+    // the sheet is two tiles, a red one and a blue one
+    let sheet = RgbaImage::from_fn(8, 4, |x, _| if x < 4 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 0, 255, 255]) });
+    let atlas = Atlas::from_sheet(&sheet, 4, &[PanelKind::Home, PanelKind::Draw]);
+
+    let image = raster(&field, &atlas, &plain);
+    assert_eq!(image.dimensions(), (8, 8));
+    assert_eq!(image.get_pixel(1, 1), &Rgba([255, 0, 0, 255]));
+    assert_eq!(image.get_pixel(5, 1), &Rgba([0, 0, 255, 255]));
+    assert_eq!(image.get_pixel(1, 5)[3], 0);
+    // Bonus has no sprite, so it has a placeholder border
+    assert_eq!(image.get_pixel(4, 4), &Rgba([0x40, 0x40, 0x40, 255]));
+
+    let scaled = raster(&field, &atlas, &PngOptions { scale: 3, ..plain });
+    assert_eq!(scaled.dimensions(), (24, 24));
+
+    let marked = raster(&field, &atlas, &PngOptions { exits: true, ..plain });
+    assert_eq!(marked.get_pixel(3, 2), &Rgba([0x20, 0x20, 0x20, 255]));
+    assert_eq!(marked.get_pixel(4, 2), &Rgba([0x20, 0x20, 0x20, 255]));
+
+    let bytes = png(&field, &Atlas::default(), &PngOptions::default()).unwrap();
+    assert_eq!(image::load_from_memory(&bytes).unwrap().width(), 64);
+}
+
+#[test]
+fn test_export_html() {
+    use crate::export::{html, HtmlOptions};
+
+    let mut field = crate::field![[Home + EAST, Draw + SOUTH], [Empty, Bonus]];
+    field.build_backtrack();
+
+    let page = html(&field, &HtmlOptions::default());
+    assert!(page.starts_with("<style>\n"));
+    assert!(page.contains(".citrus-field .bonus { background: #f0d040;"));
+    assert!(page.contains("grid-template-columns: repeat(2, 3em);"));
+    assert!(page.contains(r#"<div class="panel draw" data-x="1" data-y="0" data-kind="Draw" data-exits="south" data-backtrack="west">da</div>"#));
+    assert!(page.contains(r#"<div class="panel empty" data-x="0" data-y="1" data-kind="Empty" data-exits="" data-backtrack=""></div>"#));
+    assert_eq!(page.matches("class=\"panel ").count(), 4);
+
+    let bare = HtmlOptions { class: "a\"b".into(), labels: false, style: false };
+    let page = html(&field, &bare);
+    assert!(page.starts_with(r#"<div class="a&quot;b""#));
+    assert!(!page.contains("@@"));
+
+    // unknown kinds still get a valid class
+    field.get_mut((0, 1)).kind = PanelKind::Unknown(0xFE);
+    let page = html(&field, &HtmlOptions::default());
+    assert!(page.contains(".citrus-field .unknown-254 { background:"));
+    assert!(page.contains(r#"<div class="panel unknown-254" data-x="0" data-y="1""#));
+    assert!(!page.contains("unknown("));
+}
+
+#[cfg(feature = "tilemap")]
+#[test]
+fn test_tilemap() {
+    use crate::tilemap::{Edge, Tilemap};
+
+    let mut field = crate::field![[Home + EAST, Draw + SOUTH], [Encounter, Bonus]];
+    field.build_backtrack();
+
+    let map = Tilemap::new(&field, &[PanelKind::Home, PanelKind::Draw, PanelKind::Bonus]);
+    assert_eq!((map.width, map.height), (2, 2));
+    assert_eq!(map.get((1, 1)), Some(2));
+    // Encounter is not in the tileset, and (5, 5) is off the map
+    assert_eq!(map.get((0, 1)), None);
+    assert_eq!(map.get((5, 5)), None);
+
+    assert_eq!(map.edges.len(), 4);
+    assert_eq!(
+        map.edges_from((1, 0)).collect::<Vec<_>>(),
+        vec![
+            &Edge { from: (1, 0).into(), to: (0, 0).into(), backtrack: true },
+            &Edge { from: (1, 0).into(), to: (1, 1).into(), backtrack: false },
+        ],
+    );
+}
+
+#[test]
+fn test_direction() {
+    use crate::{Direction::*, Exits, Field, Panel, PanelKind, Pos};
+
+    assert_eq!([North.delta(), South.delta(), East.delta(), West.delta()], [(0, -1), (0, 1), (1, 0), (-1, 0)]);
+    assert_eq!([North.opposite(), East.opposite()], [South, West]);
+    assert_eq!([North.clockwise(), North.counterclockwise()], [East, West]);
+    assert_eq!(Exits::from(West) | South.into(), Exits::WEST | Exits::SOUTH);
+
+    let center = Pos::new(1, 1);
+    assert_eq!(center.step(North), Some(Pos::new(1, 0)));
+    assert_eq!(center.step(West).and_then(|pos| pos.step(West)), None);
+
+    let mut field = Field::new_vec(vec![Panel::new(PanelKind::Empty); 9], 3, 3);
+    field.get_mut((2, 1)).kind = PanelKind::Bonus;
+
+    assert_eq!(field.get((1, 1)).neighbor(East).unwrap().kind, PanelKind::Bonus);
+    assert!(field.get((2, 1)).neighbor(East).is_none());
+
+    field.get_mut((1, 1)).neighbor(South).unwrap().kind = PanelKind::Draw;
+    assert_eq!(field.get((1, 2)).kind, PanelKind::Draw);
+}
+
+#[test]
+fn test_exits_ops() {
+    use crate::{Direction, Exits};
+
+    let exits = Exits::NORTH | Exits::EAST;
+
+    assert_eq!(exits & Exits::EAST, Exits::EAST);
+    assert!((exits & Exits::SOUTH).is_empty());
+    assert_eq!(exits ^ Exits::all(), Exits::SOUTH | Exits::WEST);
+    assert_eq!(!exits | exits, Exits::all());
+    assert_eq!(!Exits::all(), Exits::none());
+    assert_eq!(Exits::all().count(), 4);
+
+    let mut removed = exits;
+    removed.remove(Exits::NORTH | Exits::SOUTH);
+    assert_eq!(removed, Exits::EAST);
+
+    assert_eq!(Exits::all().directions().collect::<Vec<_>>(), Direction::ALL.to_vec());
+    assert_eq!(exits.directions().fold(Exits::none(), |out, dir| out | dir.into()), exits);
+}
+
+#[test]
+fn test_exits_text() {
+    use crate::{Direction, Exits};
+    use crate::format::csv;
+    use crate::panel::InvalidDirection;
+    use std::convert::TryFrom;
+
+    assert_eq!(Exits::all().to_string(), "N|E|S|W");
+    assert_eq!(format!("{:?}", Exits::all()), "Exits(WEST | NORTH | EAST | SOUTH)");
+
+    for bits in 0..16 {
+        let exits = [Exits::WEST, Exits::NORTH, Exits::EAST, Exits::SOUTH].iter()
+            .enumerate()
+            .filter(|(idx, _)| bits & (1 << idx) != 0)
+            .fold(Exits::none(), |out, (_, dir)| out | *dir);
+
+        assert_eq!(exits.to_string().parse::<Exits>(), Ok(exits));
+    }
+
+    assert_eq!(" South |e ".parse::<Exits>(), Ok(Exits::SOUTH | Exits::EAST));
+    assert_eq!("N|up".parse::<Exits>(), Err(InvalidDirection { value: "up".into() }));
+    assert!("N||S".parse::<Exits>().is_err());
+
+    // every format reads and writes directions the same way
+    for dir in Direction::ALL.iter().copied() {
+        assert_eq!(dir.name().parse::<Direction>(), Ok(dir));
+        assert_eq!(Direction::try_from(dir.letter().to_ascii_lowercase()), Ok(dir));
+        assert_eq!(dir.name().chars().next().map(|c| c.to_ascii_uppercase()), Some(dir.letter()));
+        assert_eq!(Exits::from(dir).to_string(), dir.letter().to_string());
+    }
+
+    let field = crate::field![[Home + NORTH + WEST]];
+    let mut data = Vec::new();
+    csv::encode_exits(&field, &mut data).unwrap();
+    assert_eq!(String::from_utf8(data).unwrap().trim(), "NW");
+}
+
+#[test]
+fn test_panel_kind_helpers() {
+    let kinds = PanelKind::KNOWN;
+
+    assert_eq!(kinds.iter().filter(|kind| kind.is_2x()).count(), 8);
+
+    for kind in kinds {
+        let base = kind.base_kind();
+
+        assert!(!base.is_2x());
+        assert_eq!(kind.multiplier(), if kind == base { 1 } else { 2 });
+        assert_eq!(kind.is_warp(), base.is_warp());
+        assert_eq!(kind.affects_stars(), base.affects_stars());
+        assert!(!kind.is_warp() || kind.is_movement());
+    }
+
+    assert!(PanelKind::Move2x.is_movement() && !PanelKind::Move2x.is_warp());
+    assert!(PanelKind::Drop2x.affects_stars() && !PanelKind::Encounter.affects_stars());
+}
+
+#[test]
+fn test_panel_kind_names() {
+    use crate::panel::InvalidKind;
+
+    for kind in PanelKind::KNOWN.iter().copied() {
+        assert_eq!(kind.to_string().parse::<PanelKind>(), Ok(kind));
+        assert_eq!(format!("{:?}", kind).parse::<PanelKind>(), Ok(kind));
+        assert_eq!(kind.to_string().to_uppercase().parse::<PanelKind>(), Ok(kind));
+    }
+
+    assert_eq!(PanelKind::Bonus2x.to_string(), "Bonus 2x");
+    assert_eq!(" encounter-x2 ".parse::<PanelKind>(), Ok(PanelKind::Encounter2x));
+    assert_eq!("none".parse::<PanelKind>(), Ok(PanelKind::Empty));
+    assert_eq!(PanelKind::Unknown(0x2A).to_string(), "Unknown 0x2A");
+    assert_eq!("unknown 0x2a".parse::<PanelKind>(), Ok(PanelKind::Unknown(0x2A)));
+    // known bytes are never unknown
+    assert_eq!("Unknown 0x05".parse::<PanelKind>(), Ok(PanelKind::Bonus));
+    assert_eq!("Bonus 3x".parse::<PanelKind>(), Err(InvalidKind { value: "Bonus 3x".into() }));
+}
+
+#[test]
+fn test_panel_kind_text() {
+    use crate::format::csv;
+    use crate::render::ascii;
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+
+    let chars: HashSet<char> = PanelKind::KNOWN.iter().map(|kind| kind.to_char()).collect();
+    let abbrevs: HashSet<&str> = PanelKind::KNOWN.iter().map(|kind| kind.abbreviation()).collect();
+    assert_eq!((chars.len(), abbrevs.len()), (22, 22));
+
+    for kind in PanelKind::KNOWN.iter().copied() {
+        assert_eq!(PanelKind::try_from(kind.to_char()), Ok(kind));
+        assert_eq!(PanelKind::from_abbreviation(kind.abbreviation()), Some(kind));
+        assert_eq!(kind.abbreviation().chars().count(), 2);
+    }
+
+    assert_eq!(PanelKind::Unknown(0xF0).to_char(), '?');
+    assert!(PanelKind::try_from('?').is_err());
+    assert_eq!(PanelKind::from_abbreviation("??"), None);
+
+    // kinds the renderer once drew as `??` read back in every text format
+    let field = crate::field![[Ice + EAST, Heal2x + SOUTH], [Empty, Damage]];
+    assert_eq!(field.to_string(), "\nic>HE\n   \\/ \n   dm\n  ");
+    assert_eq!(ascii::parse(&field.to_string()).unwrap(), field);
+
+    let mut data = Vec::new();
+    csv::encode(&field, &mut data).unwrap();
+    assert_eq!(csv::decode(&data[..]).unwrap(), field);
+}
+
+#[test]
+fn test_panel_category() {
+    use crate::panel::PanelCategory;
+
+    for kind in PanelKind::KNOWN.iter().copied() {
+        assert_eq!(kind.category(), kind.base_kind().category());
+        assert_eq!(kind.is_movement(), kind.category() == PanelCategory::Movement && kind != PanelKind::Ice);
+
+        if kind.affects_stars() {
+            assert!(matches!(kind.category(), PanelCategory::Gain | PanelCategory::Loss));
+        }
+    }
+
+    assert_eq!(PanelKind::Encounter2x.category(), PanelCategory::Battle);
+    assert_eq!(PanelKind::Damage.category(), PanelCategory::Loss);
+    assert_eq!(PanelKind::Empty.category(), PanelCategory::Structural);
+}
+
+#[test]
+fn test_templates() {
+    use crate::analysis::{components, dead_ends, laps, Connectivity};
+    use crate::gen::templates;
+
+    for (width, height) in &[(6, 6), (7, 9), (10, 8)] {
+        let shapes = [
+            templates::ring(*width, *height),
+            templates::double_ring(*width, *height),
+            templates::spiral(*width, *height),
+            templates::plus(*width, *height),
+            templates::figure_eight(*width, *height),
+        ];
+
+        for field in shapes.iter() {
+            assert_eq!((field.width(), field.height()), (*width, *height));
+            assert_eq!(components(field, Connectivity::Strong).len(), 1);
+            assert!(dead_ends(field).is_empty());
+        }
+    }
+
+    // the spiral covers the whole field
+    assert!(templates::spiral(5, 4).panels().all(|(_, _, panel)| panel.kind == PanelKind::Neutral));
+
+    let mut ring = templates::ring(4, 3);
+    ring[crate::Pos::new(0, 0)].kind = PanelKind::Home;
+    assert_eq!(laps(&ring).laps, vec![(crate::Pos::new(0, 0), Some(10))]);
+
+    assert_eq!(templates::plus(4, 4).panels().filter(|(_, _, panel)| panel.kind != PanelKind::Empty).count(), 12);
+}
+
+#[test]
+fn test_export_dot() {
+    let mut field = crate::field![[Home + EAST + NORTH, Draw + SOUTH], [Empty, Bonus]];
+    field.get_mut((1, 0)).exits_backtrack |= crate::Exits::WEST;
+
+    let dot = crate::export::dot(&field);
+
+    assert_eq!(dot.lines().filter(|line| line.contains("label=")).count(), 3);
+    assert!(dot.contains("p0_0 [label=\"Home\\n(0, 0)\", pos=\"0,144!\"];"));
+    assert!(dot.contains("p0_0 -> p1_0;"));
+    assert!(dot.contains("p1_0 -> p1_1;"));
+    assert!(dot.contains("p1_0 -> p0_0 [style=dashed];"));
+    assert_eq!(dot.matches("->").count(), 3);
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+fn test_graph() {
+    use petgraph::algo::kosaraju_scc;
+
+    let field = crate::field![
+        [Home + EAST, Draw + SOUTH + EAST],
+        [Bonus + NORTH, Drop + WEST],
+    ];
+
+    let graph = field.to_graph();
+    assert_eq!(graph.node_count(), 4);
+    assert_eq!(graph.edge_count(), 4);
+    assert_eq!(kosaraju_scc(&graph).len(), 1);
+
+    // edges are weighted with the direction they lead in
+    let home = graph.node_indices().find(|idx| graph[*idx] == crate::Pos::new(0, 0)).unwrap();
+    let edges = graph.edges(home).map(|edge| *edge.weight()).collect::<Vec<_>>();
+    assert_eq!(edges, [crate::Direction::East]);
+
+    // a round trip keeps every exit that leads to a panel
+    let mut copy = field.clone();
+    copy.get_mut((1, 0)).exits = crate::Exits::none();
+    copy.set_exits_from_graph(&graph);
+    assert_eq!(copy, crate::field![
+        [Home + EAST, Draw + SOUTH],
+        [Bonus + NORTH, Drop + WEST],
+    ]);
+}
+
 #[test]
 fn test_fld_read() {
     use crate::format::fld;
     use std::io::Cursor;
 
-    // use Training Program as our test field
-    const TRAINING_PROGRAM: &'static [u8] = include_bytes!("field_training.fld");
+    // use Training Program as our test field
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fld");
+
+    fld::decode(fld::S15, Cursor::new(TRAINING_PROGRAM))
+        .unwrap();
+}
+
+#[cfg(feature = "base64")]
+#[test]
+fn test_fld_share_code() {
+    use crate::format::fld;
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fld");
+
+    let field = fld::decode(fld::S15, Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    // share codes carry their dimensions, and survive surrounding whitespace
+    let code = fld::encode_share_code(&field).unwrap();
+    let decoded = fld::decode_share_code(&format!(" {}\n", code)).unwrap();
+
+    assert_eq!((decoded.width(), decoded.height()), fld::S15);
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+
+    // malformed codes are rejected
+    assert!(fld::decode_share_code("").is_err());
+    assert!(fld::decode_share_code("not a share code!").is_err());
+    assert!(fld::decode_share_code(&code[..code.len() / 2]).is_err());
+
+    // a 2x1 field with only one panel
+    let mut data = vec![fld::SHARE_CODE_VERSION, 2, 0, 1, 0];
+    data.extend_from_slice(&[0; 8]);
+    assert!(matches!(
+        fld::decode_share_code(&base64::encode_config(&data, base64::URL_SAFE)),
+        Err(DecodeError::SizeMismatch { expected: 2, got: 1, .. }),
+    ));
+
+    data[0] += 1;
+    match fld::decode_share_code(&base64::encode_config(&data, base64::URL_SAFE)) {
+        Err(DecodeError::UnsupportedVersion { version, .. }) => {
+            assert_eq!(version, fld::SHARE_CODE_VERSION as u32 + 1);
+        }
+        other => panic!("expected an unsupported version, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_fldx_read() {
+    use crate::format::fldx;
+    use std::io::Cursor;
+
+    // use Training Program as our test field
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    fldx::decode(Cursor::new(TRAINING_PROGRAM))
+        .unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_roundtrip() {
+    use crate::Exits;
+
+    let mut field = crate::field![
+        [Home + EAST, Encounter + WEST + SOUTH],
+        [Empty, Bonus + NORTH],
+    ];
+    field.get_mut((1, 1)).kind = PanelKind::Unknown(0xF0);
+    field.build_backtrack();
+
+    let data = serde_json::to_string(&field).unwrap();
+    let decoded: Field = serde_json::from_str(&data).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (2, 2));
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+
+    let panel = field.get((1, 0)).clone();
+    let panel_data = serde_json::to_string(&panel).unwrap();
+    assert!(serde_json::from_str::<Panel>(&panel_data).unwrap() == panel);
+
+    for kind in &[PanelKind::Draw, PanelKind::Unknown(0xF0)] {
+        let data = serde_json::to_string(kind).unwrap();
+        assert_eq!(serde_json::from_str::<PanelKind>(&data).unwrap(), *kind);
+    }
+
+    // exits are written as direction names
+    let exits = Exits::NORTH | Exits::WEST;
+    assert_eq!(serde_json::to_string(&exits).unwrap(), r#"["west","north"]"#);
+    assert_eq!(serde_json::from_str::<Exits>(r#"["north","west"]"#).unwrap(), exits);
+    assert!(serde_json::from_str::<Exits>(r#"["up"]"#).is_err());
+
+    // the panel count must match the dimensions
+    assert!(serde_json::from_str::<Field>(r#"{"width":2,"height":2,"panels":[]}"#).is_err());
+
+    // the product of the dimensions would overflow to two panels
+    let data = format!(
+        r#"{{"width":{},"height":2,"panels":[{},{}]}}"#,
+        usize::MAX / 2 + 2, panel_data, panel_data,
+    );
+    let error = serde_json::from_str::<Field>(&data).err().unwrap();
+    assert!(error.to_string().contains("too large"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_roundtrip() {
+    use crate::format::{fldx, json};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let data = json::encode_string(&field).unwrap();
+    let decoded = json::decode_str(&data).unwrap();
+
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml_roundtrip() {
+    use crate::format::{fldx, yaml};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let mut field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+    field.build_backtrack();
+
+    let data = yaml::encode_string(&field).unwrap();
+    let decoded = yaml::decode_str(&data).unwrap();
+
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+}
+
+#[test]
+fn test_csv_roundtrip() {
+    use crate::format::{fldx, csv};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let mut field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+    field.build_backtrack();
+
+    let mut data = Vec::new();
+    csv::encode(&field, &mut data).unwrap();
+    let decoded = csv::decode(Cursor::new(data)).unwrap();
+
+    assert_eq!(field.width(), decoded.width());
+    assert_eq!(field.height(), decoded.height());
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+
+    // rows of a single empty cell are not mistaken for the end of a section
+    let field = crate::field![[Empty], [Home + SOUTH], [Draw]];
+
+    let mut data = Vec::new();
+    csv::encode(&field, &mut data).unwrap();
+    assert_eq!(csv::decode(Cursor::new(&data)).unwrap(), field);
+
+    let (mut kinds, mut exits) = (Vec::new(), Vec::new());
+    csv::encode_kinds(&field, &mut kinds).unwrap();
+    csv::encode_exits(&field, &mut exits).unwrap();
+    assert_eq!(csv::decode_separate(&kinds[..], &exits[..]).unwrap(), field);
+
+    // unknown kinds are written as their value
+    let mut field = crate::field![[Home, Draw]];
+    field.get_mut((1, 0)).kind = PanelKind::Unknown(0xFE);
+
+    let mut kinds = Vec::new();
+    csv::encode_kinds(&field, &mut kinds).unwrap();
+    assert_eq!(kinds, b"@@,254\n");
+    assert_eq!(csv::decode(&kinds[..]).unwrap(), field);
+}
+
+#[test]
+fn test_detect() {
+    use crate::format::{self, Format};
+
+    const TRAINING_PROGRAM_FLD: &[u8] = include_bytes!("field_training.fld");
+    const TRAINING_PROGRAM_FLDX: &[u8] = include_bytes!("field_training.fldx");
+
+    assert_eq!(format::detect_bytes(TRAINING_PROGRAM_FLD), Format::Fld);
+    assert_eq!(format::detect_bytes(TRAINING_PROGRAM_FLDX), Format::Fldx);
+    assert_eq!(format::detect_bytes(b"not a field"), Format::Unknown);
+}
+
+#[test]
+fn test_registry() {
+    use crate::format::{fld::Fld, FieldFormat, Registry};
+    use std::io::{Read, Write, Error};
+
+    const TRAINING_PROGRAM_FLD: &[u8] = include_bytes!("field_training.fld");
+    const TRAINING_PROGRAM_FLDX: &[u8] = include_bytes!("field_training.fldx");
+
+    /// A format that recognizes anything starting with a `!`.
+    struct Bang(&'static str);
+
+    impl FieldFormat for Bang {
+        fn name(&self) -> &str { self.0 }
+        fn extensions(&self) -> &[&str] { &["bang"] }
+        fn sniff(&self, data: &[u8]) -> bool { data.starts_with(b"!") }
+
+        fn encode(&self, _: &Field, output: &mut dyn Write) -> Result<(), Error> {
+            output.write_all(b"!")
+        }
+
+        fn decode(&self, _: &mut dyn Read) -> Result<Field, DecodeError> {
+            Ok(Field::default())
+        }
+    }
+
+    let mut registry = Registry::default();
+
+    // builtin formats are detected in order
+    assert_eq!(registry.iter().map(|f| f.name()).collect::<Vec<_>>(), ["fldx", "fld"]);
+    assert_eq!(registry.sniff(TRAINING_PROGRAM_FLDX).map(|f| f.name()), Some("fldx"));
+    assert_eq!(registry.sniff(TRAINING_PROGRAM_FLD).map(|f| f.name()), Some("fld"));
+    assert_eq!(registry.decode(TRAINING_PROGRAM_FLD).unwrap().width(), 15);
+
+    // unknown extensions and data are not matched
+    assert_eq!(registry.by_extension("FLDX").map(|f| f.name()), Some("fldx"));
+    assert!(registry.by_extension("bang").is_none());
+    assert!(registry.by_extension(".fld").is_none());
+    assert!(registry.sniff(b"!").is_none());
+    assert!(matches!(registry.decode(b"!"), Err(DecodeError::InvalidData { offset: 0, .. })));
+
+    // formats registered later are only found when nothing earlier matches
+    registry.register(Fld::new((1, 1)));
+    registry.register(Bang("first"));
+    registry.register(Bang("second"));
+
+    assert_eq!(registry.iter().len(), 5);
+    assert_eq!(registry.by_name("second").map(|f| f.name()), Some("second"));
+    assert_eq!(registry.sniff(TRAINING_PROGRAM_FLD).map(|f| f.name()), Some("fld"));
+    assert_eq!(registry.sniff(&TRAINING_PROGRAM_FLD[..8]).map(|f| f.name()), Some("fld"));
+    assert_eq!(registry.sniff(b"!").map(|f| f.name()), Some("first"));
+    assert_eq!(registry.by_extension("bang").map(|f| f.name()), Some("first"));
+    assert_eq!(registry.decode(b"!").unwrap().width(), 0);
+}
+
+#[test]
+fn test_fldx_metadata() {
+    use crate::annotate::{Annotation, FieldAnnotations};
+    use crate::format::fldx::{self, Metadata};
+    use crate::Pos;
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
 
-    fld::decode(fld::S15, Cursor::new(TRAINING_PROGRAM))
-        .unwrap();
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let mut annotations = FieldAnnotations::new();
+    annotations.entry(Pos::new(3, 1)).warp_target = Some(Pos::new(0, 4));
+    annotations.entry(Pos::new(0, 4)).region = Some("west loop".to_owned());
+    annotations.insert(Pos::new(2, 2), Annotation {
+        note: Some("start here".to_owned()),
+        ..Annotation::default()
+    });
+
+    let metadata = Metadata {
+        name: Some("Training Program".to_owned()),
+        author: Some("Fruitbat Factory".to_owned()),
+        description: None,
+        created: Some(1_500_000_000),
+        annotations,
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with_metadata(&field, &metadata, &mut data).unwrap();
+
+    let (decoded, decoded_metadata) = fldx::decode_with_metadata(Cursor::new(data)).unwrap();
+
+    assert_eq!(metadata, decoded_metadata);
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
 }
 
 #[test]
-fn test_fldx_read() {
+fn test_fldx_checksum() {
+    use crate::format::fldx::{self, EncodeOptions};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let options = EncodeOptions {
+        checksum: true,
+        ..EncodeOptions::default()
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &options, &mut data).unwrap();
+
+    // the intact data decodes
+    fldx::decode(Cursor::new(&data)).unwrap();
+
+    // truncated data does not
+    data.truncate(data.len() - 7);
+    match fldx::decode(Cursor::new(&data)) {
+        Err(e) => assert!(matches!(e, DecodeError::ChecksumMismatch { .. })),
+        Ok(_) => panic!("truncated data decoded"),
+    }
+}
+
+#[cfg(feature = "deflate")]
+#[test]
+fn test_fldx_compressed() {
+    use crate::format::fldx::{self, EncodeOptions};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let options = EncodeOptions {
+        checksum: true,
+        compress: true,
+        ..EncodeOptions::default()
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &options, &mut data).unwrap();
+    assert!(data.len() < TRAINING_PROGRAM.len());
+
+    let decoded = fldx::decode(Cursor::new(&data)).unwrap();
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+}
+
+#[test]
+fn test_fldpak_reader() {
+    use crate::format::{fld, fldpak::{self, Pack, PackReader}};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fld");
+
+    let field = fld::decode(fld::S15, Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let mut pack = Pack::new();
+    pack.insert("Training Program", field.clone());
+    pack.insert("Tiny", Field::new_slice(&[&[Panel::new(PanelKind::Home)]]));
+
+    let mut data = Vec::new();
+    fldpak::encode(&pack, &mut data).unwrap();
+
+    let mut reader = PackReader::new(Cursor::new(data)).unwrap();
+    assert_eq!(reader.names().collect::<Vec<_>>(), ["Training Program", "Tiny"]);
+
+    let decoded = reader.read("Training Program").unwrap().unwrap();
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+    assert!(reader.read("Missing").unwrap().is_none());
+
+    // two fields with the same name are rejected, not silently merged
+    let mut pack = Pack::new();
+    pack.insert("a", Field::new_slice(&[&[Panel::new(PanelKind::Home)]]));
+    pack.insert("b", Field::new_slice(&[&[Panel::new(PanelKind::Draw)]]));
+
+    let mut data = Vec::new();
+    fldpak::encode(&pack, &mut data).unwrap();
+    assert_eq!(data[20], b'b');
+    data[20] = b'a';
+
+    match fldpak::decode(&data[..]) {
+        Err(DecodeError::InvalidData { offset, .. }) => assert_eq!(offset, 18),
+        _ => panic!("duplicate names decoded"),
+    }
+    assert!(PackReader::new(Cursor::new(&data)).is_err());
+
+    // offsets past 4 GiB fail instead of being truncated
+    let end = u32::MAX as usize;
+    assert_eq!(fldpak::layout(end - 10, &[4, 6]).unwrap(), [((end - 10) as u32, 4), ((end - 6) as u32, 6)]);
+    assert!(fldpak::layout(end - 10, &[4, 7]).is_err());
+    assert!(fldpak::layout(end + 1, &[0]).is_err());
+}
+
+#[test]
+fn test_fldx_run_length() {
+    use crate::format::fldx::{self, EncodeOptions};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let options = EncodeOptions {
+        run_length: true,
+        ..EncodeOptions::default()
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &options, &mut data).unwrap();
+    assert!(data.len() < TRAINING_PROGRAM.len());
+
+    let decoded = fldx::decode(Cursor::new(&data)).unwrap();
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+}
+
+#[test]
+fn test_fldx_big_endian() {
+    use crate::format::{fldx::{self, EncodeOptions, Metadata}, Endian};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let options = EncodeOptions {
+        endian: Endian::Big,
+        checksum: true,
+        metadata: Metadata {
+            created: Some(1_500_000_000),
+            ..Metadata::default()
+        },
+        ..EncodeOptions::default()
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &options, &mut data).unwrap();
+
+    // version 2 files record their byte order
+    let (decoded, metadata) = fldx::decode_with_metadata(Cursor::new(&data)).unwrap();
+    assert_eq!(metadata, options.metadata);
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+}
+
+#[test]
+fn test_fldset_roundtrip() {
+    use crate::FieldSet;
+    use crate::format::fldset;
+    use std::io::Cursor;
+    use PanelKind::*;
+
+    let mut set = FieldSet::new("day", Field::new_slice(&[
+        &[Panel::new(Home), Panel::new(Bonus)],
+    ]));
+    let night = set.push_state("night", Field::new_slice(&[
+        &[Panel::new(Home), Panel::new(Encounter)],
+        &[Panel::new(Drop), Panel::new(Empty)],
+    ]));
+    set.add_transition(0, night, "sunset");
+    set.add_transition(night, 0, "sunrise");
+    set.switch_to(night);
+
+    let mut data = Vec::new();
+    fldset::encode(&set, &mut data).unwrap();
+    let mut decoded = fldset::decode(Cursor::new(data)).unwrap();
+
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded.current().name, "night");
+    assert_eq!(decoded.current().field.get((0, 1)).kind, Drop);
+    assert_eq!(decoded.transitions().cloned().collect::<Vec<_>>(), set.transitions().cloned().collect::<Vec<_>>());
+
+    decoded.transition("sunrise").unwrap();
+    assert_eq!(decoded.current().field.get((1, 0)).kind, Bonus);
+}
+
+#[test]
+fn test_patch_roundtrip() {
+    use crate::format::{fldx, patch::{self, Patch}};
+    use crate::Exits;
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let old = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let mut new = old.clone();
+    new.get_mut((3, 4)).kind = PanelKind::Bonus2x;
+    new.get_mut((14, 14)).exits = Exits::NORTH | Exits::WEST;
+
+    let mut data = Vec::new();
+    patch::encode(&Patch::diff(&old, &new), &mut data).unwrap();
+    assert!(data.len() < TRAINING_PROGRAM.len());
+
+    let patch = patch::decode(Cursor::new(data)).unwrap();
+
+    let mut field = old.clone();
+    patch.apply(&mut field).unwrap();
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *new.get((x, y))));
+
+    // the patch no longer applies to the patched field
+    assert!(!patch.applies_to(&field));
+    assert!(patch.apply(&mut field).is_err());
+}
+
+#[test]
+fn test_sharecode() {
+    use crate::format::{fldx, sharecode};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let code = sharecode::encode(&field).unwrap();
+    let decoded = sharecode::decode(&code).unwrap();
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *decoded.get((x, y))));
+
+    // codes from the future are rejected
+    let future = code.replacen("OJ1-", "OJ2-", 1);
+    match sharecode::decode(&future) {
+        Err(e) => assert!(matches!(e, DecodeError::UnsupportedVersion { version: 2, .. })),
+        Ok(_) => panic!("future share code decoded"),
+    }
+
+    // absurd dimensions are rejected before any panels are allocated
+    match sharecode::decode("OJ1-G2081008G2081008G208104040000") {
+        Err(e) => assert!(matches!(e, DecodeError::TooLarge { .. })),
+        Ok(_) => panic!("absurd share code decoded"),
+    }
+}
+
+#[test]
+fn test_fldx_view() {
+    use crate::annotate::FieldAnnotations;
+    use crate::format::fldx::{self, EncodeOptions, FieldView, Metadata};
+    use crate::Pos;
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let view = FieldView::new(TRAINING_PROGRAM).unwrap();
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == view.get(x, y)));
+
+    // version 2 files with metadata can be viewed too
+    let options = EncodeOptions {
+        checksum: true,
+        metadata: Metadata {
+            name: Some("Training Program".to_owned()),
+            ..Metadata::default()
+        },
+        ..EncodeOptions::default()
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &options, &mut data).unwrap();
+
+    let view = FieldView::new(&data).unwrap();
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == view.get(x, y)));
+
+    // and so can files with annotations
+    let mut annotations = FieldAnnotations::new();
+    annotations.entry(Pos::new(3, 1)).warp_target = Some(Pos::new(0, 4));
+    annotations.entry(Pos::new(0, 4)).note = Some("start here".to_owned());
+
+    let options = EncodeOptions {
+        metadata: Metadata {
+            annotations,
+            ..Metadata::default()
+        },
+        ..EncodeOptions::default()
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &options, &mut data).unwrap();
+
+    let view = FieldView::new(&data).unwrap();
+    assert_eq!((view.width(), view.height()), (field.width(), field.height()));
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == view.get(x, y)));
+}
+
+#[test]
+fn test_fldx_decoder() {
+    use crate::format::fldx::{self, Decoder, EncodeOptions};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let options = EncodeOptions {
+        checksum: true,
+        run_length: true,
+        ..EncodeOptions::default()
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &options, &mut data).unwrap();
+
+    for data in [TRAINING_PROGRAM, &data[..]].iter() {
+        let decoder = Decoder::new(Cursor::new(data)).unwrap();
+        let mut count = 0;
+
+        for panel in decoder {
+            let (x, y, panel) = panel.unwrap();
+            assert!(*field.get((x, y)) == panel);
+            count += 1;
+        }
+
+        assert_eq!(count, field.width() * field.height());
+    }
+}
+
+#[test]
+fn test_fldx_decoder_agrees() {
+    use crate::format::DecodeOptions;
+    use crate::format::fldx::{self, Decoder, EncodeOptions};
+
+    let field = crate::field![[Home, Draw]];
+
+    let encode = |version, run_length| {
+        let options = EncodeOptions {
+            version,
+            run_length,
+            ..EncodeOptions::default()
+        };
+
+        let mut data = Vec::new();
+        fldx::encode_with(&field, &options, &mut data).unwrap();
+        data
+    };
+
+    let stream = |data: &[u8], options: &DecodeOptions| -> Result<Field, DecodeError> {
+        let decoder = Decoder::with_options(data, options)?;
+        let (width, height) = (decoder.width(), decoder.height());
+        let panels = decoder
+            .map(|panel| panel.map(|(_, _, panel)| panel))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Field::new_vec(panels, width, height))
+    };
+
+    let v1 = encode(1, false);
+    let v2 = encode(2, false);
+    let rle = encode(2, true);
+
+    // trailing data after a version 1 file is a panel too many, but after a
+    // version 2 file with no flags it is only rejected when strict
+    let mut v1_extra = v1.clone();
+    v1_extra.extend_from_slice(&v1[4..6]);
+    let mut v2_extra = v2.clone();
+    v2_extra.push(0);
+
+    // runs of no panels are skipped
+    let mut rle_empty = rle[..8].to_vec();
+    rle_empty.extend_from_slice(&[0, rle[9], rle[10]]);
+    rle_empty.extend_from_slice(&rle[8..]);
+
+    let strict = DecodeOptions {
+        strict: true,
+        ..DecodeOptions::default()
+    };
+
+    for options in [DecodeOptions::default(), strict].iter() {
+        for data in [&v1, &v1_extra, &v2, &v2_extra, &rle, &rle_empty].iter() {
+            let whole = fldx::decode_with(&data[..], options);
+            let streamed = stream(data, options);
+
+            assert_eq!(format!("{:?}", streamed), format!("{:?}", whole));
+        }
+    }
+
+    assert!(fldx::decode(&v1_extra[..]).is_err());
+    assert_eq!(stream(&v2_extra, &DecodeOptions::default()).unwrap(), field);
+    assert_eq!(stream(&rle_empty, &DecodeOptions::default()).unwrap(), field);
+}
+
+
+#[test]
+fn test_decode_error_offsets() {
     use crate::format::fldx;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    // break the kind of the third panel
+    let mut data = TRAINING_PROGRAM.to_vec();
+    data[4 + 2 * 2] = 0xFF;
+
+    match fldx::decode(&data[..]) {
+        Err(DecodeError::UnknownPanelKind { offset, value }) => {
+            assert_eq!(offset, 8);
+            assert_eq!(value, 0xFF);
+        },
+        _ => panic!("unknown panel kind decoded"),
+    }
+
+    // cut the data off in the middle of a panel
+    match fldx::decode(&TRAINING_PROGRAM[..9]) {
+        Err(e) => assert_eq!(e.offset(), Some(9)),
+        Ok(_) => panic!("truncated data decoded"),
+    }
+}
+
+#[test]
+fn test_lenient_decode() {
+    use crate::format::{fld, fldx, patch::{self, Patch}, DecodeOptions};
     use std::io::Cursor;
 
-    // use Training Program as our test field
-    const TRAINING_PROGRAM: &'static [u8] = include_bytes!("field_training.fldx");
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fld");
 
-    fldx::decode(Cursor::new(TRAINING_PROGRAM))
+    // break the kind of the second panel
+    let mut data = TRAINING_PROGRAM.to_vec();
+    data[8] = 0xFE;
+
+    assert!(fld::decode(fld::S15, Cursor::new(&data)).is_err());
+
+    let options = DecodeOptions {
+        placeholder: Some(PanelKind::Neutral),
+        ..DecodeOptions::default()
+    };
+
+    let (field, substitutions) =
+        fld::decode_with_substitutions(fld::S15, Cursor::new(&data), &options).unwrap();
+
+    assert_eq!(field.get((1, 0)).kind, PanelKind::Neutral);
+    assert_eq!(substitutions.len(), 1);
+    assert_eq!((substitutions[0].x, substitutions[0].y, substitutions[0].offset), (1, 0, 8));
+
+    // runs of unknown panels report every panel
+    let mut data = Vec::new();
+    let field = Field::new_vec(vec![Panel::new(PanelKind::Draw); 6], 3, 2);
+    let encode_options = fldx::EncodeOptions {
+        run_length: true,
+        ..fldx::EncodeOptions::default()
+    };
+    fldx::encode_with(&field, &encode_options, &mut data).unwrap();
+
+    let kind = data.iter().rposition(|b| *b == u8::from(PanelKind::Draw)).unwrap();
+    data[kind] = 0xFE;
+
+    let (field, substitutions) = fldx::decode_with_substitutions(&data[..], &options).unwrap();
+    assert!(field.iter().all(|(x, y)| field.get((x, y)).kind == PanelKind::Neutral));
+    assert_eq!(substitutions.len(), 6);
+    assert_eq!((substitutions[5].x, substitutions[5].y), (2, 1));
+
+    // unknown kinds can be kept instead, and encode back to the same bytes
+    let keep = DecodeOptions { keep_unknown: true, ..options };
+    let (field, substitutions) = fldx::decode_with_substitutions(&data[..], &keep).unwrap();
+    assert!(field.iter().all(|(x, y)| field.get((x, y)).kind == PanelKind::Unknown(0xFE)));
+    assert!(substitutions.is_empty());
+
+    let mut encoded = Vec::new();
+    fldx::encode_with(&field, &encode_options, &mut encoded).unwrap();
+    assert_eq!(encoded, data);
+
+    // patches that change panels to unknown kinds follow the same options
+    let old = Field::new_vec(vec![Panel::new(PanelKind::Draw); 2], 2, 1);
+    let mut data = Vec::new();
+    patch::encode(&Patch::diff(&old, &field.subfield(0, 0, 2, 1)), &mut data).unwrap();
+
+    assert!(matches!(
+        patch::decode(&data[..]),
+        Err(DecodeError::UnknownPanelKind { value: 0xFE, .. }),
+    ));
+
+    for (options, kind) in &[(options, PanelKind::Neutral), (keep, PanelKind::Unknown(0xFE))] {
+        let mut patched = old.clone();
+        patch::decode_with(&data[..], options).unwrap().apply(&mut patched).unwrap();
+        assert!(patched.iter().all(|(x, y)| patched.get((x, y)).kind == *kind));
+    }
+}
+
+#[test]
+fn test_decode_limits() {
+    use crate::format::{fldx, patch, DecodeOptions};
+    use std::io::Cursor;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fldx");
+
+    // an absurd header is rejected before any panels are read
+    let mut data = TRAINING_PROGRAM.to_vec();
+    data[0..4].copy_from_slice(&[0xFF; 4]);
+
+    match fldx::decode_with(Cursor::new(&data), &DecodeOptions::untrusted()) {
+        Err(DecodeError::TooLarge { offset }) => assert_eq!(offset, 0),
+        _ => panic!("absurd header decoded"),
+    }
+
+    // padding after a version 2 file is only rejected when strict
+    let field = fldx::decode(Cursor::new(TRAINING_PROGRAM)).unwrap();
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &fldx::EncodeOptions::default(), &mut data).unwrap();
+
+    let len = data.len() as u64;
+    data.extend_from_slice(&[0; 16]);
+
+    fldx::decode(Cursor::new(&data)).unwrap();
+
+    match fldx::decode_with(Cursor::new(&data), &DecodeOptions::untrusted()) {
+        Err(DecodeError::TrailingData { offset }) => assert_eq!(offset, len),
+        _ => panic!("padded data decoded"),
+    }
+
+    // fields embedded in patches are held to the same limits
+    let mut data = b"FPAT\x01\x01\x01\0\0\0\0\x01FLDX\x02\x08".to_vec();
+    data.extend_from_slice(&[0x80, 0x80, 0x80, 0x10, 0x80, 0x80, 0x80, 0x10, 0x01, 0x00]);
+
+    match patch::decode_with(Cursor::new(&data), &DecodeOptions::untrusted()) {
+        Err(DecodeError::TooLarge { offset }) => assert_eq!(offset, 18),
+        _ => panic!("absurd patch decoded"),
+    }
+
+    // so is a huge count of changes, even without limits
+    let mut data = b"FPAT\x01".to_vec();
+    data.extend_from_slice(&[0x80, 0x80, 0x80, 0x80, 0x08, 0x80, 0x80, 0x80, 0x80, 0x08]);
+    data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    data.extend_from_slice(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x10]);
+
+    assert!(matches!(
+        patch::decode(Cursor::new(&data)),
+        Err(DecodeError::UnexpectedEof { .. }),
+    ));
+}
+
+#[test]
+fn test_decode_diagnostics() {
+    use crate::format::{fldx, DecodeOptions, Diagnostics, Warning};
+    use crate::Exits;
+
+    // a hole in the middle of a ring, with an exit into it
+    let mut field = Field::new_vec(vec![Panel::new(PanelKind::Neutral); 9], 3, 3);
+    field.get_mut((1, 1)).kind = PanelKind::Empty;
+    field.get_mut((1, 0)).exits = Exits::SOUTH;
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &fldx::EncodeOptions::default(), &mut data).unwrap();
+
+    let len = data.len() as u64;
+    data.extend_from_slice(&[0; 4]);
+
+    let mut diagnostics = Diagnostics::new();
+    fldx::decode_with_diagnostics(&data[..], &DecodeOptions::default(), &mut diagnostics)
         .unwrap();
+
+    assert_eq!(diagnostics.warnings(), &[
+        Warning::TrailingData { offset: len, len: 4 },
+        Warning::DanglingExit { x: 1, y: 0 },
+        Warning::Hole { x: 1, y: 1 },
+    ]);
+}
+
+#[test]
+fn test_decode_file() {
+    use crate::format::{fld, fldx};
+
+    const DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests");
+
+    let field = fld::decode_file(fld::S15, format!("{}/field_training.fld", DIR)).unwrap();
+    let fieldx = fldx::decode_file(format!("{}/field_training.fldx", DIR)).unwrap();
+
+    assert_eq!(fld::encode_to_vec(&fieldx), fld::encode_to_vec(&field));
+    assert!(fldx::decode_file(format!("{}/missing.fldx", DIR)).is_err());
+}
+
+/// A reader that returns one byte at a time, and is interrupted in between.
+struct Trickle<'a> {
+    data: &'a [u8],
+    interrupt: bool,
+}
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.interrupt = !self.interrupt;
+
+        if self.interrupt {
+            return Err(std::io::ErrorKind::Interrupted.into());
+        }
+
+        match (self.data.split_first(), buf.first_mut()) {
+            (Some((byte, rest)), Some(out)) => {
+                *out = *byte;
+                self.data = rest;
+                Ok(1)
+            },
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn test_partial_reads() {
+    use crate::format::{fld, fldx};
+
+    const TRAINING_PROGRAM_FLD: &[u8] = include_bytes!("field_training.fld");
+    const TRAINING_PROGRAM_FLDX: &[u8] = include_bytes!("field_training.fldx");
+
+    let trickle = |data| Trickle { data, interrupt: false };
+
+    let field = fld::decode(fld::S15, trickle(TRAINING_PROGRAM_FLD)).unwrap();
+    let same = |other: &Field| field.iter().all(|(x, y)| *field.get((x, y)) == *other.get((x, y)));
+
+    assert!(same(&fldx::decode(trickle(TRAINING_PROGRAM_FLDX)).unwrap()));
+
+    let options = fldx::EncodeOptions {
+        checksum: true,
+        run_length: true,
+        ..fldx::EncodeOptions::default()
+    };
+
+    let mut data = Vec::new();
+    fldx::encode_with(&field, &options, &mut data).unwrap();
+
+    assert!(same(&fldx::decode(trickle(&data)).unwrap()));
+
+    let decoder = fldx::Decoder::new(trickle(&data)).unwrap();
+    assert_eq!(decoder.count(), field.width() * field.height());
+}
+
+#[test]
+fn test_fld_candidate_dims() {
+    use crate::format::fld;
+
+    assert_eq!(fld::candidate_dims(15 * 15 * 8)[0], fld::S15);
+    assert_eq!(fld::candidate_dims(12 * 8), vec![(4, 3), (3, 4), (6, 2), (2, 6), (12, 1), (1, 12)]);
+    assert!(fld::candidate_dims(0).is_empty());
+    assert!(fld::candidate_dims(12).is_empty());
+}
+
+#[cfg(feature = "fields")]
+#[test]
+fn test_official_fields() {
+    use crate::format::fld;
+    use crate::official;
+
+    // every embedded field decodes to its dimensions, and is found by name
+    for field in official::iter() {
+        assert_eq!((field.field().width(), field.field().height()), field.dims());
+        assert!(std::ptr::eq(official::get(field.name()).unwrap(), field));
+    }
+
+    let field = official::by_name("training program").unwrap();
+    let fixture = fld::decode(fld::S15, &include_bytes!("field_training.fld")[..]).unwrap();
+    assert!(field.iter().all(|(x, y)| *field.get((x, y)) == *fixture.get((x, y))));
+
+    // only the Training Program fixture is embedded
+    assert_eq!(official::iter().map(|field| field.name()).collect::<Vec<_>>(), ["Training Program"]);
+    assert!(official::get("").is_none());
+    assert!(official::by_name("Training").is_none());
+    assert!(official::by_name("not a field").is_none());
+}
+
+#[test]
+fn test_fld_raw_roundtrip() {
+    use crate::format::fld;
+
+    const TRAINING_PROGRAM: &[u8] = include_bytes!("field_training.fld");
+
+    // scribble over the unused bytes
+    let mut data = TRAINING_PROGRAM.to_vec();
+    for (idx, panel) in data.chunks_mut(8).enumerate() {
+        panel[1] = idx as u8;
+        panel[7] = !(idx as u8);
+    }
+
+    let raw = fld::decode_raw(fld::S15, &data[..]).unwrap();
+    assert_eq!(raw.unused(1, 0), Some([1, 0, 0, 0, 0, !1]));
+
+    let mut out = Vec::new();
+    fld::encode_raw(&raw, &mut out).unwrap();
+    assert_eq!(out, data);
+
+    // the plain encoder still zeroes them
+    out.clear();
+    fld::encode(&raw.field, &mut out).unwrap();
+    assert_eq!(out, TRAINING_PROGRAM);
+}
+
+#[cfg(feature = "proptest")]
+mod props {
+    use crate::Field;
+    use crate::format::{fld, fldx};
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn fldx_round_trips(field in any::<Field>()) {
+            let bytes = fldx::encode_to_vec(&field);
+            prop_assert_eq!(fldx::decode_from_slice(&bytes).unwrap(), field);
+        }
+
+        #[test]
+        fn fld_round_trips(field in any::<Field>()) {
+            let bytes = fld::encode_to_vec(&field);
+            let dims = (field.width(), field.height());
+            prop_assert_eq!(fld::decode_from_slice(dims, &bytes).unwrap(), field);
+        }
+
+        #[test]
+        fn transforms_undo(field in any::<Field>()) {
+            prop_assert_eq!(field.rotate_cw().rotate_ccw(), field.clone());
+            prop_assert_eq!(field.rotate_180().rotate_180(), field.clone());
+            prop_assert_eq!(field.flip_horizontal().flip_horizontal(), field.clone());
+            prop_assert_eq!(field.rotate_cw().canonicalize(), field.canonicalize());
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn test_arbitrary_field() {
+    use crate::field::ARBITRARY_MAX_SIZE;
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let bytes: Vec<u8> = (0..=255).collect();
+    let field = Field::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+
+    assert!((1..=ARBITRARY_MAX_SIZE).contains(&field.width()));
+    assert!((1..=ARBITRARY_MAX_SIZE).contains(&field.height()));
 }
@@ -0,0 +1,115 @@
+//! Describing fields as tilemaps, for game engines.
+//!
+//! A [`Tilemap`] holds nothing engine-specific: a grid of tile indices, into
+//! a tileset the caller picks, and a list of the exits between tiles. This
+//! is meant to be read by whatever glue code places the tiles in an engine.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, PanelKind::{self, *}};
+//! use citrus_common::tilemap::{Edge, Tilemap};
+//!
+//! let field = field![[Home + EAST, Draw], [Empty, Bonus]];
+//! let map = Tilemap::new(&field, &[PanelKind::Home, PanelKind::Draw, PanelKind::Bonus]);
+//!
+//! assert_eq!(map.tiles, vec![Some(0), Some(1), None, Some(2)]);
+//! assert_eq!(map.edges, vec![Edge { from: (0, 0).into(), to: (1, 0).into(), backtrack: false }]);
+//! ```
+
+use crate::{Direction, Field, PanelKind, Pos};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// An exit from one tile to the one next to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Edge {
+    pub from: Pos,
+    pub to: Pos,
+    /// Whether this is a backtrack exit.
+    pub backtrack: bool,
+}
+
+/// A field as a grid of tile indices and a list of edges between tiles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Tilemap {
+    pub width: usize,
+    pub height: usize,
+    /// The index into the tileset of each panel's kind, row-major.
+    ///
+    /// Empty panels, and panels of kinds not in the tileset, have no tile.
+    pub tiles: Vec<Option<usize>>,
+    /// Every exit and backtrack exit between two tiles, in row-major order
+    /// of the tiles they lead from.
+    pub edges: Vec<Edge>,
+}
+
+impl Tilemap {
+    /// Describes a field as a tilemap, where each kind's tile is its index in
+    /// `tileset`.
+    ///
+    /// The tileset is usually the kinds in the order their sprites are laid
+    /// out on a sheet.
+    pub fn new(field: &Field, tileset: &[PanelKind]) -> Tilemap {
+        let tile = |kind: PanelKind| match kind {
+            PanelKind::Empty => None,
+            kind => tileset.iter().position(|other| *other == kind),
+        };
+
+        let tiles: Vec<Option<usize>> = field.panels()
+            .map(|(_, _, panel)| tile(panel.kind))
+            .collect();
+
+        let mut edges = Vec::new();
+
+        for (x, y, panel) in field.panels() {
+            let from = Pos::new(x, y);
+
+            if tiles[y * field.width() + x].is_none() {
+                continue;
+            }
+
+            for dir in Direction::ALL.iter().copied() {
+                let to = match from.step(dir) {
+                    Some(to) if field.contains(to) && tiles[to.y * field.width() + to.x].is_some() => to,
+                    _ => continue,
+                };
+
+                if panel.exits.has(dir.to_exits()) {
+                    edges.push(Edge { from, to, backtrack: false });
+                }
+
+                if panel.exits_backtrack.has(dir.to_exits()) {
+                    edges.push(Edge { from, to, backtrack: true });
+                }
+            }
+        }
+
+        Tilemap {
+            width: field.width(),
+            height: field.height(),
+            tiles,
+            edges,
+        }
+    }
+
+    /// Gets the tile at a position, if there is one.
+    pub fn get(&self, pos: impl Into<Pos>) -> Option<usize> {
+        let pos = pos.into();
+
+        if pos.x < self.width && pos.y < self.height {
+            self.tiles[pos.y * self.width + pos.x]
+        } else {
+            None
+        }
+    }
+
+    /// Gets the edges leading from a tile.
+    pub fn edges_from(&self, pos: impl Into<Pos>) -> impl Iterator<Item = &Edge> + '_ {
+        let pos = pos.into();
+
+        self.edges.iter().filter(move |edge| edge.from == pos)
+    }
+}
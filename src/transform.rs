@@ -0,0 +1,437 @@
+//! Transformations of whole fields.
+//!
+//! Transformations keep panels connected the way they were: exits move along
+//! with their panels, and exits into panels that did not make it into the new
+//! field are removed.
+//!
+//! # Examples
+//! ```
+//! use citrus_common::{field, PanelKind::*};
+//! use citrus_common::transform::Anchor;
+//!
+//! let field = field![
+//!     [Home, Draw],
+//!     [Bonus, Drop],
+//! ];
+//!
+//! let resized = field.resize(3, 1, Anchor::BottomRight);
+//!
+//! assert_eq!(resized.get((0, 0)).kind, Empty);
+//! assert_eq!(resized.get((1, 0)).kind, Bonus);
+//! assert_eq!(resized.get((2, 0)).kind, Drop);
+//! ```
+
+use crate::{Field, Panel, PanelKind, Exits, Pos};
+use crate::Direction;
+use crate::pos::Rect;
+
+/// The part of a field that stays in place when it is resized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Gets where the old content starts in a resized field, as a fraction of
+    /// the change in size: `0` for the start, `1` for the middle and `2` for
+    /// the end.
+    fn halves(self) -> (i64, i64) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
+}
+
+impl Field {
+    /// Creates a resized copy of the field, keeping the `anchor` side of its
+    /// content in place.
+    ///
+    /// Growing the field fills the new space with empty panels, and shrinking
+    /// it crops away the content furthest from the anchor.
+    pub fn resize(&self, width: usize, height: usize, anchor: Anchor) -> Field {
+        let (hx, hy) = anchor.halves();
+
+        let dx = (width as i64 - self.width() as i64) * hx / 2;
+        let dy = (height as i64 - self.height() as i64) * hy / 2;
+
+        self.window(-dx, -dy, width, height)
+    }
+
+    /// Creates a copy of the field cropped to the smallest rectangle holding
+    /// all of its non-empty panels.
+    ///
+    /// Also returns the position of the rectangle's top-left corner on the
+    /// original field. A field with no panels trims down to a `0x0` field.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, Pos, PanelKind::*};
+    ///
+    /// let field = field![
+    ///     [Empty, Empty, Empty],
+    ///     [Empty, Home, Draw],
+    ///     [Empty, Empty, Empty],
+    /// ];
+    ///
+    /// let (trimmed, offset) = field.trim();
+    ///
+    /// assert_eq!(trimmed, field![[Home, Draw]]);
+    /// assert_eq!(offset, Pos::new(1, 1));
+    /// ```
+    pub fn trim(&self) -> (Field, Pos) {
+        let bounds = self.panels()
+            .filter(|(_, _, panel)| panel.kind != PanelKind::Empty)
+            .fold(None, |bounds, (x, y, _)| match bounds {
+                None => Some((x, y, x, y)),
+                Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y))),
+            });
+
+        match bounds {
+            Some((x0, y0, x1, y1)) => (
+                self.window(x0 as i64, y0 as i64, x1 - x0 + 1, y1 - y0 + 1),
+                Pos::new(x0, y0),
+            ),
+            None => (Field::new(), Pos::ORIGIN),
+        }
+    }
+
+    /// Creates a copy of the field with a border of empty panels around it.
+    ///
+    /// The panel at `(x, y)` moves to `(x + left, y + top)`.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    ///
+    /// let field = field![[Home, Draw]];
+    /// let padded = field.pad(1, 1, 1, 1);
+    ///
+    /// assert_eq!((padded.width(), padded.height()), (4, 3));
+    /// assert_eq!(padded.get((1, 1)).kind, Home);
+    /// assert_eq!(padded.get((0, 0)).kind, Empty);
+    /// ```
+    pub fn pad(&self, left: usize, right: usize, top: usize, bottom: usize) -> Field {
+        self.window(
+            -(left as i64),
+            -(top as i64),
+            self.width() + left + right,
+            self.height() + top + bottom,
+        )
+    }
+
+    /// Crops the field to the `width` by `height` panels with their top-left
+    /// corner at `(x, y)`.
+    ///
+    /// Exits that would cross the new border are removed.
+    ///
+    /// # Panics
+    /// Will panic if any part of the region is out of bounds.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, Exits, PanelKind::*};
+    ///
+    /// let field = field![
+    ///     [Home + EAST, Draw + SOUTH],
+    ///     [Empty, Bonus],
+    /// ];
+    ///
+    /// let cropped = field.crop(1, 0, 1, 2);
+    ///
+    /// assert_eq!(cropped.get((0, 0)).kind, Draw);
+    /// assert!(cropped.get((0, 0)).exits == Exits::SOUTH);
+    /// ```
+    pub fn crop(self, x: usize, y: usize, width: usize, height: usize) -> Field {
+        self.subfield(x, y, width, height)
+    }
+
+    /// Copies the `width` by `height` panels with their top-left corner at
+    /// `(x, y)` to a new field, like [`Field::crop`].
+    ///
+    /// # Panics
+    /// Will panic if any part of the region is out of bounds.
+    pub fn subfield(&self, x: usize, y: usize, width: usize, height: usize) -> Field {
+        let rect = Rect::new(x, y, width, height);
+        assert!(rect.fits(self.width(), self.height()), "region {} is out of bounds", rect);
+
+        self.window(x as i64, y as i64, width, height)
+    }
+
+    /// Creates a copy of the field rotated a quarter turn clockwise.
+    ///
+    /// Exits are rotated along with their panels, so the field stays
+    /// connected the same way.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, Exits, PanelKind::*};
+    ///
+    /// let field = field![[Home + EAST, Draw + WEST]];
+    /// let rotated = field.rotate_cw();
+    ///
+    /// assert_eq!((rotated.width(), rotated.height()), (1, 2));
+    /// assert_eq!(rotated.get((0, 0)).kind, Home);
+    /// assert!(rotated.get((0, 0)).exits == Exits::SOUTH);
+    /// assert!(rotated.get((0, 1)).exits == Exits::NORTH);
+    /// ```
+    pub fn rotate_cw(&self) -> Field {
+        let height = self.height();
+
+        self.remap(height, self.width(), |x, y| (height - 1 - y, x), Direction::clockwise)
+    }
+
+    /// Creates a copy of the field rotated a quarter turn counterclockwise.
+    pub fn rotate_ccw(&self) -> Field {
+        let width = self.width();
+
+        self.remap(self.height(), width, |x, y| (y, width - 1 - x), Direction::counterclockwise)
+    }
+
+    /// Creates a copy of the field rotated a half turn.
+    pub fn rotate_180(&self) -> Field {
+        let (width, height) = (self.width(), self.height());
+
+        self.remap(width, height, |x, y| (width - 1 - x, height - 1 - y), Direction::opposite)
+    }
+
+    /// Creates a copy of the field mirrored left to right.
+    ///
+    /// East and west exits are swapped, so the field stays connected the same
+    /// way.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, Exits, PanelKind::*};
+    ///
+    /// let field = field![[Home + EAST, Draw + WEST + SOUTH]];
+    /// let flipped = field.flip_horizontal();
+    ///
+    /// assert_eq!(flipped.get((0, 0)).kind, Draw);
+    /// assert!(flipped.get((0, 0)).exits == Exits::EAST | Exits::SOUTH);
+    /// assert!(flipped.get((1, 0)).exits == Exits::WEST);
+    /// ```
+    pub fn flip_horizontal(&self) -> Field {
+        let (width, height) = (self.width(), self.height());
+
+        self.remap(width, height, |x, y| (width - 1 - x, y), |dir| match dir {
+            Direction::East | Direction::West => dir.opposite(),
+            _ => dir,
+        })
+    }
+
+    /// Creates a copy of the field mirrored top to bottom.
+    ///
+    /// North and south exits are swapped, so the field stays connected the
+    /// same way.
+    pub fn flip_vertical(&self) -> Field {
+        let (width, height) = (self.width(), self.height());
+
+        self.remap(width, height, |x, y| (x, height - 1 - y), |dir| match dir {
+            Direction::North | Direction::South => dir.opposite(),
+            _ => dir,
+        })
+    }
+
+    /// Creates a copy of the field with every panel moved by `(dx, dy)`.
+    ///
+    /// Without `wrap`, panels moved off the field are lost, and the space
+    /// they leave is filled with empty panels. With `wrap`, they come back on
+    /// the other side, and exits that would cross the seam are removed.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    ///
+    /// let field = field![[Home, Draw, Bonus]];
+    ///
+    /// let shifted = field.shift(1, 0, false);
+    /// assert_eq!(shifted, field![[Empty, Home, Draw]]);
+    ///
+    /// let wrapped = field.shift(1, 0, true);
+    /// assert_eq!(wrapped, field![[Bonus, Home, Draw]]);
+    /// ```
+    pub fn shift(&self, dx: i64, dy: i64, wrap: bool) -> Field {
+        let (width, height) = (self.width(), self.height());
+
+        if !wrap {
+            return self.window(-dx, -dy, width, height);
+        }
+
+        if width == 0 || height == 0 {
+            return self.clone();
+        }
+
+        let wrap_x = |x: usize| (x as i64 + dx).rem_euclid(width as i64) as usize;
+        let wrap_y = |y: usize| (y as i64 + dy).rem_euclid(height as i64) as usize;
+
+        let mut field = self.remap(width, height, |x, y| (wrap_x(x), wrap_y(y)), |dir| dir);
+
+        // remove exits between panels that are no longer next to each other
+        for (x, y) in self.iter() {
+            for dir in Direction::ALL.iter().copied() {
+                let neighbor = match Pos::new(x, y).step(dir) {
+                    Some(pos) if self.contains(pos) => pos,
+                    _ => continue,
+                };
+
+                let moved = Pos::new(wrap_x(x), wrap_y(y));
+                let neighbor = Pos::new(wrap_x(neighbor.x), wrap_y(neighbor.y));
+
+                if moved.step(dir) != Some(neighbor) {
+                    let panel = &mut field[moved];
+
+                    panel.exits = panel.exits.without(dir.to_exits());
+                    panel.exits_backtrack = panel.exits_backtrack.without(dir.to_exits());
+                }
+            }
+        }
+
+        field
+    }
+
+    /// Creates a copy of the field in its canonical orientation.
+    ///
+    /// Of the eight ways to rotate and mirror the field, this picks the same
+    /// one for every field that only differs from this one by orientation,
+    /// so those fields all canonicalize to the same field.
+    ///
+    /// # Examples
+    /// ```
+    /// use citrus_common::{field, PanelKind::*};
+    ///
+    /// let field = field![[Home + EAST, Draw + SOUTH], [Empty, Bonus]];
+    /// let turned = field.rotate_cw().flip_vertical();
+    ///
+    /// assert_eq!(field.canonicalize(), turned.canonicalize());
+    /// assert_eq!(field.canonical_hash(), turned.canonical_hash());
+    /// ```
+    pub fn canonicalize(&self) -> Field {
+        let turns = [self.clone(), self.rotate_cw(), self.rotate_180(), self.rotate_ccw()];
+
+        turns.iter()
+            .flat_map(|turn| vec![turn.flip_horizontal(), turn.clone()])
+            .min_by_key(Field::canonical_key)
+            .unwrap_or_default()
+    }
+
+    /// Hashes the field in its canonical orientation, so fields that only
+    /// differ by orientation hash the same.
+    ///
+    /// Unlike [`Hash`](std::hash::Hash), the hash is the same on every
+    /// platform and in every version of the crate, so it can be stored.
+    pub fn canonical_hash(&self) -> u64 {
+        // 64-bit FNV-1a
+        self.canonicalize()
+            .canonical_key()
+            .iter()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    /// Gets the bytes fields are ordered by when canonicalizing: the size of
+    /// the field, then each panel's kind and exits, row-major.
+    fn canonical_key(&self) -> Vec<u8> {
+        let mut key = Vec::with_capacity(16 + self.width() * self.height() * 2);
+
+        key.extend_from_slice(&(self.width() as u64).to_be_bytes());
+        key.extend_from_slice(&(self.height() as u64).to_be_bytes());
+
+        for (_, _, panel) in self.panels() {
+            key.push(panel.kind.into());
+            key.push(panel.exits_internal());
+        }
+
+        key
+    }
+
+    /// Moves every panel of the field to a new field of the given size.
+    ///
+    /// `pos` maps old positions to new ones, and `dir` maps old directions
+    /// to new ones.
+    fn remap<P, D>(&self, width: usize, height: usize, pos: P, dir: D) -> Field
+    where
+        P: Fn(usize, usize) -> (usize, usize),
+        D: Fn(Direction) -> Direction,
+    {
+        let map_exits = |exits: Exits| {
+            exits.directions()
+                .fold(Exits::none(), |out, d| out | dir(d).to_exits())
+        };
+
+        let mut field = Field::new_vec(vec![Panel::EMPTY; width * height], width, height);
+
+        for (x, y, panel) in self.panels() {
+            field[pos(x, y).into()] = Panel {
+                kind: panel.kind,
+                exits: map_exits(panel.exits),
+                exits_backtrack: map_exits(panel.exits_backtrack),
+            };
+        }
+
+        field
+    }
+
+    /// Copies the `width` by `height` panels at `(x, y)`, which may be partly
+    /// or entirely off the field.
+    ///
+    /// Panels off the field are empty, and exits into panels that are on the
+    /// field but not in the window are removed.
+    fn window(&self, x: i64, y: i64, width: usize, height: usize) -> Field {
+        let in_window = |px: i64, py: i64| {
+            px >= x && py >= y && px < x + width as i64 && py < y + height as i64
+        };
+
+        let data = (0..height as i64)
+            .flat_map(|wy| (0..width as i64).map(move |wx| (x + wx, y + wy)))
+            .map(|(px, py)| match self.source(px, py) {
+                Some(panel) => {
+                    let mut panel = panel.clone();
+
+                    for dir in Direction::ALL.iter().copied() {
+                        let (dx, dy) = dir.delta();
+                        let (nx, ny) = (px + dx, py + dy);
+
+                        if self.source(nx, ny).is_some() && !in_window(nx, ny) {
+                            panel.exits = panel.exits.without(dir.to_exits());
+                            panel.exits_backtrack = panel.exits_backtrack.without(dir.to_exits());
+                        }
+                    }
+
+                    panel
+                },
+                None => Panel::EMPTY,
+            })
+            .collect();
+
+        Field::new_vec(data, width, height)
+    }
+
+    /// Gets a panel by signed coordinates, if it is on the field.
+    fn source(&self, x: i64, y: i64) -> Option<&Panel> {
+        let pos = Pos::ORIGIN.offset(x, y)?;
+
+        if self.contains(pos) {
+            Some(&self[pos])
+        } else {
+            None
+        }
+    }
+}